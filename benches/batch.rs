@@ -0,0 +1,74 @@
+//! Compares `batch::run` with running a `Machine` for each input vector.
+//!
+//! Run with `cargo bench --bench batch`.
+
+use machine::batch;
+use machine::combinatorics::permutations;
+use machine::{loader, Machine};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The number of times each workload runs, of which the fastest counts.
+const ROUNDS: usize = 5;
+
+fn day(n: u32) -> Vec<i64> {
+    loader::load_file(format!("src/day{}/input.txt", n))
+        .unwrap()
+        .remove(0)
+}
+
+fn independently(program: &[i64], inputs: &[Vec<i64>]) -> Vec<Vec<i64>> {
+    inputs
+        .iter()
+        .map(|input| {
+            let mut output = Vec::new();
+            let mut input: VecDeque<i64> = input.iter().copied().collect();
+            Machine::from_memory(program).execute(&mut input, &mut output);
+            output
+        })
+        .collect()
+}
+
+/// Returns the fastest of several runs of a function.
+fn fastest<F: FnMut() -> Vec<Vec<i64>>>(mut f: F) -> (Duration, Vec<Vec<i64>>) {
+    let mut best = None;
+    let mut result = Vec::new();
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        result = f();
+        let elapsed = start.elapsed();
+        best = Some(best.map_or(elapsed, |best: Duration| best.min(elapsed)));
+    }
+
+    (best.unwrap(), result)
+}
+
+fn compare(name: &str, program: &[i64], inputs: &[Vec<i64>]) {
+    let (machines, expected) = fastest(|| independently(program, inputs));
+    let (batched, actual) = fastest(|| batch::run(program, inputs));
+    assert_eq!(actual, expected, "{} gave different outputs", name);
+
+    println!(
+        "{:<24} {:>6} lanes {:>10.3?} machines {:>10.3?} batched {:>6.2}x",
+        name,
+        inputs.len(),
+        machines,
+        batched,
+        machines.as_secs_f64() / batched.as_secs_f64()
+    );
+}
+
+fn main() {
+    let amplifier = day(7);
+    let settings: Vec<Vec<i64>> = permutations((0..5).collect()).collect();
+    let stage: Vec<Vec<i64>> = settings.iter().map(|s| vec![s[0], 0]).collect();
+    compare("day 7 first stage", &amplifier, &stage);
+
+    let diagnostic = day(5);
+    let systems: Vec<Vec<i64>> = (0..1000).map(|n| vec![n % 2 * 4 + 1]).collect();
+    compare("day 5 both systems", &diagnostic, &systems);
+
+    let boost = day(9);
+    compare("day 9 test mode", &boost, &vec![vec![1]; 1000]);
+    compare("day 9 sensor boost", &boost, &vec![vec![2]; 16]);
+}
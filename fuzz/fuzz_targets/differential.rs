@@ -0,0 +1,37 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use machine::fuzz::{self, Case, BUDGET, FIXTURES};
+use std::path::Path;
+use std::sync::Once;
+
+static SILENCE: Once = Once::new();
+
+fuzz_target!(|data: &[u8]| {
+    // libFuzzer's hook aborts on any panic, even one `check` catches, and the machine panics on
+    // invalid programs by design. Only mismatches are failures here.
+    SILENCE.call_once(|| std::panic::set_hook(Box::new(|_| {})));
+
+    let case = Case::from_bytes(data);
+    if let Some(mismatch) = fuzz::check(&case, BUDGET) {
+        let minimized = fuzz::minimize(&case, BUDGET);
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join(FIXTURES);
+        let name = format!("fuzz-{:016x}", fingerprint(&minimized.to_fixture("")));
+        match minimized.save(&fixtures, &name, &mismatch.to_string()) {
+            Ok(path) => eprintln!("Wrote {}", path.display()),
+            Err(e) => eprintln!("Could not write fixture: {}", e),
+        }
+
+        eprintln!("{}", minimized.to_fixture(&mismatch.to_string()));
+        std::process::abort();
+    }
+});
+
+/// Names fixtures by their content, so that the same mismatch found twice is written once.
+fn fingerprint(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,261 @@
+use machine::arcade::{Arcade, Controller, FollowBall, PALETTE};
+use machine::gif::Gif;
+use machine::{loader, Machine};
+use std::error::Error;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "\
+Usage: arcade <program> [options]
+
+Plays an Intcode arcade game in the terminal.
+
+Options:
+  -p, --program <index>    Which program to run if the file holds several. Defaults to 0.
+  -f, --free               Insert quarters, to play rather than only draw the screen.
+  -a, --auto               Let the computer play, keeping the paddle under the ball.
+      --delay <ms>         The delay between frames. Defaults to 100, or 0 with --quiet.
+  -q, --quiet              Draw nothing, only reporting the result. Implies --auto.
+  -r, --record <file>      Record the game as an animated GIF.
+      --every <n>          Record every n-th frame only. Defaults to 1.
+  -h, --help               Print this message.
+
+Keys:
+  a or left arrow  Tilt the joystick left.     d or right arrow  Tilt it right.
+  q  Quit.";
+
+struct Options {
+    program: String,
+    index: usize,
+    free: bool,
+    auto: bool,
+    delay: Option<u64>,
+    quiet: bool,
+    record: Option<String>,
+    every: usize,
+}
+
+/// Holds the joystick as the keys pressed since the last frame say.
+struct Keyboard<'a> {
+    terminal: &'a Terminal,
+    quit: bool,
+}
+
+impl<'a> Controller for Keyboard<'a> {
+    fn joystick(&mut self, _: &Arcade) -> i64 {
+        let mut position = 0;
+        while let Some(key) = self.terminal.key() {
+            match key {
+                b'a' | b'D' => position = -1,
+                b'd' | b'C' => position = 1,
+                b'q' => self.quit = true,
+                _ => {}
+            }
+        }
+
+        position
+    }
+
+    fn quit(&mut self) -> bool {
+        self.quit
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("arcade: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let options = match parse_args(std::env::args().skip(1))? {
+        Some(options) => options,
+        None => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    let programs = loader::load_file(&options.program)
+        .map_err(|e| format!("Could not load {}: {}", options.program, e))?;
+    let program = programs
+        .get(options.index)
+        .ok_or_else(|| format!("{} holds no program {}", options.program, options.index))?;
+
+    let mut arcade = Arcade::new(Machine::from_memory(program));
+    if options.free {
+        arcade = arcade.free_play();
+    }
+
+    let delay = Duration::from_millis(options.delay.unwrap_or(if options.quiet { 0 } else { 100 }));
+    let mut recording: Option<Gif> = None;
+    let mut recorded = 0;
+    let on_frame = |arcade: &Arcade| {
+        if options.record.is_some() {
+            if recorded % options.every == 0 || arcade.is_halted() {
+                record(arcade, &mut recording);
+            }
+            recorded += 1;
+        }
+
+        if !options.quiet {
+            print!("\x1b[H{}", arcade.render());
+            let _ = std::io::stdout().flush();
+            thread::sleep(delay);
+        }
+    };
+
+    let started = Instant::now();
+    let terminal = Terminal::new();
+    if !options.quiet {
+        print!("\x1b[?25l\x1b[2J");
+    }
+
+    let score = if options.auto || options.quiet {
+        arcade.play(&mut FollowBall, on_frame)
+    } else {
+        arcade.play(
+            &mut Keyboard {
+                terminal: &terminal,
+                quit: false,
+            },
+            on_frame,
+        )
+    };
+    drop(terminal);
+
+    println!(
+        "Score: {}, blocks left: {}, frames: {}, time: {:.2?}",
+        score,
+        arcade.blocks(),
+        arcade.frames(),
+        started.elapsed()
+    );
+
+    if let (Some(path), Some(gif)) = (&options.record, recording) {
+        let frames = gif.frames();
+        std::fs::write(path, gif.finish())
+            .map_err(|e| format!("Could not write {}: {}", path, e))?;
+        println!("Recorded {} frames to {}", frames, path);
+    }
+
+    Ok(())
+}
+
+/// Adds a frame to the recording, starting it with the size of the screen if need be.
+fn record(arcade: &Arcade, recording: &mut Option<Gif>) {
+    let (width, height) = match arcade.screen().bounds() {
+        Some((xs, ys)) => (*xs.end() as usize + 1, *ys.end() as usize + 1),
+        None => return,
+    };
+
+    let gif = recording.get_or_insert_with(|| Gif::new(width, height, &PALETTE, 5));
+    let (width, height) = gif.size();
+    gif.add_frame(&arcade.pixels(width, height));
+}
+
+/// The terminal, switched to unbuffered input for as long as this lives. When stdin is not a
+/// terminal, no keys are read.
+struct Terminal {
+    /// The settings to restore, as printed by `stty -g`.
+    saved: Option<String>,
+}
+
+impl Terminal {
+    fn new() -> Self {
+        let saved = stty(&["-g"]).map(|settings| settings.trim().to_string());
+        if saved.is_some() {
+            stty(&["-icanon", "-echo", "min", "0", "time", "0"]);
+        }
+
+        Terminal { saved }
+    }
+
+    /// Returns the next key pressed, if there is one.
+    fn key(&self) -> Option<u8> {
+        self.saved.as_ref()?;
+
+        let mut byte = [0];
+        match std::io::stdin().lock().read(&mut byte) {
+            Ok(1) => Some(byte[0]),
+            _ => None,
+        }
+    }
+
+    /// Puts the terminal back as it was.
+    fn restore(&self) {
+        if let Some(saved) = &self.saved {
+            stty(&[saved]);
+        }
+
+        print!("\x1b[?25h");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+/// Runs `stty` on the terminal behind stdin, returning its output if it succeeded.
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        String::from_utf8(output.stdout).ok()
+    } else {
+        None
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>> {
+    let mut program = None;
+    let mut options = Options {
+        program: String::new(),
+        index: 0,
+        free: false,
+        auto: false,
+        delay: None,
+        quiet: false,
+        record: None,
+        every: 1,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-p" | "--program" => options.index = value(&arg)?.parse()?,
+            "-f" | "--free" => options.free = true,
+            "-a" | "--auto" => options.auto = true,
+            "--delay" => options.delay = Some(value(&arg)?.parse()?),
+            "-q" | "--quiet" => options.quiet = true,
+            "-r" | "--record" => options.record = Some(value(&arg)?),
+            "--every" => options.every = value(&arg)?.parse::<usize>()?.max(1),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("Unknown option {}\n\n{}", arg, USAGE).into())
+            }
+            _ if program.is_none() => program = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg).into()),
+        }
+    }
+
+    options.program = program.ok_or_else(|| format!("Missing program\n\n{}", USAGE))?;
+    Ok(Some(options))
+}
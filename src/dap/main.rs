@@ -0,0 +1,24 @@
+use std::io::{self, BufReader};
+
+const USAGE: &str = "\
+Usage: dap
+
+Runs a Debug Adapter Protocol server on stdin and stdout, for debugging Intcode programs from an
+editor. The launch configuration names the program to debug:
+
+  program       The file holding the program.
+  programIndex  Which program to debug if the file holds several. Defaults to 0.
+  inputs        Input values to queue. More can be entered in the debug console.
+  stopOnEntry   Whether to stop before the first instruction.";
+
+fn main() {
+    if std::env::args().nth(1).is_some() {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if let Err(e) = machine::dap::serve(BufReader::new(io::stdin()), io::stdout()) {
+        eprintln!("dap: {}", e);
+        std::process::exit(1);
+    }
+}
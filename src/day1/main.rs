@@ -20,12 +20,12 @@ fn mass_to_fuel_recursive(mass: u64) -> u64 {
     fuel + mass_to_fuel_recursive(fuel)
 }
 
-fn part_1(mass: &Vec<u64>) {
+fn part_1(mass: &[u64]) {
     let total_fuel: u64 = mass.iter().map(|mass| mass_to_fuel(*mass)).sum();
     println!("Part 1 answer: {}", total_fuel);
 }
 
-fn part_2(mass: &Vec<u64>) {
+fn part_2(mass: &[u64]) {
     let total_fuel: u64 = mass.iter().map(|mass| mass_to_fuel_recursive(*mass)).sum();
     println!("Part 2 answer: {}", total_fuel);
 }
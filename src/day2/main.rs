@@ -1,7 +1,9 @@
 use std::error::Error;
 use std::fs::File;
 use std::io::Read;
-use machine::{Machine, Input, Output};
+use machine::Machine;
+use machine::solver::{Solver, Target};
+use machine::symbolic::{End, Limits, SymbolicMachine};
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
@@ -15,6 +17,7 @@ fn main() -> Result<()> {
 
     part_1(machine.clone());
     part_2(machine.clone());
+    formula(&machine);
     Ok(())
 }
 
@@ -25,21 +28,28 @@ fn part_1(mut machine: Machine) {
     println!("{}", machine.read(0));
 }
 
-fn part_2(mut machine: Machine) {
+fn part_2(machine: Machine) {
     const TARGET: i64 = 19690720;
 
-    for noun in 0..99 {
-        for verb in 0..99 {
-            let mut machine = machine.clone();
-            machine.set_force(1, noun);
-            machine.set_force(2, verb);
-            machine.execute(&mut 0, &mut 0);
-            if machine.read(0) == TARGET {
-                println!("{}", 100 * noun + verb);
-                return;
-            }
-        }
+    let solutions = Solver::new(&machine)
+        .vary(1, 0..=99)
+        .vary(2, 0..=99)
+        .affine(true)
+        .solve(Target::Memory(0, TARGET));
+
+    match solutions.first() {
+        Some(solution) => println!("{}", 100 * solution[0] + solution[1]),
+        None => println!("Could not find combination"),
     }
+}
+
+fn formula(machine: &Machine) {
+    let mut machine = SymbolicMachine::new(machine);
+    machine.set_symbol(1, "noun");
+    machine.set_symbol(2, "verb");
 
-    println!("Could not find combination")
-}
\ No newline at end of file
+    match machine.explore(Limits::default()).as_slice() {
+        [path] if path.end == End::Halted => println!("Address 0 = {}", path.read(0)),
+        _ => println!("Address 0 depends on more than arithmetic"),
+    }
+}
@@ -30,7 +30,7 @@ fn main() -> Result<()> {
 
 fn part1(first: &Line, second: &Line) -> Result<()> {
     let data = first
-        .get_intersections(&second)
+        .get_intersections(second)
         .map(|point| point.distance())
         .min();
 
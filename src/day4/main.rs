@@ -39,7 +39,7 @@ fn matches_requirement(mut number: u32) -> bool {
         }
 
         last_digit = Some(digit);
-        number = number % divisor;
+        number %= divisor;
         divisor /= 10;
     }
 
@@ -74,7 +74,7 @@ fn matches_requirements_part_2(mut number: u32) -> bool {
         }
 
         last_digit = Some(digit);
-        number = number % divisor;
+        number %= divisor;
         divisor /= 10;
     }
 
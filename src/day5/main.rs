@@ -1,7 +1,8 @@
 use std::error::Error;
 use std::fs::File;
 use std::io::Read;
-use machine::{Machine, Input, Output};
+use machine::{Machine, Output};
+use machine::symbolic::{End, Limits, SymbolicMachine};
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
@@ -13,7 +14,8 @@ fn main() -> Result<()> {
 
     let machine = Machine::new(&program)?;
     part_1(machine.clone());
-    part_2(machine);
+    part_2(machine.clone());
+    diagnostics(&machine);
     Ok(())
 }
 
@@ -36,6 +38,28 @@ fn part_2(mut machine: Machine) {
     println!("Code: {}", output.code())
 }
 
+/// Explores the program with an unknown system ID, and prints the code each ID from 1 to 9
+/// leads to, if any.
+fn diagnostics(machine: &Machine) {
+    for path in SymbolicMachine::new(machine).explore(Limits::default()) {
+        if path.end != End::Halted {
+            continue;
+        }
+
+        let id = match path.constraints.as_slice() {
+            [constraint] if !constraint.holds => constraint.condition.linear_terms(),
+            _ => None,
+        };
+
+        if let Some((terms, constant)) = id {
+            if terms.len() == 1 && terms.get("input0") == Some(&1) && (-9..=-1).contains(&constant) {
+                let code = path.outputs.last().map_or(String::from("none"), |c| c.to_string());
+                println!("System ID {}: code {}", -constant, code);
+            }
+        }
+    }
+}
+
 #[derive(Default)]
 struct OutputSource {
     results: Vec<i64>
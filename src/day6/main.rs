@@ -1,7 +1,7 @@
 use std::error::Error;
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet, BinaryHeap};
+use std::collections::{HashMap, BinaryHeap};
 use std::fs::File;
 use std::io::{BufReader, BufRead};
 use std::cmp::Ordering;
@@ -97,8 +97,8 @@ impl OrbitGraph {
 
     pub fn count_orbits(&mut self) -> u32 {
         self.objects
-            .iter()
-            .map(|(_, obj)| obj.borrow_mut().orbit_count())
+            .values()
+            .map(|obj| obj.borrow_mut().orbit_count())
             .sum()
     }
 
@@ -112,7 +112,7 @@ impl OrbitGraph {
 
         impl PartialOrd for State {
             fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-                self.cost.partial_cmp(&other.cost)
+                Some(self.cmp(other))
             }
         }
 
@@ -137,7 +137,7 @@ impl OrbitGraph {
 
             for related in self.objects[&name].borrow_mut().orbits() {
                 let next = State { cost: cost + 1, name: related.borrow().name.clone() };
-                let distance = dists.entry(next.name.clone()).or_insert(std::u32::MAX);
+                let distance = dists.entry(next.name.clone()).or_insert(u32::MAX);
 
                 if next.cost < *distance {
                     *distance = next.cost;
@@ -17,17 +17,15 @@ fn main() -> Result<()> {
 
 mod part1 {
     use machine::{Input, Machine};
-    use crate::permutations;
+    use machine::search::{PhaseSearch, PhaseSpace};
 
     pub fn exe(machine: &Machine) {
-        let perms = permutations(&mut [0, 1, 2, 3, 4], 5, 5);
-
-        let max = perms.iter()
-            .map(|p| (p, run_permutation(machine, p)))
-            .max_by(|first, second| first.1.cmp(&second.1))
-            .unwrap();
-
-        println!("Maximum signal: {} produced by phase permutation: {:?}", max.1, max.0);
+        let results = PhaseSearch::new(PhaseSpace::permutations(0..5))
+            .run(|p| run_permutation(machine, p));
+        match results.first() {
+            Some(max) => println!("Maximum signal: {} produced by phase permutation: {:?}", max.signal, max.setting),
+            None => println!("No phase permutation produced a signal"),
+        }
     }
 
     fn run_permutation(machine: &Machine, perm: &[i64]) -> i64 {
@@ -63,8 +61,8 @@ mod part1 {
 }
 
 mod part2 {
-    use machine::{Machine};
-    use crate::permutations;
+    use machine::Machine;
+    use machine::search::{PhaseSearch, PhaseSpace};
     use std::{
         sync::{
             atomic::{AtomicBool, Ordering},
@@ -75,14 +73,12 @@ mod part2 {
     };
 
     pub fn exe(machine: &Machine) {
-        let perms = permutations(&mut [5, 6, 7, 8, 9], 5, 5);
-
-        let max = perms.iter()
-            .map(|p| (p, run_permutation(machine, p)))
-            .max_by(|first, second| first.1.cmp(&second.1))
-            .unwrap();
-
-        println!("Maximum signal: {} produced by phase permutation: {:?}", max.1, max.0);
+        let results = PhaseSearch::new(PhaseSpace::permutations(5..10))
+            .run(|p| run_permutation(machine, p));
+        match results.first() {
+            Some(max) => println!("Maximum signal: {} produced by phase permutation: {:?}", max.signal, max.setting),
+            None => println!("No phase permutation produced a signal"),
+        }
     }
 
     pub fn run_permutation(machine: &Machine, perm: &[i64]) -> i64 {
@@ -104,9 +100,8 @@ mod part2 {
             txs[i].clone().send(*p).unwrap();
         }
 
-        let mut i: usize = 0;
         let trackers = (0..perm.len()).map(|_| Arc::new(AtomicBool::new(false))).collect::<Vec<Arc<AtomicBool>>>();
-        for mut rx in rxs {
+        for (i, mut rx) in rxs.into_iter().enumerate() {
             let mut m = machine.clone();
             let mut tx = txs[i].clone();
             let tracker = trackers[i].clone();
@@ -114,7 +109,6 @@ mod part2 {
                 m.execute(&mut rx, &mut tx);
                 tracker.store(true, Ordering::SeqCst);
             });
-            i += 1;
         }
 
         // While any threads are running..
@@ -125,7 +119,7 @@ mod part2 {
                     output = result;
 
                     // Fire and forget, the receive pipe may be closed if this is the final output.
-                    my_tx.send(output);
+                    let _ = my_tx.send(output);
                 },
                 Err(e) => match e {
                     TryRecvError::Empty => {},
@@ -141,23 +135,3 @@ mod part2 {
         output
     }
 }
-
-fn permutations(sequence: &mut [i64], size: usize, n: usize) -> Vec<Vec<i64>> {
-    if size == 1 {
-        return vec![sequence.to_vec()];
-    }
-
-    let mut results = vec![];
-
-    for i in 0..size {
-        results.append(&mut permutations(sequence, size - 1, n));
-
-        if size % 2 == 1 {
-            sequence.swap(0, size - 1);
-        } else {
-            sequence.swap(i, size - 1);
-        }
-    }
-
-    results
-}
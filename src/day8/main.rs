@@ -1,6 +1,5 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter};
-use std::str::FromStr;
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
@@ -62,7 +61,6 @@ impl Layer {
     pub fn from_data_stream(data: &str, width: usize, height: usize) -> Vec<Layer> {
         let mut data_stream = data
             .chars()
-            .into_iter()
             .peekable();
 
         let mut layers = Vec::new();
@@ -86,7 +84,7 @@ impl Layer {
         let mut data = Vec::with_capacity(width * height);
 
         fn pixel_at(layers: &[Layer], i: usize) -> char {
-            for layer in layers.as_ref() {
+            for layer in layers {
                 if layer.data[i] != '2' {
                     return layer.data[i];
                 }
@@ -0,0 +1,44 @@
+use machine::fuzz::{self, Mismatch, BUDGET, FIXTURES};
+use std::error::Error;
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Usage: fuzz [cases] [seed]
+///
+/// Generates `cases` random programs starting from `seed`, checks each against the reference
+/// interpreter and writes a minimized fixture for the first mismatch of each kind.
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cases: u64 = args.first().map_or(Ok(10_000), |s| s.parse())?;
+    let seed: u64 = args.get(1).map_or(Ok(0), |s| s.parse())?;
+
+    // Expected panics, such as on unknown opcodes, are part of normal operation here.
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let mut found: Vec<Mismatch> = Vec::new();
+    let mut count = 0;
+    for seed in seed..seed + cases {
+        let case = fuzz::generate(seed);
+        if let Some(mismatch) = fuzz::check(&case, BUDGET) {
+            count += 1;
+            if found.iter().any(|m| m.same_kind(&mismatch)) {
+                continue;
+            }
+
+            let minimized = fuzz::minimize(&case, BUDGET);
+            let comment = format!("seed {}: {}", seed, mismatch);
+            let path = minimized.save(Path::new(FIXTURES), &format!("seed-{}", seed), &comment)?;
+            println!("{} -> {}", comment, path.display());
+            found.push(mismatch);
+        }
+    }
+
+    println!(
+        "Checked {} cases, found {} mismatches of {} kinds",
+        cases,
+        count,
+        found.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use machine::gdb::Stub;
+use machine::{loader, Machine};
+use std::error::Error;
+use std::net::TcpListener;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "\
+Usage: gdbstub <program> [options]
+
+Serves an Intcode program over the GDB remote serial protocol, for a single debugger session.
+Attach with `target remote localhost:<port>`. Cell n is at address 8 * n, and the registers
+ip and base hold addresses in the same form.
+
+Options:
+  -p, --program <index>    Which program to serve if the file holds several. Defaults to 0.
+  -i, --input <values>     Input values, separated by commas. May be repeated. More can be given
+                           from gdb with `monitor input <values>`.
+      --port <port>        The port to listen on, on localhost. Defaults to 1234.
+  -h, --help               Print this message.";
+
+struct Options {
+    program: String,
+    index: usize,
+    inputs: Vec<i64>,
+    port: u16,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("gdbstub: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let options = match parse_args(std::env::args().skip(1))? {
+        Some(options) => options,
+        None => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    let programs = loader::load_file(&options.program)
+        .map_err(|e| format!("Could not load {}: {}", options.program, e))?;
+    let program = programs
+        .get(options.index)
+        .ok_or_else(|| format!("{} holds no program {}", options.program, options.index))?;
+
+    let listener = TcpListener::bind(("127.0.0.1", options.port))?;
+    println!("Listening on {}", listener.local_addr()?);
+    let (stream, peer) = listener.accept()?;
+    println!("Debugger connected from {}", peer);
+
+    let mut stub = Stub::new(Machine::from_memory(program), options.inputs);
+    stub.serve(stream)?;
+    println!("Debugger detached. Output: {:?}", stub.outputs);
+    Ok(())
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>> {
+    let mut program = None;
+    let mut options = Options {
+        program: String::new(),
+        index: 0,
+        inputs: Vec::new(),
+        port: 1234,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-p" | "--program" => options.index = value(&arg)?.parse()?,
+            "-i" | "--input" => {
+                for v in value(&arg)?.split(',').filter(|v| !v.trim().is_empty()) {
+                    options.inputs.push(v.trim().parse()?);
+                }
+            }
+            "--port" => options.port = value(&arg)?.parse()?,
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("Unknown option {}\n\n{}", arg, USAGE).into())
+            }
+            _ if program.is_none() => program = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg).into()),
+        }
+    }
+
+    options.program = program.ok_or_else(|| format!("Missing program\n\n{}", USAGE))?;
+    Ok(Some(options))
+}
@@ -0,0 +1,297 @@
+use machine::{compile, decompile, loader, optimize, panic_message, Input, Machine, Output};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "\
+Usage: intcode <program> [options]
+
+Runs an Intcode program to completion.
+
+Options:
+  -p, --program <index>    Which program to run if the file holds several. Defaults to 0.
+  -i, --input <values>     Input values, separated by commas. May be repeated.
+  -f, --input-file <path>  Read input values from a file, after any given with --input.
+      --stdin              Read input values from stdin once all others are used.
+  -s, --set <addr>=<value> Patch a memory cell before running. May be repeated.
+  -a, --ascii              Read file and stdin input as text, and print output as text.
+  -d, --dump <cells>       Print memory cells after halting, e.g. 0 or 0,4 or 0..10.
+  -e, --emit <c|wat>       Print the program, with any patches, compiled to C or WebAssembly text
+                           instead of running it.
+  -D, --decompile          Print the program, with any patches, as pseudocode instead of running it.
+  -O, --optimize           Print the program, with any patches, optimized instead of running it.
+  -h, --help               Print this message.";
+
+struct Options {
+    program: String,
+    index: usize,
+    inputs: Vec<String>,
+    input_files: Vec<String>,
+    stdin: bool,
+    patches: Vec<(usize, i64)>,
+    ascii: bool,
+    dump: Vec<Range<usize>>,
+    emit: Option<String>,
+    decompile: bool,
+    optimize: bool,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("intcode: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let options = match parse_args(std::env::args().skip(1))? {
+        Some(options) => options,
+        None => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    let programs = loader::load_file(&options.program)
+        .map_err(|e| format!("Could not load {}: {}", options.program, e))?;
+    let program = programs.get(options.index).ok_or_else(|| {
+        format!(
+            "{} holds {} programs, no program {}",
+            options.program,
+            programs.len(),
+            options.index
+        )
+    })?;
+    let mut machine = Machine::from_memory(program);
+
+    for (addr, value) in &options.patches {
+        machine.set_force(*addr, *value);
+    }
+
+    let memory = machine.read_range(0..machine.len());
+    if options.decompile {
+        print!("{}", decompile::decompile(&memory));
+        return Ok(());
+    }
+
+    if options.optimize {
+        println!("{}", optimize::optimize(&memory).source());
+        return Ok(());
+    }
+
+    if let Some(target) = &options.emit {
+        let program = compile::compile(&memory);
+        match target.as_str() {
+            "c" => print!("{}", program.to_c()),
+            _ => print!("{}", program.to_wat()),
+        }
+
+        return Ok(());
+    }
+
+    let mut queued = VecDeque::new();
+    for values in &options.inputs {
+        queued.extend(parse_values(values)?);
+    }
+
+    for path in &options.input_files {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+        if options.ascii {
+            queued.extend(text.chars().map(|c| c as i64));
+        } else {
+            queued.extend(parse_values(&text)?);
+        }
+    }
+
+    let mut input = InputSource {
+        queued,
+        stdin: options.stdin,
+        ascii: options.ascii,
+    };
+    let mut output = OutputSink {
+        ascii: options.ascii,
+    };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        machine.execute(&mut input, &mut output)
+    }));
+    std::io::stdout().flush()?;
+    result.map_err(|payload| format!("The program faulted: {}", panic_message(&*payload)))?;
+
+    for range in &options.dump {
+        for addr in range.clone() {
+            println!("{}: {}", addr, machine.read(addr));
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>> {
+    let mut program = None;
+    let mut options = Options {
+        program: String::new(),
+        index: 0,
+        inputs: Vec::new(),
+        input_files: Vec::new(),
+        stdin: false,
+        patches: Vec::new(),
+        ascii: false,
+        dump: Vec::new(),
+        emit: None,
+        decompile: false,
+        optimize: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-p" | "--program" => options.index = value(&arg)?.parse()?,
+            "-i" | "--input" => options.inputs.push(value(&arg)?),
+            "-f" | "--input-file" => options.input_files.push(value(&arg)?),
+            "--stdin" => options.stdin = true,
+            "-s" | "--set" => options.patches.push(parse_patch(&value(&arg)?)?),
+            "-a" | "--ascii" => options.ascii = true,
+            "-d" | "--dump" => options.dump.extend(parse_cells(&value(&arg)?)?),
+            "-D" | "--decompile" => options.decompile = true,
+            "-O" | "--optimize" => options.optimize = true,
+            "-e" | "--emit" => match value(&arg)?.as_str() {
+                target @ ("c" | "wat") => options.emit = Some(target.to_string()),
+                target => {
+                    return Err(format!("Unknown target {}, expected c or wat", target).into())
+                }
+            },
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("Unknown option {}\n\n{}", arg, USAGE).into())
+            }
+            _ if program.is_none() => program = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg).into()),
+        }
+    }
+
+    options.program = program.ok_or_else(|| format!("Missing program\n\n{}", USAGE))?;
+    Ok(Some(options))
+}
+
+/// Parses comma or whitespace separated values.
+fn parse_values(text: &str) -> Result<Vec<i64>> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse::<i64>()
+                .map_err(|e| format!("Bad value {:?}: {}", v, e).into())
+        })
+        .collect()
+}
+
+/// Parses a patch of the form `addr=value`.
+fn parse_patch(text: &str) -> Result<(usize, i64)> {
+    let (addr, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("Expected <addr>=<value>, found {:?}", text))?;
+
+    Ok((addr.trim().parse()?, value.trim().parse()?))
+}
+
+/// Parses a list of cells such as `0,4,10..20`.
+fn parse_cells(text: &str) -> Result<Vec<Range<usize>>> {
+    text.split(',')
+        .map(|cell| match cell.split_once("..") {
+            Some((start, end)) => {
+                let (start, end): (usize, usize) = (start.trim().parse()?, end.trim().parse()?);
+                if start > end {
+                    return Err(format!("Reversed range {}", cell.trim()).into());
+                }
+
+                Ok(start..end)
+            }
+            None => {
+                let addr: usize = cell.trim().parse()?;
+                let end = addr
+                    .checked_add(1)
+                    .ok_or_else(|| format!("Bad cell {}", addr))?;
+                Ok(addr..end)
+            }
+        })
+        .collect()
+}
+
+/// Supplies queued values first, then values read from stdin as they are needed.
+struct InputSource {
+    queued: VecDeque<i64>,
+    stdin: bool,
+    ascii: bool,
+}
+
+impl InputSource {
+    /// Reads the next line of stdin into the queue. Returns false at the end of stdin.
+    fn read_line(&mut self) -> bool {
+        let mut line = String::new();
+        match std::io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {}
+        }
+
+        if self.ascii {
+            self.queued.extend(line.chars().map(|c| c as i64));
+        } else {
+            match parse_values(&line) {
+                Ok(values) => self.queued.extend(values),
+                Err(e) => fail(&e.to_string()),
+            }
+        }
+
+        true
+    }
+}
+
+impl Input for InputSource {
+    fn get(&mut self) -> i64 {
+        while self.queued.is_empty() {
+            if !self.stdin || !self.read_line() {
+                fail("Program requested input, but there is none left");
+            }
+        }
+
+        self.queued.pop_front().unwrap()
+    }
+}
+
+/// Prints each value as it is produced.
+struct OutputSink {
+    ascii: bool,
+}
+
+impl Output for OutputSink {
+    fn write(&mut self, val: i64) {
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+
+        // Values outside of ASCII, such as a final answer, are printed as numbers.
+        let result = if self.ascii && (0..128).contains(&val) {
+            write!(stdout, "{}", val as u8 as char)
+        } else {
+            writeln!(stdout, "{}", val)
+        };
+
+        if result.and_then(|_| stdout.flush()).is_err() {
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Reports an error from inside the machine, where it cannot be returned.
+fn fail(message: &str) -> ! {
+    let _ = std::io::stdout().flush();
+    eprintln!("intcode: {}", message);
+    std::process::exit(1);
+}
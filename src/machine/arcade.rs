@@ -0,0 +1,256 @@
+//! An arcade cabinet running an Intcode game.
+//!
+//! The game draws by outputting `x, y, tile` triples, where the tile is one of those in `Tile`,
+//! except that a triple at `(-1, 0)` gives the score. Whenever the game reads input it wants the
+//! joystick's position: -1 for left, 0 for neutral and 1 for right. Each read ends a frame, so
+//! the screen is complete whenever a `Controller` is asked for the joystick.
+//!
+//! Address 0 of the game holds the number of quarters inserted, and setting it to 2 plays for
+//! free.
+
+use crate::canvas::{Canvas, Point};
+use crate::{Data, Machine, Status};
+
+/// Where the game reports the score.
+pub const SCORE: Point = (-1, 0);
+
+/// The colors of the tiles, in the order of their ids, for recording.
+pub const PALETTE: [[u8; 3]; 5] = [
+    [0, 0, 0],
+    [128, 128, 128],
+    [200, 80, 40],
+    [60, 120, 220],
+    [255, 255, 255],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Empty,
+    /// Indestructible.
+    Wall,
+    /// Broken by the ball.
+    Block,
+    /// Moved by the joystick.
+    Paddle,
+    Ball,
+}
+
+impl Tile {
+    /// Returns the tile with a given id, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id the game outputs.
+    pub fn from_id(id: Data) -> Option<Tile> {
+        match id {
+            0 => Some(Tile::Empty),
+            1 => Some(Tile::Wall),
+            2 => Some(Tile::Block),
+            3 => Some(Tile::Paddle),
+            4 => Some(Tile::Ball),
+            _ => None,
+        }
+    }
+
+    /// Returns the character the tile is drawn as.
+    pub fn glyph(self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '#',
+            Tile::Block => '=',
+            Tile::Paddle => '_',
+            Tile::Ball => 'o',
+        }
+    }
+}
+
+/// Decides where to hold the joystick each time the game reads it.
+pub trait Controller {
+    /// Returns -1 to tilt the joystick left, 1 to tilt it right, or 0 to leave it neutral.
+    ///
+    /// # Arguments
+    ///
+    /// * `arcade` - The cabinet, with the frame just drawn.
+    fn joystick(&mut self, arcade: &Arcade) -> Data;
+
+    /// Returns whether to stop playing, which is asked each time after `joystick`. Never stops
+    /// by default.
+    fn quit(&mut self) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(&Arcade) -> Data> Controller for F {
+    fn joystick(&mut self, arcade: &Arcade) -> Data {
+        self(arcade)
+    }
+}
+
+/// Keeps the paddle under the ball.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FollowBall;
+
+impl Controller for FollowBall {
+    fn joystick(&mut self, arcade: &Arcade) -> Data {
+        match (arcade.ball(), arcade.paddle()) {
+            (Some((ball, _)), Some((paddle, _))) => (ball - paddle).signum(),
+            _ => 0,
+        }
+    }
+}
+
+/// An arcade cabinet and its screen.
+pub struct Arcade {
+    machine: Machine,
+    screen: Canvas,
+    /// Outputs that do not yet make a whole triple.
+    output: Vec<Data>,
+    ball: Option<Point>,
+    paddle: Option<Point>,
+    frames: usize,
+    halted: bool,
+}
+
+impl Arcade {
+    /// Creates a cabinet running a game.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The machine running the game.
+    pub fn new(machine: Machine) -> Self {
+        Arcade {
+            machine,
+            screen: Canvas::new().special(SCORE),
+            output: Vec::new(),
+            ball: None,
+            paddle: None,
+            frames: 0,
+            halted: false,
+        }
+    }
+
+    /// Inserts enough quarters to play for free.
+    pub fn free_play(mut self) -> Self {
+        self.machine.set_force(0, 2);
+        self
+    }
+
+    /// Plays the game until it halts or the controller quits, and returns the last score.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - What holds the joystick.
+    /// * `on_frame` - Called with each frame once it is drawn, including the last.
+    pub fn play<C: Controller + ?Sized, F: FnMut(&Arcade)>(
+        &mut self,
+        controller: &mut C,
+        mut on_frame: F,
+    ) -> Data {
+        loop {
+            self.draw();
+            on_frame(self);
+            if self.halted {
+                return self.score().unwrap_or(0);
+            }
+
+            let mut position = controller.joystick(self);
+            if controller.quit() {
+                return self.score().unwrap_or(0);
+            }
+            self.machine.step(&mut position, &mut self.output);
+            self.frames += 1;
+        }
+    }
+
+    /// Runs the game until it next reads the joystick or halts, drawing what it outputs.
+    fn draw(&mut self) {
+        self.halted = self.machine.run_until_input(&mut self.output) == Status::Halted;
+
+        let whole = self.output.len() / 3 * 3;
+        for triple in self.output[..whole].chunks_exact(3) {
+            let (point, id) = ((triple[0], triple[1]), triple[2]);
+            if point != SCORE {
+                match Tile::from_id(id) {
+                    Some(Tile::Ball) => self.ball = Some(point),
+                    Some(Tile::Paddle) => self.paddle = Some(point),
+                    Some(_) => {}
+                    None => panic!("Unknown tile: {}", id),
+                }
+            }
+
+            self.screen.draw(point, id);
+        }
+        self.output.drain(..whole);
+    }
+
+    /// Returns the score last shown, if any.
+    pub fn score(&self) -> Option<Data> {
+        self.screen.value(SCORE)
+    }
+
+    /// Returns where the ball was last drawn.
+    pub fn ball(&self) -> Option<Point> {
+        self.ball
+    }
+
+    /// Returns where the paddle was last drawn.
+    pub fn paddle(&self) -> Option<Point> {
+        self.paddle
+    }
+
+    /// Returns the tile on the screen at a point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The point.
+    pub fn tile(&self, point: Point) -> Tile {
+        self.screen.get(point).map_or(Tile::Empty, |id| {
+            Tile::from_id(id).expect("Screen holds only valid tiles")
+        })
+    }
+
+    /// Returns the number of blocks on the screen.
+    pub fn blocks(&self) -> usize {
+        self.screen.count(Tile::Block as Data)
+    }
+
+    /// Returns the number of times the game has read the joystick.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns whether the game has halted.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn screen(&self) -> &Canvas {
+        &self.screen
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// Renders the screen as text, followed by the score.
+    pub fn render(&self) -> String {
+        let mut text = self
+            .screen
+            .render(|id| id.and_then(Tile::from_id).unwrap_or(Tile::Empty).glyph());
+        text.push_str(&format!("Score: {}\n", self.score().unwrap_or(0)));
+        text
+    }
+
+    /// Returns the screen as indices into `PALETTE`, row by row, for recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The number of columns, from `x` = 0.
+    /// * `height` - The number of rows, from `y` = 0.
+    pub fn pixels(&self, width: usize, height: usize) -> Vec<u8> {
+        (0..height as Data)
+            .flat_map(|y| (0..width as Data).map(move |x| (x, y)))
+            .map(|point| self.tile(point) as u8)
+            .collect()
+    }
+}
@@ -0,0 +1,284 @@
+//! Running one program on many inputs at once.
+//!
+//! Each input vector gets a lane, with memory and registers of its own. Lanes whose instruction
+//! pointers coincide form a group, whose instruction is decoded once and then executed for every
+//! lane in a single pass. A group splits when its lanes jump to different places, or when one of
+//! them has rewritten the instruction, and groups that reach the same address merge again.
+//!
+//! Lanes behave as `Machine` does, including its panics, except that memory is unpaged since
+//! lanes are never forked.
+
+use crate::Data;
+use std::mem;
+
+/// A copy of the program with its own state.
+struct Lane {
+    memory: Vec<Data>,
+    base: Data,
+    inputs: Vec<Data>,
+    /// The number of inputs read so far.
+    read: usize,
+    outputs: Vec<Data>,
+}
+
+/// Lanes sharing an instruction pointer.
+struct Group {
+    ip: usize,
+    lanes: Vec<usize>,
+}
+
+/// A batch of lanes running the same program.
+pub struct Batch {
+    lanes: Vec<Lane>,
+    groups: Vec<Group>,
+    passes: usize,
+}
+
+impl Batch {
+    /// Creates a batch with a lane for each input vector, all at the start of the program.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory` - The program.
+    /// * `inputs` - The inputs of each lane, in the order they are read.
+    pub fn new(memory: &[Data], inputs: &[Vec<Data>]) -> Batch {
+        let lanes = inputs
+            .iter()
+            .map(|inputs| Lane {
+                memory: memory.to_vec(),
+                base: 0,
+                inputs: inputs.clone(),
+                read: 0,
+                outputs: Vec::new(),
+            })
+            .collect();
+        let groups = if inputs.is_empty() {
+            Vec::new()
+        } else {
+            vec![Group {
+                ip: 0,
+                lanes: (0..inputs.len()).collect(),
+            }]
+        };
+
+        Batch {
+            lanes,
+            groups,
+            passes: 0,
+        }
+    }
+
+    /// Executes one instruction in every group and returns whether every lane has halted.
+    pub fn step(&mut self) -> bool {
+        let mut pending = mem::take(&mut self.groups);
+        let mut next = Vec::with_capacity(pending.len());
+
+        while let Some(mut group) = pending.pop() {
+            // Lanes that rewrote the instruction go on in a group of their own.
+            let word = self.lanes[group.lanes[0]].read(group.ip);
+            let (same, other): (Vec<usize>, Vec<usize>) = group
+                .lanes
+                .iter()
+                .partition(|&&lane| self.lanes[lane].read(group.ip) == word);
+            if !other.is_empty() {
+                pending.push(Group {
+                    ip: group.ip,
+                    lanes: other,
+                });
+            }
+
+            group.lanes = same;
+            self.passes += 1;
+            self.pass(group, word, &mut next);
+        }
+
+        // Merge groups that arrived at the same address.
+        if next.len() > 1 {
+            next.sort_by_key(|group| group.ip);
+            next.dedup_by(|later, earlier| {
+                let merge = later.ip == earlier.ip;
+                if merge {
+                    earlier.lanes.append(&mut later.lanes);
+                }
+                merge
+            });
+        }
+
+        self.groups = next;
+        self.groups.is_empty()
+    }
+
+    /// Runs every lane to completion.
+    pub fn execute(&mut self) {
+        while !self.step() {}
+    }
+
+    /// Returns the number of groups of lanes still running.
+    pub fn groups(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns the number of times an instruction was executed for a group, which is the number
+    /// of steps a single lane would have taken if the lanes never split.
+    pub fn passes(&self) -> usize {
+        self.passes
+    }
+
+    /// Returns the output of each lane so far, in the order of their inputs.
+    pub fn outputs(&self) -> Vec<&[Data]> {
+        self.lanes
+            .iter()
+            .map(|lane| lane.outputs.as_slice())
+            .collect()
+    }
+
+    /// Returns the output of each lane, in the order of their inputs.
+    pub fn into_outputs(self) -> Vec<Vec<Data>> {
+        self.lanes.into_iter().map(|lane| lane.outputs).collect()
+    }
+
+    /// Executes an instruction for each lane of a group, adding the groups that result to
+    /// `next`. Lanes that halt join no group.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group, whose lanes all hold `word` at its instruction pointer.
+    /// * `word` - The value holding the opcode and modes.
+    /// * `next` - The groups for the next step.
+    fn pass(&mut self, group: Group, word: Data, next: &mut Vec<Group>) {
+        let ip = group.ip;
+        let modes = [word / 100 % 10, word / 1000 % 10, word / 10000 % 10];
+
+        let length = match word % 100 {
+            op @ (1 | 2 | 7 | 8) => {
+                let f: fn(Data, Data) -> Data = match op {
+                    1 => |a, b| a.checked_add(b).expect("Arithmetic overflow"),
+                    2 => |a, b| a.checked_mul(b).expect("Arithmetic overflow"),
+                    7 => |a, b| (a < b) as Data,
+                    _ => |a, b| (a == b) as Data,
+                };
+                for &l in &group.lanes {
+                    let lane = &mut self.lanes[l];
+                    let value = f(lane.param(ip, 1, modes[0]), lane.param(ip, 2, modes[1]));
+                    lane.write(ip, 3, modes[2], value);
+                }
+                4
+            }
+            3 => {
+                for &l in &group.lanes {
+                    let lane = &mut self.lanes[l];
+                    let value = *lane.inputs.get(lane.read).expect("Input queue is empty");
+                    lane.read += 1;
+                    lane.write(ip, 1, modes[0], value);
+                }
+                2
+            }
+            4 => {
+                for &l in &group.lanes {
+                    let lane = &mut self.lanes[l];
+                    let value = lane.param(ip, 1, modes[0]);
+                    lane.outputs.push(value);
+                }
+                2
+            }
+            op @ (5 | 6) => {
+                let mut targets: Vec<(usize, usize)> = group
+                    .lanes
+                    .iter()
+                    .map(|&l| {
+                        let lane = &self.lanes[l];
+                        let condition = lane.param(ip, 1, modes[0]) != 0;
+                        if condition == (op == 5) {
+                            (lane.param(ip, 2, modes[1]) as usize, l)
+                        } else {
+                            (ip + 3, l)
+                        }
+                    })
+                    .collect();
+
+                // Split the lanes by where they went.
+                targets.sort_unstable();
+                for (target, lane) in targets {
+                    match next.last_mut() {
+                        Some(group) if group.ip == target => group.lanes.push(lane),
+                        _ => next.push(Group {
+                            ip: target,
+                            lanes: vec![lane],
+                        }),
+                    }
+                }
+                return;
+            }
+            9 => {
+                for &l in &group.lanes {
+                    let lane = &mut self.lanes[l];
+                    let adjustment = lane.param(ip, 1, modes[0]);
+                    lane.base = lane
+                        .base
+                        .checked_add(adjustment)
+                        .expect("Arithmetic overflow");
+                }
+                2
+            }
+            99 => return,
+            op => panic!("Unknown op code: {}", op),
+        };
+
+        next.push(Group {
+            ip: ip + length,
+            lanes: group.lanes,
+        });
+    }
+}
+
+impl Lane {
+    fn read(&self, addr: usize) -> Data {
+        self.memory.get(addr).copied().unwrap_or(0)
+    }
+
+    /// Gets the data associated with a parameter.
+    fn param(&self, ip: usize, offset: usize, mode: Data) -> Data {
+        self.read(self.address(ip, offset, mode))
+    }
+
+    /// Writes a value using a parameter.
+    fn write(&mut self, ip: usize, offset: usize, mode: Data, value: Data) {
+        let addr = self.address(ip, offset, mode);
+        if self.memory.len() <= addr {
+            self.memory.resize(addr + 1, 0);
+        }
+
+        self.memory[addr] = value;
+    }
+
+    /// Returns the address a parameter refers to.
+    fn address(&self, ip: usize, offset: usize, mode: Data) -> usize {
+        let address = match mode {
+            0 => self.read(ip + offset),
+            1 => (ip + offset) as Data,
+            2 => self
+                .read(ip + offset)
+                .checked_add(self.base)
+                .expect("Arithmetic overflow"),
+            _ => panic!("Unknown mode encountered: {}", mode),
+        };
+
+        if address < 0 {
+            panic!("Negative address: {}", address);
+        }
+
+        address as usize
+    }
+}
+
+/// Runs a program once for each input vector and returns the output of each run.
+///
+/// # Arguments
+///
+/// * `memory` - The program.
+/// * `inputs` - The inputs of each run.
+pub fn run(memory: &[Data], inputs: &[Vec<Data>]) -> Vec<Vec<Data>> {
+    let mut batch = Batch::new(memory, inputs);
+    batch.execute();
+    batch.into_outputs()
+}
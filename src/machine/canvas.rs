@@ -0,0 +1,148 @@
+//! A sparse grid of tiles, as drawn by programs that output coordinates.
+//!
+//! Only tiles that were drawn are stored, so coordinates may be anywhere, including negative.
+//! Some programs also report values other than tiles through coordinates off the grid, as the
+//! arcade cabinet does its score at `(-1, 0)`. Marking such coordinates as special keeps their
+//! values out of the picture.
+//!
+//! A canvas renders to text, for the terminal, or to a binary PPM image. Both cover the bounds
+//! of the tiles drawn, with `y` increasing downwards.
+
+use crate::Data;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+
+/// A position on the canvas, as `(x, y)`.
+pub type Point = (Data, Data);
+
+/// A sparse grid of tiles.
+#[derive(Clone, Debug, Default)]
+pub struct Canvas {
+    tiles: HashMap<Point, Data>,
+    special: HashSet<Point>,
+    values: HashMap<Point, Data>,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        Canvas::default()
+    }
+
+    /// Marks a coordinate as special, so values drawn there are kept apart from the tiles.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The coordinate.
+    pub fn special(mut self, point: Point) -> Self {
+        self.special.insert(point);
+        self
+    }
+
+    /// Draws a tile, or records a value if the coordinate is special.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - Where to draw.
+    /// * `tile` - The tile.
+    pub fn draw(&mut self, point: Point, tile: Data) {
+        if self.special.contains(&point) {
+            self.values.insert(point, tile);
+        } else {
+            self.tiles.insert(point, tile);
+        }
+    }
+
+    /// Returns the tile at a coordinate, if one was drawn there.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The coordinate.
+    pub fn get(&self, point: Point) -> Option<Data> {
+        self.tiles.get(&point).copied()
+    }
+
+    /// Returns the last value drawn at a special coordinate.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The special coordinate.
+    pub fn value(&self, point: Point) -> Option<Data> {
+        self.values.get(&point).copied()
+    }
+
+    /// Iterates over the tiles drawn, in no particular order.
+    pub fn tiles(&self) -> impl Iterator<Item = (Point, Data)> + '_ {
+        self.tiles.iter().map(|(point, tile)| (*point, *tile))
+    }
+
+    /// Returns the number of tiles drawn, each coordinate counting once.
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns whether no tile was drawn.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Returns the number of tiles of a given kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `tile` - The kind of tile.
+    pub fn count(&self, tile: Data) -> usize {
+        self.tiles.values().filter(|t| **t == tile).count()
+    }
+
+    /// Returns the ranges of `x` and `y` covering every tile, or `None` if there are none.
+    pub fn bounds(&self) -> Option<(RangeInclusive<Data>, RangeInclusive<Data>)> {
+        let xs = self.tiles.keys().map(|(x, _)| *x);
+        let ys = self.tiles.keys().map(|(_, y)| *y);
+        Some((xs.clone().min()?..=xs.max()?, ys.clone().min()?..=ys.max()?))
+    }
+
+    /// Renders the canvas as lines of text.
+    ///
+    /// # Arguments
+    ///
+    /// * `glyph` - The character for a tile, or for a coordinate where none was drawn.
+    pub fn render<F: Fn(Option<Data>) -> char>(&self, glyph: F) -> String {
+        let mut text = String::new();
+        if let Some((xs, ys)) = self.bounds() {
+            for y in ys {
+                text.extend(xs.clone().map(|x| glyph(self.get((x, y)))));
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+
+    /// Renders the canvas as a binary PPM image, with each tile a square of pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The RGB color of a tile, or of a coordinate where none was drawn.
+    /// * `scale` - The width and height of a tile, in pixels.
+    pub fn ppm<F: Fn(Option<Data>) -> [u8; 3]>(&self, color: F, scale: usize) -> Vec<u8> {
+        let (xs, ys) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return b"P6\n0 0\n255\n".to_vec(),
+        };
+        let width = xs.clone().count() * scale;
+        let height = ys.clone().count() * scale;
+
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for y in ys {
+            let row: Vec<u8> = xs
+                .clone()
+                .flat_map(|x| color(self.get((x, y))).repeat(scale))
+                .collect();
+            for _ in 0..scale {
+                image.extend_from_slice(&row);
+            }
+        }
+
+        image
+    }
+}
@@ -0,0 +1,330 @@
+/// Returns an iterator over every ordering of the given items, generated by Heap's algorithm.
+///
+/// Each permutation differs from the previous one by a single swap, so the order is not
+/// lexicographic. Use `k_permutations` if a stable, rankable order is required.
+///
+/// # Arguments
+///
+/// * `items` - The items to permute.
+pub fn permutations<T: Clone>(items: Vec<T>) -> Permutations<T> {
+    let n = items.len();
+    Permutations {
+        items,
+        counters: vec![0; n],
+        i: 0,
+        started: false,
+    }
+}
+
+/// Returns an iterator over every ordered selection of `k` distinct items, in lexicographic
+/// order of the items' positions.
+///
+/// # Arguments
+///
+/// * `items` - The items to select from.
+/// * `k` - The number of items in each selection.
+pub fn k_permutations<T: Clone>(items: Vec<T>, k: usize) -> KPermutations<T> {
+    let n = items.len();
+    KPermutations {
+        items,
+        k,
+        indices: (0..n).collect(),
+        cycles: (0..k.min(n)).map(|i| n - i).collect(),
+        state: if k <= n { State::Start } else { State::Done },
+    }
+}
+
+/// Returns an iterator over every unordered selection of `k` distinct items, in lexicographic
+/// order of the items' positions.
+///
+/// # Arguments
+///
+/// * `items` - The items to select from.
+/// * `k` - The number of items in each selection.
+pub fn combinations<T: Clone>(items: Vec<T>, k: usize) -> Combinations<T> {
+    let n = items.len();
+    Combinations {
+        items,
+        indices: (0..k).collect(),
+        state: if k <= n { State::Start } else { State::Done },
+    }
+}
+
+/// Returns an iterator over every sequence which takes one item from each pool in turn. The last
+/// pool varies fastest.
+///
+/// # Arguments
+///
+/// * `pools` - The pools to draw each position of the sequence from.
+pub fn product<T: Clone>(pools: Vec<Vec<T>>) -> Product<T> {
+    let state = if pools.iter().any(|p| p.is_empty()) {
+        State::Done
+    } else {
+        State::Start
+    };
+
+    Product {
+        indices: vec![0; pools.len()],
+        pools,
+        state,
+    }
+}
+
+/// Returns the number of ordered selections of `k` distinct items out of `n`, or `None` if it
+/// does not fit in a `usize`.
+pub fn permutation_count(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+
+    (n - k + 1..=n).try_fold(1usize, |acc, v| acc.checked_mul(v))
+}
+
+/// Returns the number of unordered selections of `k` distinct items out of `n`, or `None` if it
+/// does not fit in a `usize`.
+pub fn combination_count(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+
+    let k = k.min(n - k);
+    (0..k).try_fold(1usize, |acc, i| Some(acc.checked_mul(n - i)? / (i + 1)))
+}
+
+/// Returns the number of sequences in the product of pools with the given sizes, or `None` if it
+/// does not fit in a `usize`.
+pub fn product_count<I: IntoIterator<Item = usize>>(sizes: I) -> Option<usize> {
+    sizes
+        .into_iter()
+        .try_fold(1usize, |acc, v| acc.checked_mul(v))
+}
+
+/// Returns the `k`-permutation of the items with the given rank in the order produced by
+/// `k_permutations`, without generating any of the ones before it.
+///
+/// # Arguments
+///
+/// * `items` - The items to select from.
+/// * `k` - The number of items in the selection.
+/// * `rank` - The rank of the selection.
+pub fn nth_permutation<T: Clone>(items: &[T], k: usize, mut rank: usize) -> Option<Vec<T>> {
+    let n = items.len();
+    if rank >= permutation_count(n, k)? {
+        return None;
+    }
+
+    let mut pool = items.to_vec();
+    let mut result = Vec::with_capacity(k);
+
+    for i in 0..k {
+        let block = permutation_count(n - i - 1, k - i - 1)?;
+        result.push(pool.remove(rank / block));
+        rank %= block;
+    }
+
+    Some(result)
+}
+
+/// Returns the sequence with the given rank in the order produced by `product`, without
+/// generating any of the ones before it.
+///
+/// # Arguments
+///
+/// * `pools` - The pools to draw each position of the sequence from.
+/// * `rank` - The rank of the sequence.
+pub fn nth_product<T: Clone, P: AsRef<[T]>>(pools: &[P], mut rank: usize) -> Option<Vec<T>> {
+    if rank >= product_count(pools.iter().map(|p| p.as_ref().len()))? {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(pools.len());
+    for pool in pools.iter().rev() {
+        let pool = pool.as_ref();
+        result.push(pool[rank % pool.len()].clone());
+        rank /= pool.len();
+    }
+
+    result.reverse();
+    Some(result)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Start,
+    Running,
+    Done,
+}
+
+/// Iterator returned by `permutations`.
+pub struct Permutations<T> {
+    items: Vec<T>,
+    counters: Vec<usize>,
+    i: usize,
+    started: bool,
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            return Some(self.items.clone());
+        }
+
+        while self.i < self.items.len() {
+            if self.counters[self.i] < self.i {
+                if self.i.is_multiple_of(2) {
+                    self.items.swap(0, self.i);
+                } else {
+                    self.items.swap(self.counters[self.i], self.i);
+                }
+
+                self.counters[self.i] += 1;
+                self.i = 0;
+                return Some(self.items.clone());
+            }
+
+            self.counters[self.i] = 0;
+            self.i += 1;
+        }
+
+        None
+    }
+}
+
+/// Iterator returned by `k_permutations`.
+pub struct KPermutations<T> {
+    items: Vec<T>,
+    k: usize,
+    indices: Vec<usize>,
+    cycles: Vec<usize>,
+    state: State,
+}
+
+impl<T: Clone> KPermutations<T> {
+    fn current(&self) -> Vec<T> {
+        self.indices[..self.k]
+            .iter()
+            .map(|i| self.items[*i].clone())
+            .collect()
+    }
+}
+
+impl<T: Clone> Iterator for KPermutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.state {
+            State::Done => return None,
+            State::Start => {
+                self.state = State::Running;
+                return Some(self.current());
+            }
+            State::Running => {}
+        }
+
+        let n = self.items.len();
+        for i in (0..self.k).rev() {
+            self.cycles[i] -= 1;
+            if self.cycles[i] == 0 {
+                self.indices[i..].rotate_left(1);
+                self.cycles[i] = n - i;
+            } else {
+                let j = n - self.cycles[i];
+                self.indices.swap(i, j);
+                return Some(self.current());
+            }
+        }
+
+        self.state = State::Done;
+        None
+    }
+}
+
+/// Iterator returned by `combinations`.
+pub struct Combinations<T> {
+    items: Vec<T>,
+    indices: Vec<usize>,
+    state: State,
+}
+
+impl<T: Clone> Iterator for Combinations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.state {
+            State::Done => return None,
+            State::Start => self.state = State::Running,
+            State::Running => {
+                let n = self.items.len();
+                let k = self.indices.len();
+
+                // Find the right-most index which can still be advanced.
+                match (0..k).rev().find(|i| self.indices[*i] != i + n - k) {
+                    Some(i) => {
+                        self.indices[i] += 1;
+                        for j in i + 1..k {
+                            self.indices[j] = self.indices[j - 1] + 1;
+                        }
+                    }
+                    None => {
+                        self.state = State::Done;
+                        return None;
+                    }
+                }
+            }
+        }
+
+        Some(
+            self.indices
+                .iter()
+                .map(|i| self.items[*i].clone())
+                .collect(),
+        )
+    }
+}
+
+/// Iterator returned by `product`.
+pub struct Product<T> {
+    pools: Vec<Vec<T>>,
+    indices: Vec<usize>,
+    state: State,
+}
+
+impl<T: Clone> Iterator for Product<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.state {
+            State::Done => return None,
+            State::Start => self.state = State::Running,
+            State::Running => {
+                // Advance the indices like an odometer, last pool first.
+                let mut i = self.pools.len();
+                loop {
+                    if i == 0 {
+                        self.state = State::Done;
+                        return None;
+                    }
+
+                    i -= 1;
+                    self.indices[i] += 1;
+                    if self.indices[i] < self.pools[i].len() {
+                        break;
+                    }
+
+                    self.indices[i] = 0;
+                }
+            }
+        }
+
+        Some(
+            self.indices
+                .iter()
+                .zip(self.pools.iter())
+                .map(|(i, pool)| pool[*i].clone())
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,630 @@
+//! Compilation of Intcode programs to C and to WebAssembly text.
+//!
+//! Every address of a program that holds a valid instruction is compiled, with its opcode and
+//! modes fixed. Parameters are still read from memory when the instruction runs, since programs
+//! commonly rewrite them, but an instruction whose opcode or modes have changed stops the program
+//! with `Trap::Modified`, and one that only becomes valid at run time stops it with
+//! `Trap::Uncompiled`. So programs which rewrite their instructions, such as Day 5's, cannot be
+//! compiled.
+//!
+//! Compiled programs differ from `Machine` in two more ways: arithmetic wraps on overflow, and
+//! memory is limited to `Program::cells` cells.
+//!
+//! The generated code reads input and writes output through functions provided by the host,
+//! which play the parts of `Input::get` and `Output::write`. In C these are `intcode_input` and
+//! `intcode_output`; in WebAssembly they are the imports `intcode.input` and `intcode.output`.
+//! Both forms return 0 when the program halts, or the code of a `Trap`.
+
+use crate::disassemble;
+use crate::{Data, Input, Machine, Output};
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter, Write};
+
+/// The least number of memory cells a compiled program has.
+pub const CELLS: usize = 1 << 16;
+
+/// The size of a WebAssembly page, in bytes.
+const PAGE: usize = 1 << 16;
+
+/// The cell a parameter of a decoded instruction refers to, given the address of the parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// The parameter itself, in immediate mode.
+    Cell(usize),
+    /// The cell whose address the parameter holds.
+    Position(usize),
+    /// The cell at the relative base plus the value the parameter holds.
+    Relative(usize),
+}
+
+/// A decoded instruction, with its parameters in order. The last parameter of instructions that
+/// write is the destination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add(Operand, Operand, Operand),
+    Multiply(Operand, Operand, Operand),
+    Input(Operand),
+    Output(Operand),
+    JumpIfTrue(Operand, Operand),
+    JumpIfFalse(Operand, Operand),
+    Less(Operand, Operand, Operand),
+    Equal(Operand, Operand, Operand),
+    AdjustBase(Operand),
+    Halt,
+}
+
+/// An instruction at a fixed address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub addr: usize,
+    /// The value holding the opcode and modes.
+    pub word: Data,
+    pub op: Op,
+}
+
+impl Instruction {
+    /// Returns the address of the instruction that follows.
+    pub fn next(&self) -> usize {
+        let params = match self.op {
+            Op::Add(..) | Op::Multiply(..) | Op::Less(..) | Op::Equal(..) => 3,
+            Op::JumpIfTrue(..) | Op::JumpIfFalse(..) => 2,
+            Op::Input(_) | Op::Output(_) | Op::AdjustBase(_) => 1,
+            Op::Halt => 0,
+        };
+
+        self.addr + 1 + params
+    }
+}
+
+/// The reason a compiled program stopped without halting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trap {
+    /// The opcode or modes of the instruction at this address changed after it was compiled.
+    Modified(usize),
+    /// An address was negative or past the last cell.
+    BadAddress(Data),
+    /// Execution reached an address which held no valid instruction when the program was
+    /// compiled.
+    Uncompiled(Data),
+    /// The instruction budget ran out. Only the local interpreter stops for this.
+    Budget,
+}
+
+impl Trap {
+    /// Returns the code the generated programs return for this trap.
+    pub fn code(&self) -> i32 {
+        match self {
+            Trap::Modified(_) => 1,
+            Trap::BadAddress(_) => 2,
+            Trap::Uncompiled(_) => 3,
+            Trap::Budget => 4,
+        }
+    }
+}
+
+impl Display for Trap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trap::Modified(addr) => write!(f, "instruction at {} was modified", addr),
+            Trap::BadAddress(addr) => write!(f, "bad address {}", addr),
+            Trap::Uncompiled(addr) => write!(f, "no instruction was compiled at {}", addr),
+            Trap::Budget => write!(f, "instruction budget ran out"),
+        }
+    }
+}
+
+/// A compiled program.
+#[derive(Clone, Debug)]
+pub struct Program {
+    /// The initial memory.
+    pub memory: Vec<Data>,
+    /// The compiled instructions, in address order.
+    pub instructions: Vec<Instruction>,
+    /// The number of memory cells.
+    pub cells: usize,
+}
+
+/// Decodes the instruction at an address, if it holds a valid one.
+///
+/// # Arguments
+///
+/// * `memory` - The program.
+/// * `addr` - The address of the instruction.
+pub fn decode(memory: &[Data], addr: usize) -> Option<Instruction> {
+    let word = memory.get(addr).copied().unwrap_or(0);
+    let width = disassemble::width(word)?;
+
+    // Reject instructions with negative words or stray digits beyond their modes.
+    if word < 0 || word / 10_i64.pow(width as u32 + 1) != 0 {
+        return None;
+    }
+
+    let mut params = Vec::with_capacity(3);
+    for n in 1..width {
+        let slot = addr + n;
+        params.push(match word / 10_i64.pow(n as u32 + 1) % 10 {
+            0 => Operand::Position(slot),
+            1 => Operand::Cell(slot),
+            2 => Operand::Relative(slot),
+            _ => return None,
+        });
+    }
+
+    let op = match (word % 100, params.as_slice()) {
+        (1, &[a, b, c]) => Op::Add(a, b, c),
+        (2, &[a, b, c]) => Op::Multiply(a, b, c),
+        (3, &[a]) => Op::Input(a),
+        (4, &[a]) => Op::Output(a),
+        (5, &[a, b]) => Op::JumpIfTrue(a, b),
+        (6, &[a, b]) => Op::JumpIfFalse(a, b),
+        (7, &[a, b, c]) => Op::Less(a, b, c),
+        (8, &[a, b, c]) => Op::Equal(a, b, c),
+        (9, &[a]) => Op::AdjustBase(a),
+        _ => Op::Halt,
+    };
+
+    Some(Instruction { addr, word, op })
+}
+
+/// Compiles a program.
+///
+/// # Arguments
+///
+/// * `memory` - The program, including any patches made before it runs.
+pub fn compile(memory: &[Data]) -> Program {
+    Program {
+        memory: memory.to_vec(),
+        instructions: (0..memory.len())
+            .filter_map(|addr| decode(memory, addr))
+            .collect(),
+        // Leave room for the parameters of an instruction at the very end.
+        cells: CELLS.max(memory.len() + 3),
+    }
+}
+
+impl Program {
+    /// Interprets the decoded instructions with the semantics the generated code is meant to
+    /// have, and returns the final memory if the program halts. This is a model of the generated
+    /// code, not a run of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input data source.
+    /// * `output` - The output data sink.
+    /// * `budget` - The maximum number of instructions, counting the final halt as one.
+    pub fn run<I: Input, O: Output>(
+        &self,
+        input: &mut I,
+        output: &mut O,
+        budget: usize,
+    ) -> Result<Vec<Data>, Trap> {
+        let mut index = vec![None; self.memory.len()];
+        for (i, instruction) in self.instructions.iter().enumerate() {
+            index[instruction.addr] = Some(i);
+        }
+
+        let mut memory = self.memory.clone();
+        memory.resize(self.cells, 0);
+        let mut ip = 0;
+        let mut base: Data = 0;
+
+        for _ in 0..budget {
+            let instruction = match index.get(ip).copied().flatten() {
+                Some(i) => &self.instructions[i],
+                None => return Err(Trap::Uncompiled(ip as Data)),
+            };
+
+            if memory[ip] != instruction.word {
+                return Err(Trap::Modified(ip));
+            }
+
+            ip = instruction.next();
+            match instruction.op {
+                Op::Add(a, b, c)
+                | Op::Multiply(a, b, c)
+                | Op::Less(a, b, c)
+                | Op::Equal(a, b, c) => {
+                    let (a, b) = (self.load(&memory, base, a)?, self.load(&memory, base, b)?);
+                    let value = match instruction.op {
+                        Op::Add(..) => a.wrapping_add(b),
+                        Op::Multiply(..) => a.wrapping_mul(b),
+                        Op::Less(..) => (a < b) as Data,
+                        _ => (a == b) as Data,
+                    };
+                    let addr = self.address(&memory, base, c)?;
+                    memory[addr] = value;
+                }
+                Op::Input(a) => {
+                    let addr = self.address(&memory, base, a)?;
+                    memory[addr] = input.get();
+                }
+                Op::Output(a) => output.write(self.load(&memory, base, a)?),
+                Op::JumpIfTrue(a, b) | Op::JumpIfFalse(a, b) => {
+                    let condition = self.load(&memory, base, a)?;
+                    let jump = (condition != 0) == matches!(instruction.op, Op::JumpIfTrue(..));
+                    if jump {
+                        let target = self.load(&memory, base, b)?;
+                        if target < 0 {
+                            return Err(Trap::Uncompiled(target));
+                        }
+                        ip = target as usize;
+                    }
+                }
+                Op::AdjustBase(a) => base = base.wrapping_add(self.load(&memory, base, a)?),
+                Op::Halt => return Ok(memory),
+            }
+        }
+
+        Err(Trap::Budget)
+    }
+
+    /// Returns the address of the cell an operand refers to.
+    fn address(&self, memory: &[Data], base: Data, operand: Operand) -> Result<usize, Trap> {
+        let addr = match operand {
+            Operand::Cell(slot) => return Ok(slot),
+            Operand::Position(slot) => memory[slot],
+            Operand::Relative(slot) => base.wrapping_add(memory[slot]),
+        };
+
+        if addr < 0 || addr as usize >= self.cells {
+            Err(Trap::BadAddress(addr))
+        } else {
+            Ok(addr as usize)
+        }
+    }
+
+    /// Returns the value of the cell an operand refers to.
+    fn load(&self, memory: &[Data], base: Data, operand: Operand) -> Result<Data, Trap> {
+        Ok(memory[self.address(memory, base, operand)?])
+    }
+
+    /// Generates C source for the program, which defines `int intcode_run(void)`.
+    pub fn to_c(&self) -> String {
+        let mut c = String::new();
+        c.push_str(
+            "/* Generated from an Intcode program. The host provides intcode_input and\n \
+             * intcode_output, and calls intcode_run, which returns 0 once the program halts. */\n\
+             #include <stdint.h>\n\n",
+        );
+        let _ = writeln!(c, "#define CELLS {}\n", self.cells);
+        c.push_str(
+            "enum {\n    INTCODE_HALTED,\n    INTCODE_MODIFIED,\n    INTCODE_BAD_ADDRESS,\n    \
+             INTCODE_UNCOMPILED\n};\n\n\
+             int64_t intcode_input(void);\nvoid intcode_output(int64_t value);\n\n",
+        );
+
+        c.push_str("static int64_t mem[CELLS] = {");
+        for (i, value) in self.memory.iter().enumerate() {
+            c.push_str(if i % 12 == 0 { "\n    " } else { " " });
+            let _ = write!(c, "{},", c_literal(*value));
+        }
+        c.push_str("\n};\n\n");
+
+        c.push_str(
+            "/* Arithmetic wraps, rather than overflowing. */\n\
+             static inline int64_t add(int64_t a, int64_t b)\n{\n    \
+             return (int64_t)((uint64_t)a + (uint64_t)b);\n}\n\n\
+             static inline int64_t mul(int64_t a, int64_t b)\n{\n    \
+             return (int64_t)((uint64_t)a * (uint64_t)b);\n}\n\n\
+             int intcode_run(void)\n{\n    \
+             int64_t ip = 0, base = 0, r1 = 0, r2 = 0, r3 = 0;\n\n    \
+             /* Not every program uses every variable. */\n    \
+             (void)base, (void)r1, (void)r2, (void)r3;\n\n    \
+             for (;;) {\n        switch (ip) {\n",
+        );
+
+        for instruction in &self.instructions {
+            let _ = writeln!(c, "        case {}:", instruction.addr);
+            for line in self.c_instruction(instruction) {
+                let _ = writeln!(c, "            {}", line);
+            }
+        }
+
+        c.push_str(
+            "        default:\n            return INTCODE_UNCOMPILED;\n        }\n    }\n}\n",
+        );
+        c
+    }
+
+    fn c_instruction(&self, instruction: &Instruction) -> Vec<String> {
+        let mut lines = vec![format!(
+            "if (mem[{}] != {}) return INTCODE_MODIFIED;",
+            instruction.addr, instruction.word
+        )];
+
+        match instruction.op {
+            Op::Add(a, b, c) | Op::Multiply(a, b, c) | Op::Less(a, b, c) | Op::Equal(a, b, c) => {
+                let a = self.c_cell(&mut lines, 1, a);
+                let b = self.c_cell(&mut lines, 2, b);
+                let c = self.c_cell(&mut lines, 3, c);
+                let value = match instruction.op {
+                    Op::Add(..) => format!("add({}, {})", a, b),
+                    Op::Multiply(..) => format!("mul({}, {})", a, b),
+                    Op::Less(..) => format!("{} < {}", a, b),
+                    _ => format!("{} == {}", a, b),
+                };
+                lines.push(format!("{} = {};", c, value));
+            }
+            Op::Input(a) => {
+                let a = self.c_cell(&mut lines, 1, a);
+                lines.push(format!("{} = intcode_input();", a));
+            }
+            Op::Output(a) => {
+                let a = self.c_cell(&mut lines, 1, a);
+                lines.push(format!("intcode_output({});", a));
+            }
+            Op::JumpIfTrue(a, b) | Op::JumpIfFalse(a, b) => {
+                let a = self.c_cell(&mut lines, 1, a);
+                let test = if let Op::JumpIfTrue(..) = instruction.op {
+                    "!="
+                } else {
+                    "=="
+                };
+
+                // The target is only read when the jump is taken.
+                let mut taken = Vec::new();
+                let b = self.c_cell(&mut taken, 2, b);
+                taken.push(format!("ip = {};", b));
+                taken.push(String::from("break;"));
+
+                lines.push(format!("if ({} {} 0) {{", a, test));
+                lines.extend(taken.into_iter().map(|line| format!("    {}", line)));
+                lines.push(String::from("}"));
+            }
+            Op::AdjustBase(a) => {
+                let a = self.c_cell(&mut lines, 1, a);
+                lines.push(format!("base = add(base, {});", a));
+            }
+            Op::Halt => {
+                lines.push(String::from("return INTCODE_HALTED;"));
+                return lines;
+            }
+        }
+
+        lines.push(format!("ip = {};", instruction.next()));
+        lines.push(String::from("break;"));
+        lines
+    }
+
+    /// Returns an lvalue for the cell an operand refers to, adding any statements it needs first.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The statements to add to.
+    /// * `n` - The number of the parameter, which picks the variable to hold its address.
+    /// * `operand` - The operand.
+    fn c_cell(&self, lines: &mut Vec<String>, n: usize, operand: Operand) -> String {
+        let addr = match operand {
+            Operand::Cell(slot) => return format!("mem[{}]", slot),
+            Operand::Position(slot) => format!("mem[{}]", slot),
+            Operand::Relative(slot) => format!("add(base, mem[{}])", slot),
+        };
+
+        lines.push(format!("r{} = {};", n, addr));
+        lines.push(format!(
+            "if (r{} < 0 || r{} >= CELLS) return INTCODE_BAD_ADDRESS;",
+            n, n
+        ));
+        format!("mem[r{}]", n)
+    }
+
+    /// Generates a WebAssembly text module for the program, which exports `run` and `memory`.
+    pub fn to_wat(&self) -> String {
+        let mut wat = String::new();
+        wat.push_str(
+            ";; Generated from an Intcode program. The host provides intcode.input and\n\
+             ;; intcode.output, and calls run, which returns 0 once the program halts.\n\
+             (module\n  \
+             (import \"intcode\" \"input\" (func $input (result i64)))\n  \
+             (import \"intcode\" \"output\" (func $output (param i64)))\n",
+        );
+        let _ = writeln!(
+            wat,
+            "  (memory (export \"memory\") {})",
+            (self.cells * 8).div_ceil(PAGE)
+        );
+
+        wat.push_str("  (data (i32.const 0) \"");
+        for value in &self.memory {
+            for byte in value.to_le_bytes() {
+                let _ = write!(wat, "\\{:02x}", byte);
+            }
+        }
+        wat.push_str("\")\n");
+
+        wat.push_str(
+            "  (func (export \"run\") (result i32)\n    \
+             (local $ip i64) (local $base i64) (local $r1 i64) (local $r2 i64) (local $r3 i64)\n    \
+             (block $uncompiled\n    (block $bad_address\n    (block $modified\n    \
+             (loop $dispatch\n",
+        );
+        let _ = writeln!(
+            wat,
+            "      (br_if $uncompiled (i64.ge_u (local.get $ip) (i64.const {})))",
+            self.memory.len()
+        );
+
+        // Each instruction's code follows the end of its block, so that the table can branch
+        // to it. The innermost block belongs to the first instruction.
+        wat.push_str("      (block $default\n");
+        for instruction in self.instructions.iter().rev() {
+            let _ = writeln!(wat, "      (block $i{}", instruction.addr);
+        }
+
+        let mut labels = vec![String::from("$default"); self.memory.len()];
+        for instruction in &self.instructions {
+            labels[instruction.addr] = format!("$i{}", instruction.addr);
+        }
+        let _ = writeln!(
+            wat,
+            "        (br_table {} $default (i32.wrap_i64 (local.get $ip))))",
+            labels.join(" ")
+        );
+
+        for instruction in &self.instructions {
+            let _ = writeln!(wat, "      ;; {}", instruction.addr);
+            for line in self.wat_instruction(instruction) {
+                let _ = writeln!(wat, "      {}", line);
+            }
+            wat.push_str("      )\n");
+        }
+
+        wat.push_str(
+            "      (br $uncompiled))\n    \
+             unreachable)\n    \
+             (return (i32.const 1)))\n    \
+             (return (i32.const 2)))\n    \
+             (i32.const 3)))\n",
+        );
+        wat
+    }
+
+    fn wat_instruction(&self, instruction: &Instruction) -> Vec<String> {
+        let mut lines = vec![format!(
+            "(br_if $modified (i64.ne (i64.load (i32.const {})) (i64.const {})))",
+            instruction.addr * 8,
+            instruction.word
+        )];
+
+        match instruction.op {
+            Op::Add(a, b, c) | Op::Multiply(a, b, c) | Op::Less(a, b, c) | Op::Equal(a, b, c) => {
+                let a = self.wat_read(&mut lines, 1, a);
+                let b = self.wat_read(&mut lines, 2, b);
+                let c = self.wat_cell(&mut lines, 3, c);
+                let value = match instruction.op {
+                    Op::Add(..) => format!("(i64.add {} {})", a, b),
+                    Op::Multiply(..) => format!("(i64.mul {} {})", a, b),
+                    Op::Less(..) => format!("(i64.extend_i32_u (i64.lt_s {} {}))", a, b),
+                    _ => format!("(i64.extend_i32_u (i64.eq {} {}))", a, b),
+                };
+                lines.push(format!("(i64.store {} {})", c, value));
+            }
+            Op::Input(a) => {
+                let a = self.wat_cell(&mut lines, 1, a);
+                lines.push(format!("(i64.store {} (call $input))", a));
+            }
+            Op::Output(a) => {
+                let a = self.wat_read(&mut lines, 1, a);
+                lines.push(format!("(call $output {})", a));
+            }
+            Op::JumpIfTrue(a, b) | Op::JumpIfFalse(a, b) => {
+                let a = self.wat_read(&mut lines, 1, a);
+                let test = if let Op::JumpIfTrue(..) = instruction.op {
+                    "i64.ne"
+                } else {
+                    "i64.eq"
+                };
+
+                // The target is only read when the jump is taken.
+                let mut taken = Vec::new();
+                let b = self.wat_read(&mut taken, 2, b);
+                taken.push(format!("(local.set $ip {})", b));
+                taken.push(String::from("(br $dispatch)"));
+
+                lines.push(format!("(if ({} {} (i64.const 0)) (then", test, a));
+                lines.extend(taken.into_iter().map(|line| format!("  {}", line)));
+                lines.push(String::from("))"));
+            }
+            Op::AdjustBase(a) => {
+                let a = self.wat_read(&mut lines, 1, a);
+                lines.push(format!(
+                    "(local.set $base (i64.add (local.get $base) {}))",
+                    a
+                ));
+            }
+            Op::Halt => {
+                lines.push(String::from("(return (i32.const 0))"));
+                return lines;
+            }
+        }
+
+        lines.push(format!(
+            "(local.set $ip (i64.const {}))",
+            instruction.next()
+        ));
+        lines.push(String::from("(br $dispatch)"));
+        lines
+    }
+
+    /// Returns an expression for the value of the cell an operand refers to, adding any code it
+    /// needs first.
+    fn wat_read(&self, lines: &mut Vec<String>, n: usize, operand: Operand) -> String {
+        format!("(i64.load {})", self.wat_cell(lines, n, operand))
+    }
+
+    /// Returns an expression for the byte address of the cell an operand refers to, adding any
+    /// code it needs first.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The code to add to.
+    /// * `n` - The number of the parameter, which picks the local to hold its address.
+    /// * `operand` - The operand.
+    fn wat_cell(&self, lines: &mut Vec<String>, n: usize, operand: Operand) -> String {
+        let addr = match operand {
+            Operand::Cell(slot) => return format!("(i32.const {})", slot * 8),
+            Operand::Position(slot) => format!("(i64.load (i32.const {}))", slot * 8),
+            Operand::Relative(slot) => format!(
+                "(i64.add (local.get $base) (i64.load (i32.const {})))",
+                slot * 8
+            ),
+        };
+
+        // Negative addresses are caught too, as they compare as large unsigned values.
+        lines.push(format!("(local.set $r{} {})", n, addr));
+        lines.push(format!(
+            "(br_if $bad_address (i64.ge_u (local.get $r{}) (i64.const {})))",
+            n, self.cells
+        ));
+        format!("(i32.wrap_i64 (i64.shl (local.get $r{}) (i64.const 3)))", n)
+    }
+}
+
+/// Formats a value as a C literal. The most negative value has no literal of its own.
+fn c_literal(value: Data) -> String {
+    if value == Data::MIN {
+        String::from("INT64_MIN")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Runs a program under both `Machine` and `Program::run`, and returns the output if they agree
+/// on it and on the final memory. This checks the decoding and the intended semantics, but not
+/// the generated C or WebAssembly, which need a C compiler or a WebAssembly runtime to run.
+///
+/// # Arguments
+///
+/// * `memory` - The program, including any patches made before it runs.
+/// * `inputs` - The values the program reads, which must be enough for it to halt.
+/// * `budget` - The maximum number of instructions either may execute.
+pub fn verify(memory: &[Data], inputs: &[Data], budget: usize) -> Result<Vec<Data>, String> {
+    let mut compiled_output = Vec::new();
+    let compiled = compile(memory)
+        .run(
+            &mut inputs.iter().copied().collect::<VecDeque<Data>>(),
+            &mut compiled_output,
+            budget,
+        )
+        .map_err(|trap| format!("Compiled program stopped: {}", trap))?;
+
+    let mut machine = Machine::from_memory(memory);
+    let mut output = Vec::new();
+    let mut input = inputs.iter().copied().collect::<VecDeque<Data>>();
+    if !machine.execute_for(&mut input, &mut output, budget) {
+        return Err(String::from("Machine did not halt within the budget"));
+    }
+
+    if output != compiled_output {
+        return Err(format!(
+            "Outputs differ: machine {:?}, compiled {:?}",
+            output, compiled_output
+        ));
+    }
+
+    let len = machine.len().max(compiled.len());
+    match (0..len).find(|&addr| machine.read(addr) != compiled.get(addr).copied().unwrap_or(0)) {
+        Some(addr) => Err(format!("Memory differs at {}", addr)),
+        None => Ok(output),
+    }
+}
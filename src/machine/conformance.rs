@@ -0,0 +1,430 @@
+//! A table-driven conformance suite for Intcode interpreters.
+//!
+//! The cases are the example programs from the Day 2, 5 and 9 puzzle statements, along with edge
+//! cases of parameter modes and memory access. Any interpreter can be checked against them by
+//! implementing `Backend`.
+
+use crate::{Data, Input, Machine, Output};
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::channel;
+
+/// An interpreter that can be checked against the suite.
+pub trait Backend {
+    /// Runs a program until it halts and returns its final memory. Cells past the end of the
+    /// returned memory are expected to hold 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - The initial memory.
+    /// * `input` - The input data source.
+    /// * `output` - The output data sink.
+    fn run(&self, program: &[Data], input: &mut dyn Input, output: &mut dyn Output) -> Vec<Data>;
+}
+
+/// Runs programs on `Machine`.
+pub struct MachineBackend;
+
+impl Backend for MachineBackend {
+    fn run(&self, program: &[Data], input: &mut dyn Input, output: &mut dyn Output) -> Vec<Data> {
+        let mut machine = Machine::from_memory(program);
+        machine.execute(&mut &mut *input, &mut &mut *output);
+        (0..machine.memory.len())
+            .map(|addr| machine.read(addr))
+            .collect()
+    }
+}
+
+/// A program run with the given inputs, and what it should produce.
+pub struct Case {
+    pub name: &'static str,
+    pub program: &'static str,
+    pub inputs: &'static [Data],
+    pub outputs: &'static [Data],
+    /// Cells of the final memory that should hold the given values.
+    pub memory: &'static [(usize, Data)],
+}
+
+/// The ways in which input is supplied to and output is collected from a backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Io {
+    /// Input from a `VecDeque`, output to a `Vec`.
+    Queue,
+    /// Input from a `Receiver`, output to a `Sender`.
+    Channel,
+    /// Input from a single `i64`, output to a single `i64` which keeps the last value.
+    Scalar,
+}
+
+/// The configurations every case is run under.
+pub const IO: [Io; 3] = [Io::Queue, Io::Channel, Io::Scalar];
+
+/// A case which did not behave as expected.
+#[derive(Debug)]
+pub struct Failure {
+    pub case: &'static str,
+    pub io: Io,
+    pub message: String,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:?}): {}", self.case, self.io, self.message)
+    }
+}
+
+/// Runs every case under every IO configuration and returns the failures.
+///
+/// # Arguments
+///
+/// * `backend` - The interpreter to check.
+pub fn run<B: Backend>(backend: &B) -> Vec<Failure> {
+    let mut failures = Vec::new();
+
+    for case in CASES {
+        for io in IO.iter() {
+            if let Err(message) = run_case(backend, case, *io) {
+                failures.push(Failure {
+                    case: case.name,
+                    io: *io,
+                    message,
+                });
+            }
+        }
+    }
+
+    failures
+}
+
+/// Runs a single case under a single IO configuration.
+///
+/// # Arguments
+///
+/// * `backend` - The interpreter to check.
+/// * `case` - The case to run.
+/// * `io` - How to supply input and collect output.
+pub fn run_case<B: Backend>(backend: &B, case: &Case, io: Io) -> Result<(), String> {
+    let program = case
+        .program
+        .split(',')
+        .map(|v| v.parse::<Data>())
+        .collect::<Result<Vec<Data>, _>>()
+        .map_err(|e| format!("Bad program: {}", e))?;
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| match io {
+        Io::Queue => {
+            let mut input: VecDeque<Data> = case.inputs.iter().copied().collect();
+            let mut output = Vec::new();
+            let memory = backend.run(&program, &mut input, &mut output);
+            (memory, output)
+        }
+        Io::Channel => {
+            let (input_tx, mut input) = channel();
+            let (mut output, output_rx) = channel();
+            for value in case.inputs {
+                input_tx.send(*value).unwrap();
+            }
+
+            drop(input_tx);
+            let memory = backend.run(&program, &mut input, &mut output);
+            drop(output);
+            (memory, output_rx.iter().collect())
+        }
+        Io::Scalar => {
+            let mut input = case.inputs.first().copied().unwrap_or(0);
+            let mut output: Data = 0;
+            let memory = backend.run(&program, &mut input, &mut output);
+            (memory, vec![output])
+        }
+    }));
+
+    let (memory, outputs) = result.map_err(|_| String::from("Backend panicked"))?;
+
+    let expected = match io {
+        Io::Scalar => vec![case.outputs.last().copied().unwrap_or(0)],
+        _ => case.outputs.to_vec(),
+    };
+
+    if outputs != expected {
+        return Err(format!("Expected output {:?}, got {:?}", expected, outputs));
+    }
+
+    for (addr, value) in case.memory {
+        let actual = memory.get(*addr).copied().unwrap_or(0);
+        if actual != *value {
+            return Err(format!("Expected {} at {}, got {}", value, addr, actual));
+        }
+    }
+
+    Ok(())
+}
+
+const DAY_5_JUMPS: &str = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
+const DAY_5_JUMPS_IMMEDIATE: &str = "3,3,1105,-1,9,1101,0,0,12,4,12,99,1";
+const DAY_5_COMPARE_8: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,\
+                               0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,\
+                               20,4,20,1105,1,46,98,99";
+const DAY_9_QUINE: &str = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+
+/// Every case in the suite.
+pub const CASES: &[Case] = &[
+    // Day 2: addition and multiplication in position mode.
+    Case {
+        name: "day2 example",
+        program: "1,9,10,3,2,3,11,0,99,30,40,50",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(0, 3500), (3, 70)],
+    },
+    Case {
+        name: "day2 add",
+        program: "1,0,0,0,99",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(0, 2)],
+    },
+    Case {
+        name: "day2 multiply",
+        program: "2,3,0,3,99",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(3, 6)],
+    },
+    Case {
+        name: "day2 multiply after program",
+        program: "2,4,4,5,99,0",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(5, 9801)],
+    },
+    Case {
+        name: "day2 self-modifying",
+        program: "1,1,1,4,99,5,6,0,99",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(0, 30), (4, 2)],
+    },
+    // Day 5: input, output, immediate mode, comparisons and jumps.
+    Case {
+        name: "day5 echo",
+        program: "3,0,4,0,99",
+        inputs: &[42],
+        outputs: &[42],
+        memory: &[(0, 42)],
+    },
+    Case {
+        name: "day5 immediate multiply",
+        program: "1002,4,3,4,33",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(4, 99)],
+    },
+    Case {
+        name: "day5 negative immediate",
+        program: "1101,100,-1,4,0",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(4, 99)],
+    },
+    Case {
+        name: "day5 equal position, equal",
+        program: "3,9,8,9,10,9,4,9,99,-1,8",
+        inputs: &[8],
+        outputs: &[1],
+        memory: &[],
+    },
+    Case {
+        name: "day5 equal position, not equal",
+        program: "3,9,8,9,10,9,4,9,99,-1,8",
+        inputs: &[7],
+        outputs: &[0],
+        memory: &[],
+    },
+    Case {
+        name: "day5 less position, less",
+        program: "3,9,7,9,10,9,4,9,99,-1,8",
+        inputs: &[5],
+        outputs: &[1],
+        memory: &[],
+    },
+    Case {
+        name: "day5 less position, not less",
+        program: "3,9,7,9,10,9,4,9,99,-1,8",
+        inputs: &[8],
+        outputs: &[0],
+        memory: &[],
+    },
+    Case {
+        name: "day5 equal immediate, equal",
+        program: "3,3,1108,-1,8,3,4,3,99",
+        inputs: &[8],
+        outputs: &[1],
+        memory: &[],
+    },
+    Case {
+        name: "day5 equal immediate, not equal",
+        program: "3,3,1108,-1,8,3,4,3,99",
+        inputs: &[9],
+        outputs: &[0],
+        memory: &[],
+    },
+    Case {
+        name: "day5 less immediate, less",
+        program: "3,3,1107,-1,8,3,4,3,99",
+        inputs: &[-3],
+        outputs: &[1],
+        memory: &[],
+    },
+    Case {
+        name: "day5 less immediate, not less",
+        program: "3,3,1107,-1,8,3,4,3,99",
+        inputs: &[8],
+        outputs: &[0],
+        memory: &[],
+    },
+    Case {
+        name: "day5 jump position, zero",
+        program: DAY_5_JUMPS,
+        inputs: &[0],
+        outputs: &[0],
+        memory: &[],
+    },
+    Case {
+        name: "day5 jump position, non-zero",
+        program: DAY_5_JUMPS,
+        inputs: &[5],
+        outputs: &[1],
+        memory: &[],
+    },
+    Case {
+        name: "day5 jump immediate, zero",
+        program: DAY_5_JUMPS_IMMEDIATE,
+        inputs: &[0],
+        outputs: &[0],
+        memory: &[],
+    },
+    Case {
+        name: "day5 jump immediate, non-zero",
+        program: DAY_5_JUMPS_IMMEDIATE,
+        inputs: &[3],
+        outputs: &[1],
+        memory: &[],
+    },
+    Case {
+        name: "day5 compare to 8, below",
+        program: DAY_5_COMPARE_8,
+        inputs: &[7],
+        outputs: &[999],
+        memory: &[],
+    },
+    Case {
+        name: "day5 compare to 8, equal",
+        program: DAY_5_COMPARE_8,
+        inputs: &[8],
+        outputs: &[1000],
+        memory: &[],
+    },
+    Case {
+        name: "day5 compare to 8, above",
+        program: DAY_5_COMPARE_8,
+        inputs: &[9],
+        outputs: &[1001],
+        memory: &[],
+    },
+    // Day 9: relative mode and large numbers.
+    Case {
+        name: "day9 quine",
+        program: DAY_9_QUINE,
+        inputs: &[],
+        outputs: &[
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ],
+        memory: &[(100, 16), (101, 1)],
+    },
+    Case {
+        name: "day9 large product",
+        program: "1102,34915192,34915192,7,4,7,99,0",
+        inputs: &[],
+        outputs: &[1_219_070_632_396_864],
+        memory: &[],
+    },
+    Case {
+        name: "day9 large immediate",
+        program: "104,1125899906842624,99",
+        inputs: &[],
+        outputs: &[1_125_899_906_842_624],
+        memory: &[],
+    },
+    // Edge cases of modes and memory access.
+    Case {
+        name: "relative input write",
+        program: "109,10,203,5,204,5,99",
+        inputs: &[42],
+        outputs: &[42],
+        memory: &[(15, 42)],
+    },
+    Case {
+        name: "relative add write",
+        program: "109,5,21101,3,4,0,99",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(5, 7)],
+    },
+    Case {
+        name: "negative relative base adjustment",
+        program: "109,10,109,-4,204,0,99",
+        inputs: &[],
+        outputs: &[99],
+        memory: &[],
+    },
+    Case {
+        name: "write past end of memory",
+        program: "1101,1,2,100,4,100,99",
+        inputs: &[],
+        outputs: &[3],
+        memory: &[(100, 3)],
+    },
+    Case {
+        name: "input past end of memory",
+        program: "3,1000,4,1000,99",
+        inputs: &[-7],
+        outputs: &[-7],
+        memory: &[(1000, -7)],
+    },
+    Case {
+        name: "read uninitialized position",
+        program: "4,1000,99",
+        inputs: &[],
+        outputs: &[0],
+        memory: &[(1000, 0)],
+    },
+    Case {
+        name: "read uninitialized relative",
+        program: "109,200,204,300,99",
+        inputs: &[],
+        outputs: &[0],
+        memory: &[],
+    },
+    Case {
+        name: "read between writes",
+        program: "1101,5,5,50,4,49,4,50,99",
+        inputs: &[],
+        outputs: &[0, 10],
+        memory: &[(49, 0), (50, 10)],
+    },
+    Case {
+        name: "mode digits beyond parameters are ignored",
+        program: "11104,7,99",
+        inputs: &[],
+        outputs: &[7],
+        memory: &[],
+    },
+    Case {
+        name: "halt stops execution",
+        program: "99,1101,1,1,0",
+        inputs: &[],
+        outputs: &[],
+        memory: &[(0, 99)],
+    },
+];
@@ -0,0 +1,737 @@
+//! A server for the Debug Adapter Protocol, so editors can debug Intcode programs.
+//!
+//! The adapter launches a program from a file, given in the launch configuration as `program`,
+//! along with optional `programIndex`, `inputs` and `stopOnEntry` settings. Editors are shown a
+//! disassembly of the program as its source, and breakpoints may be set on its lines or, as
+//! instruction breakpoints, on addresses. The disassembly is of the program as loaded, so does
+//! not follow code the program rewrites.
+//!
+//! The variables view holds the registers, the program's IO, and memory in windows of
+//! `WINDOW` cells. Output is printed to the debug console, and values typed into the console
+//! are queued as input. A program that reads input when none is queued stops until some is.
+
+use crate::compile::decode;
+use crate::disassemble::{self, Line};
+use crate::json::{object, Value};
+use crate::{loader, panic_message, Data, Machine};
+use std::collections::{BTreeSet, VecDeque};
+use std::convert::TryFrom;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+/// The number of memory cells in each window of the variables view.
+pub const WINDOW: usize = 16;
+
+/// The number of instructions executed between checks for a pause while running.
+const CHECK: usize = 4096;
+
+/// The number of recent outputs shown in the variables view.
+const RECENT: usize = 16;
+
+/// The variables references of the scopes. Memory windows follow from `WINDOWS`.
+const REGISTERS: i64 = 1;
+const IO: i64 = 2;
+const MEMORY: i64 = 3;
+const WINDOWS: i64 = 1000;
+
+/// The only thread.
+const THREAD: i64 = 1;
+
+/// The longest message read, in bytes. Longer ones are skipped.
+const MAX_MESSAGE: usize = 1 << 20;
+
+/// The reference of the disassembly source.
+const SOURCE: i64 = 1;
+
+/// Serves a single debugging session, reading requests from `input` and writing responses and
+/// events to `output`, until the client disconnects or closes its end.
+///
+/// # Arguments
+///
+/// * `input` - The client's requests.
+/// * `output` - Where to send responses and events.
+pub fn serve<R: BufRead + Send + 'static, W: Write>(input: R, output: W) -> io::Result<()> {
+    // Requests are read on their own thread so that a running program can be paused.
+    let (sender, requests) = channel();
+    thread::spawn(move || {
+        let mut input = input;
+        while let Ok(Some(message)) = read_message(&mut input) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut adapter = Adapter {
+        output,
+        seq: 0,
+        requests,
+        deferred: VecDeque::new(),
+        lines_start_at_1: true,
+        debuggee: None,
+        configured: false,
+        stop_on_entry: false,
+        started: false,
+        line_breakpoints: BTreeSet::new(),
+        instruction_breakpoints: BTreeSet::new(),
+    };
+
+    adapter.run()
+}
+
+/// Reads a message, or returns `None` at the end of the input.
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+    loop {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if input.read_line(&mut header)? == 0 {
+                return Ok(None);
+            }
+
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("Content-Length") {
+                    length = value.trim().parse::<usize>().ok();
+                }
+            }
+        }
+
+        let length = match length {
+            Some(length) if length <= MAX_MESSAGE => length,
+            Some(length) => {
+                io::copy(
+                    &mut io::Read::take(&mut *input, length as u64),
+                    &mut io::sink(),
+                )?;
+                continue;
+            }
+            None => continue,
+        };
+        let mut body = vec![0; length];
+        input.read_exact(&mut body)?;
+        if let Ok(message) = Value::parse(&String::from_utf8_lossy(&body)) {
+            return Ok(Some(message));
+        }
+    }
+}
+
+/// The program being debugged.
+struct Debuggee {
+    machine: Machine,
+    /// The name of the program's file, which names the disassembly.
+    name: String,
+    listing: Vec<Line>,
+    inputs: VecDeque<Data>,
+    outputs: Vec<Data>,
+    halted: bool,
+}
+
+impl Debuggee {
+    /// Returns the index of the line holding an address.
+    fn line(&self, addr: usize) -> Option<usize> {
+        let index = self.listing.partition_point(|line| line.addr <= addr);
+        index
+            .checked_sub(1)
+            .filter(|&i| addr < self.listing[i].addr + self.listing[i].len)
+    }
+
+    /// Describes the next instruction as it is now.
+    fn next(&self) -> String {
+        let ip = self.machine.ip();
+        let memory = self.machine.read_range(0..ip + 4);
+        match decode(&memory, ip) {
+            Some(instruction) => disassemble::format(&memory, &instruction),
+            None => format!("data {}", memory[ip]),
+        }
+    }
+}
+
+/// Why execution stopped.
+enum Stop {
+    Entry,
+    Step,
+    Breakpoint,
+    Pause,
+    NeedsInput,
+    Fault(String),
+    Halted,
+}
+
+struct Adapter<W> {
+    output: W,
+    seq: i64,
+    requests: Receiver<Value>,
+    /// Requests that arrived while the program was running.
+    deferred: VecDeque<Value>,
+    lines_start_at_1: bool,
+    debuggee: Option<Debuggee>,
+    configured: bool,
+    stop_on_entry: bool,
+    started: bool,
+    line_breakpoints: BTreeSet<usize>,
+    instruction_breakpoints: BTreeSet<usize>,
+}
+
+impl<W: Write> Adapter<W> {
+    fn run(&mut self) -> io::Result<()> {
+        loop {
+            let request = match self.deferred.pop_front() {
+                Some(request) => request,
+                None => match self.requests.recv() {
+                    Ok(request) => request,
+                    Err(_) => return Ok(()),
+                },
+            };
+
+            if request.get("type").as_str() != Some("request") {
+                continue;
+            }
+            if !self.handle(&request)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn send(&mut self, mut message: Vec<(&str, Value)>) -> io::Result<()> {
+        self.seq += 1;
+        message.insert(0, ("seq", self.seq.into()));
+        let body = object(message).to_string();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.output.flush()
+    }
+
+    fn respond(&mut self, request: &Value, result: Result<Value, String>) -> io::Result<()> {
+        let mut message = vec![
+            ("type", "response".into()),
+            ("request_seq", request.get("seq").clone()),
+            ("command", request.get("command").clone()),
+            ("success", result.is_ok().into()),
+        ];
+        match result {
+            Ok(Value::Null) => {}
+            Ok(body) => message.push(("body", body)),
+            Err(error) => message.push(("message", error.into())),
+        }
+
+        self.send(message)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        let mut message = vec![("type", "event".into()), ("event", event.into())];
+        if body != Value::Null {
+            message.push(("body", body));
+        }
+
+        self.send(message)
+    }
+
+    fn console(&mut self, category: &str, text: String) -> io::Result<()> {
+        let body = object(vec![("category", category.into()), ("output", text.into())]);
+        self.event("output", body)
+    }
+
+    /// Handles a request and returns whether the session goes on.
+    fn handle(&mut self, request: &Value) -> io::Result<bool> {
+        let args = request.get("arguments");
+        let command = request.get("command").as_str().unwrap_or_default();
+
+        let result = match command {
+            "initialize" => {
+                self.lines_start_at_1 = args.get("linesStartAt1").as_bool().unwrap_or(true);
+                self.respond(request, Ok(capabilities()))?;
+                self.event("initialized", Value::Null)?;
+                return Ok(true);
+            }
+            "launch" => self.launch(args),
+            "setBreakpoints" => Ok(self.set_breakpoints(args)),
+            "setInstructionBreakpoints" => Ok(self.set_instruction_breakpoints(args)),
+            "setExceptionBreakpoints" => Ok(object(vec![("breakpoints", vec![].into())])),
+            "configurationDone" => {
+                self.configured = true;
+                self.respond(request, Ok(Value::Null))?;
+                return self.start().map(|_| true);
+            }
+            "threads" => Ok(object(vec![(
+                "threads",
+                vec![object(vec![
+                    ("id", THREAD.into()),
+                    ("name", "intcode".into()),
+                ])]
+                .into(),
+            )])),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => Ok(scopes()),
+            "variables" => self.variables(args),
+            "source" => self.source(),
+            "evaluate" => self.evaluate(args),
+            "disassemble" => self.disassemble(args),
+            // Programs have no frames to step out of, so stepOut is not supported.
+            "continue" | "next" | "stepIn" => {
+                let body = match command {
+                    "continue" => object(vec![("allThreadsContinued", true.into())]),
+                    _ => Value::Null,
+                };
+                if self.debuggee.as_ref().is_none_or(|d| d.halted) {
+                    Err("The program is not running".to_string())
+                } else {
+                    self.respond(request, Ok(body))?;
+                    self.resume(command != "continue")?;
+                    return Ok(true);
+                }
+            }
+            // Running programs are paused between steps, so a stopped one has nothing to do.
+            "pause" => Ok(Value::Null),
+            "disconnect" | "terminate" => {
+                self.respond(request, Ok(Value::Null))?;
+                if command == "terminate" {
+                    self.event("terminated", Value::Null)?;
+                }
+                return Ok(false);
+            }
+            _ => Err(format!("Unsupported request {}", command)),
+        };
+
+        self.respond(request, result)?;
+        Ok(true)
+    }
+
+    fn launch(&mut self, args: &Value) -> Result<Value, String> {
+        let path = args
+            .get("program")
+            .as_str()
+            .ok_or("The launch configuration has no program")?;
+        let index = args.get("programIndex").as_i64().unwrap_or(0).max(0) as usize;
+        let programs =
+            loader::load_file(path).map_err(|e| format!("Could not load {}: {}", path, e))?;
+        let program = programs
+            .get(index)
+            .ok_or_else(|| format!("{} holds no program {}", path, index))?;
+
+        let inputs = args
+            .get("inputs")
+            .as_array()
+            .iter()
+            .map(|v| v.as_i64().ok_or("Inputs must be integers"))
+            .collect::<Result<VecDeque<Data>, _>>()?;
+        let name = std::path::Path::new(path)
+            .file_name()
+            .map_or(path.into(), |n| n.to_string_lossy().into_owned());
+
+        self.stop_on_entry = args.get("stopOnEntry").as_bool().unwrap_or(false);
+        self.debuggee = Some(Debuggee {
+            machine: Machine::from_memory(program),
+            name: format!("{}.asm", name),
+            listing: disassemble::disassemble(program),
+            inputs,
+            outputs: Vec::new(),
+            halted: false,
+        });
+
+        Ok(Value::Null)
+    }
+
+    /// Starts the program once it is launched and configured.
+    fn start(&mut self) -> io::Result<()> {
+        if self.started || !self.configured || self.debuggee.is_none() {
+            return Ok(());
+        }
+
+        self.started = true;
+        if self.stop_on_entry {
+            self.stopped(Stop::Entry)
+        } else {
+            self.resume(false)
+        }
+    }
+
+    /// Returns the first line number.
+    fn first_line(&self) -> usize {
+        self.lines_start_at_1 as usize
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Value {
+        let first = self.first_line();
+        let listing = self.debuggee.as_ref().map_or(&[][..], |d| &d.listing[..]);
+        let mut addrs = BTreeSet::new();
+        let breakpoints: Vec<Value> = args
+            .get("breakpoints")
+            .as_array()
+            .iter()
+            .map(|b| {
+                let line = b.get("line").as_i64().unwrap_or(-1);
+                let found = usize::try_from(line - first as i64)
+                    .ok()
+                    .and_then(|n| listing.get(n));
+                match found {
+                    Some(found) => {
+                        addrs.insert(found.addr);
+                        object(vec![
+                            ("verified", true.into()),
+                            ("line", line.into()),
+                            ("instructionReference", found.addr.to_string().into()),
+                        ])
+                    }
+                    None => object(vec![
+                        ("verified", false.into()),
+                        ("line", line.into()),
+                        ("message", "No instruction on this line".into()),
+                    ]),
+                }
+            })
+            .collect();
+
+        self.line_breakpoints = addrs;
+        object(vec![("breakpoints", breakpoints.into())])
+    }
+
+    fn set_instruction_breakpoints(&mut self, args: &Value) -> Value {
+        let mut addrs = BTreeSet::new();
+        let breakpoints: Vec<Value> = args
+            .get("breakpoints")
+            .as_array()
+            .iter()
+            .map(|b| {
+                let reference = b.get("instructionReference").as_str().unwrap_or_default();
+                let offset = b.get("offset").as_i64().unwrap_or(0);
+                let addr = reference
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|a| a.checked_add(offset))
+                    .filter(|&a| a >= 0);
+                match addr {
+                    Some(addr) => {
+                        addrs.insert(addr as usize);
+                        object(vec![
+                            ("verified", true.into()),
+                            ("instructionReference", addr.to_string().into()),
+                        ])
+                    }
+                    None => object(vec![
+                        ("verified", false.into()),
+                        ("message", format!("Bad address {:?}", reference).into()),
+                    ]),
+                }
+            })
+            .collect();
+
+        self.instruction_breakpoints = addrs;
+        object(vec![("breakpoints", breakpoints.into())])
+    }
+
+    /// Runs the program until it stops, checking for requests to pause as it goes.
+    ///
+    /// # Arguments
+    ///
+    /// * `step` - Whether to execute a single instruction.
+    fn resume(&mut self, step: bool) -> io::Result<()> {
+        let mut count = 0;
+        let stop = loop {
+            let debuggee = self.debuggee.as_mut().expect("Resumed without a program");
+            let ip = debuggee.machine.ip();
+            // Execution resumes from a breakpoint by executing the instruction there.
+            if count > 0
+                && (self.line_breakpoints.contains(&ip)
+                    || self.instruction_breakpoints.contains(&ip))
+            {
+                break Stop::Breakpoint;
+            }
+            if debuggee.machine.is_awaiting_input() && debuggee.inputs.is_empty() {
+                break Stop::NeedsInput;
+            }
+
+            let mut output = Vec::new();
+            let (machine, inputs) = (&mut debuggee.machine, &mut debuggee.inputs);
+            // A fault stops the machine and is reported to the client.
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| machine.step(inputs, &mut output)));
+            debuggee.outputs.extend(&output);
+            for value in output {
+                self.console("stdout", format!("{}\n", value))?;
+            }
+
+            count += 1;
+            match result {
+                Ok(true) => break Stop::Halted,
+                Err(payload) => break Stop::Fault(panic_message(&*payload)),
+                Ok(false) if step => break Stop::Step,
+                Ok(false) => {}
+            }
+
+            if count % CHECK == 0 && self.paused() {
+                break Stop::Pause;
+            }
+        };
+
+        self.stopped(stop)
+    }
+
+    /// Returns whether a request to pause arrived, setting aside any other requests.
+    fn paused(&mut self) -> bool {
+        loop {
+            match self.requests.try_recv() {
+                Ok(request) => {
+                    let command = request.get("command").as_str().unwrap_or_default();
+                    match command {
+                        "pause" | "disconnect" | "terminate" => {
+                            self.deferred.push_front(request);
+                            return true;
+                        }
+                        _ => self.deferred.push_back(request),
+                    }
+                }
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    fn stopped(&mut self, stop: Stop) -> io::Result<()> {
+        let (reason, text) = match stop {
+            Stop::Entry => ("entry", None),
+            Stop::Step => ("step", None),
+            Stop::Breakpoint => ("breakpoint", None),
+            Stop::Pause => ("pause", None),
+            Stop::NeedsInput => {
+                self.console(
+                    "console",
+                    "The program is waiting for input. Enter values in the debug console.\n"
+                        .to_string(),
+                )?;
+                ("pause", Some("Waiting for input".to_string()))
+            }
+            Stop::Fault(message) => ("exception", Some(message)),
+            Stop::Halted => {
+                if let Some(debuggee) = &mut self.debuggee {
+                    debuggee.halted = true;
+                }
+                self.event("exited", object(vec![("exitCode", 0.into())]))?;
+                return self.event("terminated", Value::Null);
+            }
+        };
+
+        let mut body = vec![
+            ("reason", reason.into()),
+            ("threadId", THREAD.into()),
+            ("allThreadsStopped", true.into()),
+        ];
+        if let Some(text) = text {
+            body.push(("description", text.clone().into()));
+            body.push(("text", text.into()));
+        }
+
+        self.event("stopped", object(body))
+    }
+
+    fn stack_trace(&self) -> Result<Value, String> {
+        let debuggee = self.debuggee.as_ref().ok_or("No program is loaded")?;
+        let ip = debuggee.machine.ip();
+        let mut frame = vec![
+            ("id", 1.into()),
+            ("name", format!("{}: {}", ip, debuggee.next()).into()),
+            ("column", 0.into()),
+            ("instructionPointerReference", ip.to_string().into()),
+        ];
+
+        match debuggee.line(ip) {
+            Some(line) => {
+                frame.push(("line", (line + self.first_line()).into()));
+                frame.push((
+                    "source",
+                    object(vec![
+                        ("name", debuggee.name.clone().into()),
+                        ("sourceReference", SOURCE.into()),
+                    ]),
+                ));
+            }
+            None => frame.push(("line", 0.into())),
+        }
+
+        Ok(object(vec![
+            ("stackFrames", vec![object(frame)].into()),
+            ("totalFrames", 1.into()),
+        ]))
+    }
+
+    fn variables(&self, args: &Value) -> Result<Value, String> {
+        let debuggee = self.debuggee.as_ref().ok_or("No program is loaded")?;
+        let machine = &debuggee.machine;
+        let variable = |name: String, value: String, reference: i64| {
+            object(vec![
+                ("name", name.into()),
+                ("value", value.into()),
+                ("variablesReference", reference.into()),
+            ])
+        };
+        let list = |values: &mut dyn Iterator<Item = &Data>| {
+            values.map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+        };
+
+        let variables = match args.get("variablesReference").as_i64() {
+            Some(REGISTERS) => vec![
+                variable("ip".into(), machine.ip().to_string(), 0),
+                variable("base".into(), machine.base().to_string(), 0),
+                variable("next".into(), debuggee.next(), 0),
+            ],
+            Some(IO) => {
+                let skip = debuggee.outputs.len().saturating_sub(RECENT);
+                vec![
+                    variable("queued".into(), list(&mut debuggee.inputs.iter()), 0),
+                    variable("outputs".into(), debuggee.outputs.len().to_string(), 0),
+                    variable(
+                        "recent".into(),
+                        list(&mut debuggee.outputs[skip..].iter()),
+                        0,
+                    ),
+                ]
+            }
+            Some(MEMORY) => (0..machine.len().div_ceil(WINDOW))
+                .map(|n| {
+                    let (start, end) = (n * WINDOW, (n + 1) * WINDOW - 1);
+                    let name = format!("{}..{}", start, end);
+                    let value = list(&mut machine.read_range(start..end + 1).iter());
+                    variable(name, value, WINDOWS + n as i64)
+                })
+                .collect(),
+            Some(reference) if reference >= WINDOWS => {
+                // The reference comes from the client, so may lie past any window.
+                let start = usize::try_from(reference - WINDOWS)
+                    .ok()
+                    .and_then(|n| n.checked_mul(WINDOW))
+                    .filter(|start| start.checked_add(WINDOW).is_some())
+                    .ok_or("Unknown variables reference")?;
+                (start..start + WINDOW)
+                    .map(|addr| variable(format!("[{}]", addr), machine.read(addr).to_string(), 0))
+                    .collect()
+            }
+            _ => return Err("Unknown variables reference".to_string()),
+        };
+
+        Ok(object(vec![("variables", variables.into())]))
+    }
+
+    fn source(&self) -> Result<Value, String> {
+        let debuggee = self.debuggee.as_ref().ok_or("No program is loaded")?;
+        let content: String = debuggee
+            .listing
+            .iter()
+            .map(|line| format!("{:>6}  {}\n", line.addr, line.text))
+            .collect();
+
+        Ok(object(vec![("content", content.into())]))
+    }
+
+    /// Evaluates an expression from the debug console: values to queue as input, a register or
+    /// a memory cell in the form `[addr]`.
+    fn evaluate(&mut self, args: &Value) -> Result<Value, String> {
+        let debuggee = self.debuggee.as_mut().ok_or("No program is loaded")?;
+        let expression = args.get("expression").as_str().unwrap_or_default().trim();
+        let result = match expression {
+            "ip" => debuggee.machine.ip().to_string(),
+            "base" => debuggee.machine.base().to_string(),
+            _ if expression.starts_with('[') && expression.ends_with(']') => {
+                let addr = expression[1..expression.len() - 1]
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|e| format!("Bad address: {}", e))?;
+                debuggee.machine.read(addr).to_string()
+            }
+            // Hovering over a value should not queue it.
+            _ if args.get("context").as_str() == Some("hover") => {
+                return Err("Not a register or memory cell".to_string())
+            }
+            _ => {
+                let values = expression
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|v| !v.is_empty())
+                    .map(str::parse)
+                    .collect::<Result<Vec<Data>, _>>()
+                    .map_err(|_| "Expected ip, base, [addr] or values to input".to_string())?;
+                debuggee.inputs.extend(values);
+                format!("{} values queued", debuggee.inputs.len())
+            }
+        };
+
+        Ok(object(vec![
+            ("result", result.into()),
+            ("variablesReference", 0.into()),
+        ]))
+    }
+
+    /// Disassembles instructions around an address, by their position in the listing.
+    fn disassemble(&self, args: &Value) -> Result<Value, String> {
+        let debuggee = self.debuggee.as_ref().ok_or("No program is loaded")?;
+        let reference = args
+            .get("memoryReference")
+            .as_str()
+            .and_then(|r| r.parse::<i64>().ok())
+            .ok_or("Bad memory reference")?;
+        let addr = reference.saturating_add(args.get("offset").as_i64().unwrap_or(0));
+        let offset = args.get("instructionOffset").as_i64().unwrap_or(0);
+        let count = args.get("instructionCount").as_i64().unwrap_or(0).max(0);
+
+        let listing = &debuggee.listing;
+        let anchor = match usize::try_from(addr) {
+            Ok(addr) => listing.partition_point(|line| line.addr + line.len <= addr) as i64,
+            Err(_) => 0,
+        };
+
+        // Positions outside the listing are padded, as the protocol asks.
+        let instructions: Vec<Value> = (anchor + offset..anchor + offset + count)
+            .map(
+                |n| match usize::try_from(n).ok().and_then(|n| listing.get(n)) {
+                    Some(line) => object(vec![
+                        ("address", line.addr.to_string().into()),
+                        ("instruction", line.text.clone().into()),
+                    ]),
+                    None => object(vec![
+                        ("address", n.to_string().into()),
+                        ("instruction", "".into()),
+                        ("presentationHint", "invalid".into()),
+                    ]),
+                },
+            )
+            .collect();
+
+        Ok(object(vec![("instructions", instructions.into())]))
+    }
+}
+
+fn capabilities() -> Value {
+    object(vec![
+        ("supportsConfigurationDoneRequest", true.into()),
+        ("supportsInstructionBreakpoints", true.into()),
+        ("supportsDisassembleRequest", true.into()),
+        ("supportsEvaluateForHovers", true.into()),
+        ("supportsTerminateRequest", true.into()),
+    ])
+}
+
+fn scopes() -> Value {
+    let scope = |name: &str, reference: i64, expensive: bool| {
+        object(vec![
+            ("name", name.into()),
+            ("variablesReference", reference.into()),
+            ("expensive", expensive.into()),
+        ])
+    };
+
+    object(vec![(
+        "scopes",
+        vec![
+            scope("Registers", REGISTERS, false),
+            scope("IO", IO, false),
+            scope("Memory", MEMORY, true),
+        ]
+        .into(),
+    )])
+}
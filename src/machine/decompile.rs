@@ -0,0 +1,787 @@
+//! Decompilation of Intcode programs to structured pseudocode.
+//!
+//! The program is split into basic blocks by following jumps from the start. A jump that is
+//! always taken, in a block which has just stored the address following the jump, is taken to
+//! be a call, and its target the entry of a function. Functions keep their variables in a frame
+//! which they claim by adjusting the relative base with opcode 9, and return by jumping to the
+//! address stored at the base.
+//!
+//! Variables are named after the cells they live in. `m12` is the cell at address 12, and `v3` is
+//! the cell 3 past the relative base at the entry of the enclosing function, so that a function's
+//! return address is `v0` and the values its caller passed are `v1`, `v2` and so on. A cell below
+//! that base, such as one in the caller's frame, is written with an underscore, so `v_2` is the
+//! cell 2 before it. In `main` the relative base starts at 0, so its cells are named by address
+//! like any other. Where the relative base cannot be worked out, cells are named relative to it,
+//! as in `rb[3]`.
+//!
+//! Jump targets are read from the program as it is before running, so any jumps a program
+//! rewrites are decompiled as they were written.
+
+use crate::compile::{decode, Instruction, Op, Operand};
+use crate::Data;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// How a basic block ends.
+#[derive(Clone, Copy, Debug)]
+enum End {
+    /// Falls through into the block at the address.
+    Next(usize),
+    /// Jumps to `target` if `(cond != 0) == nonzero`, and otherwise falls through to `next`.
+    Branch {
+        cond: Operand,
+        nonzero: bool,
+        target: usize,
+        next: usize,
+    },
+    Jump(usize),
+    /// Calls the function at `function`, having stored the return address in `link`.
+    Call {
+        function: usize,
+        link: Operand,
+        next: usize,
+    },
+    /// Jumps to an address held in a cell.
+    Indirect(Operand),
+    Halt,
+    /// Reaches an address that holds no valid instruction.
+    Invalid(Data),
+}
+
+#[derive(Clone, Debug)]
+struct Block {
+    addr: usize,
+    instructions: Vec<Instruction>,
+    end: End,
+}
+
+impl Block {
+    /// Returns the addresses the block may continue at within its function.
+    fn successors(&self) -> Vec<usize> {
+        match self.end {
+            End::Next(next) | End::Jump(next) | End::Call { next, .. } => vec![next],
+            End::Branch { target, next, .. } => vec![target, next],
+            End::Indirect(_) | End::Halt | End::Invalid(_) => Vec::new(),
+        }
+    }
+}
+
+/// Decompiles a program to pseudocode, with one function per call target as well as `main`.
+///
+/// # Arguments
+///
+/// * `memory` - The program.
+pub fn decompile(memory: &[Data]) -> String {
+    let blocks = discover(memory);
+
+    let mut entries = BTreeSet::new();
+    entries.insert(0);
+    for block in blocks.values() {
+        if let End::Call { function, .. } = block.end {
+            entries.insert(function);
+        }
+    }
+
+    let mut out = String::new();
+    for entry in entries {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+
+        let name = function_name(entry);
+        out.push_str(&format!("fn {}() {{\n", name));
+        out.push_str(&Function::new(memory, &blocks, entry).render());
+        out.push_str("}\n");
+    }
+
+    out
+}
+
+fn function_name(entry: usize) -> String {
+    if entry == 0 {
+        String::from("main")
+    } else {
+        format!("f{}", entry)
+    }
+}
+
+/// Splits the code reachable from the start into basic blocks.
+fn discover(memory: &[Data]) -> BTreeMap<usize, Block> {
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+
+    // A leader found inside an existing block splits it, so repeat until no new ones appear.
+    loop {
+        let mut blocks = BTreeMap::new();
+        let mut pending: Vec<usize> = leaders.iter().copied().collect();
+        let mut found = leaders.clone();
+
+        while let Some(addr) = pending.pop() {
+            if blocks.contains_key(&addr) {
+                continue;
+            }
+
+            let block = build(memory, addr, &leaders);
+            let mut targets = block.successors();
+            if let End::Call { function, .. } = block.end {
+                targets.push(function);
+            }
+
+            for target in targets {
+                if found.insert(target) {
+                    pending.push(target);
+                }
+            }
+            blocks.insert(addr, block);
+        }
+
+        if found == leaders {
+            return blocks;
+        }
+        leaders = found;
+    }
+}
+
+/// Builds the block starting at an address, which runs until a jump, a halt or another leader.
+fn build(memory: &[Data], addr: usize, leaders: &BTreeSet<usize>) -> Block {
+    let constant = |operand: Operand| match operand {
+        Operand::Cell(slot) => Some(memory.get(slot).copied().unwrap_or(0)),
+        _ => None,
+    };
+
+    let mut instructions: Vec<Instruction> = Vec::new();
+    let mut at = addr;
+    let end = loop {
+        if at != addr && leaders.contains(&at) {
+            break End::Next(at);
+        }
+
+        let instruction = match decode(memory, at) {
+            Some(instruction) => instruction,
+            None => break End::Invalid(at as Data),
+        };
+        let next = instruction.next();
+
+        let (cond, target, nonzero) = match instruction.op {
+            Op::Halt => break End::Halt,
+            Op::JumpIfTrue(cond, target) => (cond, target, true),
+            Op::JumpIfFalse(cond, target) => (cond, target, false),
+            _ => {
+                instructions.push(instruction);
+                at = next;
+                continue;
+            }
+        };
+
+        let target = match constant(target) {
+            Some(target) if target >= 0 => target as usize,
+            Some(target) => break End::Invalid(target),
+            None => match constant(cond) {
+                Some(value) if (value != 0) != nonzero => break End::Next(next),
+                _ => break End::Indirect(target),
+            },
+        };
+
+        break match constant(cond) {
+            Some(value) if (value != 0) == nonzero => {
+                // A call stores the address after the jump before making it.
+                let link = instructions
+                    .iter()
+                    .rposition(|i| stored(memory, i) == Some(next));
+                match link {
+                    Some(index) => End::Call {
+                        function: target,
+                        link: destination(&instructions.remove(index)),
+                        next,
+                    },
+                    None => End::Jump(target),
+                }
+            }
+            Some(_) => End::Next(next),
+            None => End::Branch {
+                cond,
+                nonzero,
+                target,
+                next,
+            },
+        };
+    };
+
+    Block {
+        addr,
+        instructions,
+        end,
+    }
+}
+
+/// Returns the constant an instruction stores, if it stores one.
+fn stored(memory: &[Data], instruction: &Instruction) -> Option<usize> {
+    let constant = |operand: Operand| match operand {
+        Operand::Cell(slot) => memory.get(slot).copied(),
+        _ => None,
+    };
+
+    let value = match instruction.op {
+        Op::Add(a, b, _) => constant(a)?.checked_add(constant(b)?)?,
+        Op::Multiply(a, b, _) => constant(a)?.checked_mul(constant(b)?)?,
+        _ => return None,
+    };
+
+    if value < 0 {
+        None
+    } else {
+        Some(value as usize)
+    }
+}
+
+fn destination(instruction: &Instruction) -> Operand {
+    match instruction.op {
+        Op::Add(_, _, c) | Op::Multiply(_, _, c) | Op::Less(_, _, c) | Op::Equal(_, _, c) => c,
+        Op::Input(a) => a,
+        _ => unreachable!("Instruction does not store"),
+    }
+}
+
+/// A line of output, before labels that nothing jumps to are dropped.
+enum Line {
+    Text(usize, String),
+    Label(usize),
+}
+
+/// The innermost loop around a region.
+#[derive(Clone, Copy)]
+struct Loop {
+    header: usize,
+    exit: Option<usize>,
+}
+
+/// A function being rendered.
+struct Function<'a> {
+    memory: &'a [Data],
+    /// The function's blocks, in address order.
+    blocks: Vec<&'a Block>,
+    index: HashMap<usize, usize>,
+    /// The relative base on entry to each block, relative to that on entry to the function.
+    bases: HashMap<usize, Option<Data>>,
+    /// Whether the relative base on entry is that of a fresh machine, as it is for `main`, so
+    /// that cells relative to it are at known addresses.
+    absolute: bool,
+    lines: Vec<Line>,
+    gotos: BTreeSet<usize>,
+}
+
+impl<'a> Function<'a> {
+    fn new(memory: &'a [Data], all: &'a BTreeMap<usize, Block>, entry: usize) -> Self {
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![entry];
+        let mut bases: HashMap<usize, Option<Data>> = HashMap::new();
+        bases.insert(entry, Some(0));
+
+        while let Some(addr) = pending.pop() {
+            let block = match all.get(&addr) {
+                Some(block) => block,
+                None => continue,
+            };
+            seen.insert(addr);
+
+            let mut base = bases[&addr];
+            for instruction in &block.instructions {
+                if let Op::AdjustBase(a) = instruction.op {
+                    base = match (base, a) {
+                        (Some(base), Operand::Cell(slot)) => base.checked_add(memory[slot]),
+                        _ => None,
+                    };
+                }
+            }
+
+            // Calls are assumed to restore the relative base.
+            for next in block.successors() {
+                match bases.get(&next) {
+                    None => {
+                        bases.insert(next, base);
+                        pending.push(next);
+                    }
+                    Some(&known) if known.is_some() && known != base => {
+                        bases.insert(next, None);
+                        pending.push(next);
+                    }
+                    _ if !seen.contains(&next) => pending.push(next),
+                    _ => {}
+                }
+            }
+        }
+
+        let blocks: Vec<&Block> = seen.iter().map(|addr| &all[addr]).collect();
+        let index = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| (block.addr, i))
+            .collect();
+
+        Function {
+            memory,
+            blocks,
+            index,
+            bases,
+            absolute: entry == 0,
+            lines: Vec::new(),
+            gotos: BTreeSet::new(),
+        }
+    }
+
+    fn render(mut self) -> String {
+        let len = self.blocks.len();
+        self.region(0, len, None, None, 1);
+
+        let mut out = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Text(depth, text) => {
+                    out.push_str(&"    ".repeat(*depth));
+                    out.push_str(text);
+                    out.push('\n');
+                }
+                Line::Label(addr) if self.gotos.contains(addr) => {
+                    out.push_str(&format!("L{}:\n", addr));
+                }
+                Line::Label(_) => {}
+            }
+        }
+
+        out
+    }
+
+    fn line(&mut self, depth: usize, text: String) {
+        self.lines.push(Line::Text(depth, text));
+    }
+
+    /// Renders the blocks in `start..end`.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The index of the first block.
+    /// * `end` - The index after the last block.
+    /// * `follow` - Where control goes once the region is done, which needs no jump.
+    /// * `inner` - The innermost enclosing loop.
+    /// * `depth` - The indentation.
+    fn region(
+        &mut self,
+        start: usize,
+        end: usize,
+        follow: Option<usize>,
+        inner: Option<Loop>,
+        depth: usize,
+    ) {
+        let mut i = start;
+        while i < end {
+            let block = self.blocks[i];
+            let header = block.addr;
+
+            // The last block in the region that jumps back here closes a loop.
+            let back = if inner.is_some_and(|l| l.header == header) {
+                None
+            } else {
+                (i..end)
+                    .rev()
+                    .find(|&j| self.blocks[j].successors().contains(&header))
+            };
+
+            if let Some(last) = back {
+                i = self.emit_loop(i, last, depth);
+                continue;
+            }
+
+            i = self.emit_block(i, end, follow, inner, depth);
+        }
+    }
+
+    /// Renders the loop from block `first` to block `last`, and returns the index after it.
+    fn emit_loop(&mut self, first: usize, last: usize, depth: usize) -> usize {
+        let header = self.blocks[first].addr;
+        let exit = self.blocks.get(last + 1).map(|block| block.addr);
+        let inner = Some(Loop { header, exit });
+        self.lines.push(Line::Label(header));
+
+        let head = self.blocks[first];
+        let tail = self.blocks[last];
+
+        // A header which only tests whether to leave makes a while loop.
+        if let End::Branch {
+            cond,
+            nonzero,
+            target,
+            next,
+        } = head.end
+        {
+            let body = self.blocks.get(first + 1).map(|block| block.addr);
+            if head.instructions.is_empty() && Some(target) == exit && Some(next) == body {
+                let base = self.bases.get(&header).copied().flatten();
+                let test = self.test(cond, !nonzero, base);
+                self.line(depth, format!("while {} {{", test));
+                self.region(first + 1, last + 1, Some(header), inner, depth + 1);
+                self.line(depth, String::from("}"));
+                return last + 1;
+            }
+        }
+
+        // A tail which tests whether to go round again makes a do-while loop.
+        if let End::Branch {
+            cond,
+            nonzero,
+            target,
+            next,
+        } = tail.end
+        {
+            if target == header && Some(next) == exit {
+                self.line(depth, String::from("do {"));
+                self.region(first, last, Some(tail.addr), inner, depth + 1);
+                self.lines.push(Line::Label(tail.addr));
+                let base = self.statements(tail, depth + 1);
+                self.line(
+                    depth,
+                    format!("}} while {}", self.test(cond, nonzero, base)),
+                );
+                return last + 1;
+            }
+        }
+
+        self.line(depth, String::from("loop {"));
+        self.region(first, last + 1, Some(header), inner, depth + 1);
+        self.line(depth, String::from("}"));
+        last + 1
+    }
+
+    /// Renders block `i`, along with any blocks an if statement at its end takes in, and returns
+    /// the index of the next block to render.
+    fn emit_block(
+        &mut self,
+        i: usize,
+        end: usize,
+        follow: Option<usize>,
+        inner: Option<Loop>,
+        depth: usize,
+    ) -> usize {
+        let block = self.blocks[i];
+        self.lines.push(Line::Label(block.addr));
+        let base = self.statements(block, depth);
+        let fallthrough = self.fallthrough(i, end, follow);
+
+        match block.end {
+            End::Next(target) | End::Jump(target) => {
+                if let Some(text) = self.jump(target, fallthrough, inner) {
+                    self.line(depth, text);
+                }
+            }
+            End::Call {
+                function,
+                link,
+                next,
+            } => {
+                let frame = self.name(link, base);
+                self.line(
+                    depth,
+                    format!("call {}(frame {})", function_name(function), frame),
+                );
+                if let Some(text) = self.jump(next, fallthrough, inner) {
+                    self.line(depth, text);
+                }
+            }
+            End::Indirect(target) => {
+                let text = match (target, base) {
+                    (Operand::Relative(slot), Some(base)) if base + self.memory[slot] == 0 => {
+                        String::from("return")
+                    }
+                    _ => format!("goto *{}", self.name(target, base)),
+                };
+                self.line(depth, text);
+            }
+            End::Halt => self.line(depth, String::from("halt")),
+            End::Invalid(addr) => self.line(depth, format!("invalid instruction at {}", addr)),
+            End::Branch { .. } => return self.emit_branch(i, end, follow, inner, depth, base),
+        }
+
+        i + 1
+    }
+
+    /// Returns where control goes after block `i` without a jump.
+    fn fallthrough(&self, i: usize, end: usize, follow: Option<usize>) -> Option<usize> {
+        if i + 1 < end {
+            Some(self.blocks[i + 1].addr)
+        } else {
+            follow
+        }
+    }
+
+    /// Renders the conditional jump ending block `i` as an if statement, and returns the index
+    /// of the next block to render.
+    fn emit_branch(
+        &mut self,
+        i: usize,
+        end: usize,
+        follow: Option<usize>,
+        inner: Option<Loop>,
+        depth: usize,
+        base: Option<Data>,
+    ) -> usize {
+        let (cond, nonzero, target, next) = match self.blocks[i].end {
+            End::Branch {
+                cond,
+                nonzero,
+                target,
+                next,
+            } => (cond, nonzero, target, next),
+            _ => unreachable!("Block does not end with a branch"),
+        };
+
+        let fallthrough = self.fallthrough(i, end, follow);
+        let taken = self.test(cond, nonzero, base);
+        let skipped = self.test(cond, !nonzero, base);
+
+        // Returns the index of the block that ends a region running up to an address.
+        let region_end = |addr: usize| {
+            if Some(addr) == follow {
+                Some(end)
+            } else {
+                self.index
+                    .get(&addr)
+                    .copied()
+                    .filter(|&t| t > i && t <= end)
+            }
+        };
+        let then_end = region_end(target);
+        let merge = then_end.and_then(|t| match self.blocks[t - 1].end {
+            End::Jump(merge) if t > i + 1 => {
+                region_end(merge).filter(|&m| m > t).map(|m| (merge, m))
+            }
+            _ => None,
+        });
+
+        let then_end = match then_end {
+            Some(t) if inner.is_none_or(|l| l.header != target && l.exit != Some(target)) => t,
+            _ => {
+                // Jumps out of a loop, or to anywhere else an if statement cannot reach.
+                if Some(target) != fallthrough {
+                    let jump = self.jump(target, None, inner).unwrap_or_default();
+                    self.line(depth, format!("if {} {{", taken));
+                    self.line(depth + 1, jump);
+                    self.line(depth, String::from("}"));
+                }
+
+                if let Some(text) = self.jump(next, fallthrough, inner) {
+                    self.line(depth, text);
+                }
+                return i + 1;
+            }
+        };
+
+        // A then branch that ends by jumping past the else branch makes an if-else.
+        if let Some((merge, m)) = merge {
+            let start = self.lines.len();
+            self.region(i + 1, then_end, Some(merge), inner, depth + 1);
+            let then_lines = self.lines.split_off(start);
+            self.region(then_end, m, Some(merge), inner, depth + 1);
+            let else_lines = self.lines.split_off(start);
+
+            match (is_empty(&then_lines), is_empty(&else_lines)) {
+                (false, false) => {
+                    self.line(depth, format!("if {} {{", skipped));
+                    self.lines.extend(then_lines);
+                    self.line(depth, String::from("} else {"));
+                    self.lines.extend(else_lines);
+                    self.line(depth, String::from("}"));
+                }
+                (true, _) => {
+                    self.guard(taken, else_lines, depth);
+                    self.lines.extend(then_lines);
+                }
+                (false, true) => {
+                    self.guard(skipped, then_lines, depth);
+                    self.lines.extend(else_lines);
+                }
+            }
+            return m;
+        }
+
+        if then_end > i + 1 {
+            let start = self.lines.len();
+            self.region(i + 1, then_end, Some(target), inner, depth + 1);
+            let then_lines = self.lines.split_off(start);
+            self.guard(skipped, then_lines, depth);
+        }
+        then_end
+    }
+
+    /// Renders lines under an if statement, or on their own if they have no statements. Labels
+    /// in an arm without statements mark the same place as the end of the if statement.
+    fn guard(&mut self, condition: String, lines: Vec<Line>, depth: usize) {
+        if is_empty(&lines) {
+            self.lines.extend(lines);
+        } else {
+            self.line(depth, format!("if {} {{", condition));
+            self.lines.extend(lines);
+            self.line(depth, String::from("}"));
+        }
+    }
+
+    /// Returns the statement for a jump, or `None` if control gets there anyway.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Where the jump goes.
+    /// * `fallthrough` - Where control goes without a jump.
+    /// * `inner` - The innermost enclosing loop.
+    fn jump(
+        &mut self,
+        target: usize,
+        fallthrough: Option<usize>,
+        inner: Option<Loop>,
+    ) -> Option<String> {
+        match inner {
+            _ if Some(target) == fallthrough => None,
+            Some(l) if l.header == target => Some(String::from("continue")),
+            Some(l) if l.exit == Some(target) => Some(String::from("break")),
+            _ => {
+                self.gotos.insert(target);
+                Some(format!("goto L{}", target))
+            }
+        }
+    }
+
+    /// Renders a block's statements, and returns the relative base after them.
+    fn statements(&mut self, block: &Block, depth: usize) -> Option<Data> {
+        let mut base = self.bases.get(&block.addr).copied().flatten();
+
+        for instruction in &block.instructions {
+            let text = match instruction.op {
+                Op::Add(a, b, c) => {
+                    let (a, b) = (self.value(a, base), self.value(b, base));
+                    match assign(self.name(c, base), sum(a, b)) {
+                        Some(text) => text,
+                        None => continue,
+                    }
+                }
+                Op::Multiply(a, b, c) => {
+                    let (a, b) = (self.value(a, base), self.value(b, base));
+                    match assign(self.name(c, base), product(a, b)) {
+                        Some(text) => text,
+                        None => continue,
+                    }
+                }
+                Op::Less(a, b, c) => format!(
+                    "{} = {} < {}",
+                    self.name(c, base),
+                    self.value(a, base),
+                    self.value(b, base)
+                ),
+                Op::Equal(a, b, c) => format!(
+                    "{} = {} == {}",
+                    self.name(c, base),
+                    self.value(a, base),
+                    self.value(b, base)
+                ),
+                Op::Input(a) => format!("{} = input()", self.name(a, base)),
+                Op::Output(a) => format!("output({})", self.value(a, base)),
+                Op::AdjustBase(a) => {
+                    let text = match self.value(a, base) {
+                        Value::Constant(c) if c < 0 => format!("rb -= {}", -(c as i128)),
+                        value => format!("rb += {}", value),
+                    };
+                    base = match (base, a) {
+                        (Some(b), Operand::Cell(slot)) => b.checked_add(self.memory[slot]),
+                        _ => None,
+                    };
+                    text
+                }
+                Op::JumpIfTrue(..) | Op::JumpIfFalse(..) | Op::Halt => continue,
+            };
+
+            self.line(depth, text);
+        }
+
+        base
+    }
+
+    /// Returns a condition that holds when `(operand != 0) == nonzero`.
+    fn test(&self, operand: Operand, nonzero: bool, base: Option<Data>) -> String {
+        let value = self.value(operand, base);
+        format!("{} {} 0", value, if nonzero { "!=" } else { "==" })
+    }
+
+    fn value(&self, operand: Operand, base: Option<Data>) -> Value {
+        match operand {
+            Operand::Cell(slot) => Value::Constant(self.memory.get(slot).copied().unwrap_or(0)),
+            _ => Value::Variable(self.name(operand, base)),
+        }
+    }
+
+    /// Returns the name of the variable an operand refers to.
+    fn name(&self, operand: Operand, base: Option<Data>) -> String {
+        let cell = |slot: usize| self.memory.get(slot).copied().unwrap_or(0);
+        match operand {
+            Operand::Cell(slot) => format!("m{}", slot),
+            Operand::Position(slot) => format!("m{}", cell(slot)),
+            Operand::Relative(slot) => match base.and_then(|b| b.checked_add(cell(slot))) {
+                Some(offset) if offset >= 0 && self.absolute => format!("m{}", offset),
+                Some(offset) if offset >= 0 => format!("v{}", offset),
+                Some(offset) => format!("v_{}", -(offset as i128)),
+                None => format!("rb[{}]", cell(slot)),
+            },
+        }
+    }
+}
+
+/// An operand as it appears in an expression.
+#[derive(Clone)]
+enum Value {
+    Constant(Data),
+    Variable(String),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Constant(c) => write!(f, "{}", c),
+            Value::Variable(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Returns whether rendered lines hold no statements.
+fn is_empty(lines: &[Line]) -> bool {
+    lines.iter().all(|line| matches!(line, Line::Label(_)))
+}
+
+/// Returns an assignment, or `None` if it leaves the variable as it was.
+fn assign(name: String, value: String) -> Option<String> {
+    if name == value {
+        None
+    } else {
+        Some(format!("{} = {}", name, value))
+    }
+}
+
+fn sum(a: Value, b: Value) -> String {
+    match (a, b) {
+        (Value::Constant(a), Value::Constant(b)) => match a.checked_add(b) {
+            Some(c) => c.to_string(),
+            None => format!("{} + {}", a, b),
+        },
+        (Value::Constant(0), v) | (v, Value::Constant(0)) => v.to_string(),
+        (v, Value::Constant(c)) | (Value::Constant(c), v) if c < 0 && c != Data::MIN => {
+            format!("{} - {}", v, -c)
+        }
+        (a, b) => format!("{} + {}", a, b),
+    }
+}
+
+fn product(a: Value, b: Value) -> String {
+    match (a, b) {
+        (Value::Constant(a), Value::Constant(b)) => match a.checked_mul(b) {
+            Some(c) => c.to_string(),
+            None => format!("{} * {}", a, b),
+        },
+        (Value::Constant(0), _) | (_, Value::Constant(0)) => String::from("0"),
+        (Value::Constant(1), v) | (v, Value::Constant(1)) => v.to_string(),
+        (Value::Constant(-1), v) | (v, Value::Constant(-1)) => format!("-{}", v),
+        (a, b) => format!("{} * {}", a, b),
+    }
+}
@@ -0,0 +1,103 @@
+//! A disassembler for Intcode, producing one line of assembly per instruction.
+//!
+//! Parameters are written `#5` in immediate mode, `[5]` in position mode and `[rb+5]` in
+//! relative mode, and instructions as `add`, `mul`, `in`, `out`, `jnz`, `jz`, `lt`, `eq`, `arb`
+//! and `hlt`. Cells that do not hold a valid instruction are written as `data`.
+//!
+//! The disassembly sweeps memory from the start, so data that happens to decode as an
+//! instruction is shown as one.
+
+use crate::compile::{decode, Instruction, Op, Operand};
+use crate::Data;
+
+/// Returns the number of cells an instruction covers, including its parameters, or `None` if
+/// its opcode is unknown.
+///
+/// # Arguments
+///
+/// * `word` - The first cell of the instruction.
+pub fn width(word: Data) -> Option<usize> {
+    match word % 100 {
+        1 | 2 | 7 | 8 => Some(4),
+        5 | 6 => Some(3),
+        3 | 4 | 9 => Some(2),
+        99 => Some(1),
+        _ => None,
+    }
+}
+
+/// A line of assembly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub addr: usize,
+    /// The number of cells the line covers.
+    pub len: usize,
+    pub text: String,
+}
+
+/// Disassembles a program.
+///
+/// # Arguments
+///
+/// * `memory` - The program.
+pub fn disassemble(memory: &[Data]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while addr < memory.len() {
+        let line = match decode(memory, addr) {
+            Some(instruction) => Line {
+                addr,
+                len: instruction.next() - addr,
+                text: format(memory, &instruction),
+            },
+            None => Line {
+                addr,
+                len: 1,
+                text: format!("data {}", memory[addr]),
+            },
+        };
+
+        addr += line.len;
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Formats a single instruction.
+///
+/// # Arguments
+///
+/// * `memory` - The memory holding the instruction.
+/// * `instruction` - The instruction.
+pub fn format(memory: &[Data], instruction: &Instruction) -> String {
+    let operand = |operand: Operand| {
+        let cell = |slot: usize| memory.get(slot).copied().unwrap_or(0);
+        match operand {
+            Operand::Cell(slot) => format!("#{}", cell(slot)),
+            Operand::Position(slot) => format!("[{}]", cell(slot)),
+            Operand::Relative(slot) if cell(slot) < 0 => format!("[rb{}]", cell(slot)),
+            Operand::Relative(slot) => format!("[rb+{}]", cell(slot)),
+        }
+    };
+
+    let (name, operands) = match instruction.op {
+        Op::Add(a, b, c) => ("add", vec![a, b, c]),
+        Op::Multiply(a, b, c) => ("mul", vec![a, b, c]),
+        Op::Input(a) => ("in", vec![a]),
+        Op::Output(a) => ("out", vec![a]),
+        Op::JumpIfTrue(a, b) => ("jnz", vec![a, b]),
+        Op::JumpIfFalse(a, b) => ("jz", vec![a, b]),
+        Op::Less(a, b, c) => ("lt", vec![a, b, c]),
+        Op::Equal(a, b, c) => ("eq", vec![a, b, c]),
+        Op::AdjustBase(a) => ("arb", vec![a]),
+        Op::Halt => ("hlt", vec![]),
+    };
+
+    let operands: Vec<String> = operands.into_iter().map(operand).collect();
+    if operands.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} {}", name, operands.join(", "))
+    }
+}
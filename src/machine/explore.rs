@@ -0,0 +1,241 @@
+//! Mapping an unknown area with a droid controlled by an Intcode program.
+//!
+//! The droid reads a movement command, 1 to 4 for north, south, west and east, and replies with
+//! a status: 0 if it hit a wall and stayed put, 1 if it moved, and 2 if it moved and found the
+//! target.
+//!
+//! Rather than walking the droid back after each dead end, the explorer forks it: every open
+//! position reached keeps a clone of the droid standing there, and each direction is tried on a
+//! clone of its own. Clones share memory until they write to it, so this costs little more than
+//! the positions themselves. Positions are explored breadth first, so the first time one is
+//! reached is by a shortest path.
+
+use crate::canvas::{Canvas, Point};
+use crate::{Data, Machine, Status};
+use std::collections::{HashMap, VecDeque};
+
+/// What the droid found at a position, as it reports it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cell {
+    Wall = 0,
+    Open = 1,
+    Target = 2,
+}
+
+impl Cell {
+    /// Returns the cell a status reports, if the status is valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The droid's reply.
+    pub fn from_status(status: Data) -> Option<Cell> {
+        match status {
+            0 => Some(Cell::Wall),
+            1 => Some(Cell::Open),
+            2 => Some(Cell::Target),
+            _ => None,
+        }
+    }
+}
+
+/// A movement command. North is towards negative `y`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    North = 1,
+    South = 2,
+    West = 3,
+    East = 4,
+}
+
+impl Move {
+    pub const ALL: [Move; 4] = [Move::North, Move::South, Move::West, Move::East];
+
+    /// Returns the position one step away in this direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The position to step from.
+    pub fn apply(self, from: Point) -> Point {
+        let (x, y) = from;
+        match self {
+            Move::North => (x, y - 1),
+            Move::South => (x, y + 1),
+            Move::West => (x - 1, y),
+            Move::East => (x + 1, y),
+        }
+    }
+
+    pub fn reverse(self) -> Move {
+        match self {
+            Move::North => Move::South,
+            Move::South => Move::North,
+            Move::West => Move::East,
+            Move::East => Move::West,
+        }
+    }
+}
+
+/// The area a droid explored, with its start at `(0, 0)`.
+#[derive(Clone, Debug)]
+pub struct Map {
+    cells: Canvas,
+    /// The move that first reached each open position, and so lies on a shortest path to it.
+    arrivals: HashMap<Point, Move>,
+    target: Option<Point>,
+}
+
+impl Map {
+    /// Returns what is at a position, if the droid found out.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The position.
+    pub fn cell(&self, point: Point) -> Option<Cell> {
+        self.cells.get(point).and_then(Cell::from_status)
+    }
+
+    /// Returns where the target is, if the droid found it.
+    pub fn target(&self) -> Option<Point> {
+        self.target
+    }
+
+    /// Returns the number of open positions, including the start and the target.
+    pub fn open(&self) -> usize {
+        self.arrivals.len() + 1
+    }
+
+    /// Returns the moves of a shortest path from the start to a position, if it is reachable.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The position.
+    pub fn path_to(&self, mut point: Point) -> Option<Vec<Move>> {
+        let mut path = Vec::new();
+        while point != (0, 0) {
+            let arrival = *self.arrivals.get(&point)?;
+            path.push(arrival);
+            point = arrival.reverse().apply(point);
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    /// Returns the moves of a shortest path from the start to the target.
+    pub fn shortest_path(&self) -> Option<Vec<Move>> {
+        self.path_to(self.target?)
+    }
+
+    /// Returns the number of moves needed to reach every open position from a position, as when
+    /// something spreading from there fills the area a step a minute.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Where the filling starts.
+    pub fn fill_times(&self, from: Point) -> HashMap<Point, usize> {
+        let mut times = HashMap::new();
+        if self.cell(from).is_none_or(|cell| cell == Cell::Wall) {
+            return times;
+        }
+
+        let mut queue = VecDeque::new();
+        times.insert(from, 0);
+        queue.push_back(from);
+        while let Some(point) = queue.pop_front() {
+            let time = times[&point];
+            for step in &Move::ALL {
+                let next = step.apply(point);
+                let open = self.cell(next).is_some_and(|cell| cell != Cell::Wall);
+                if open && !times.contains_key(&next) {
+                    times.insert(next, time + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        times
+    }
+
+    /// Returns the time it takes to fill the area from a position, or `None` if the position
+    /// is not open.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Where the filling starts.
+    pub fn flood_fill(&self, from: Point) -> Option<usize> {
+        self.fill_times(from).values().max().copied()
+    }
+
+    /// Renders the map as text, with the start as `D`, the target as `O`, walls as `#`, open
+    /// positions as `.` and unexplored ones as spaces.
+    pub fn render(&self) -> String {
+        let mut cells = self.cells.clone();
+        cells.draw((0, 0), -1);
+        cells.render(|status| match status {
+            Some(-1) => 'D',
+            Some(status) => match Cell::from_status(status) {
+                Some(Cell::Wall) => '#',
+                Some(Cell::Open) => '.',
+                Some(Cell::Target) => 'O',
+                None => '?',
+            },
+            None => ' ',
+        })
+    }
+}
+
+/// Maps everything a droid can reach, breadth first from where it starts.
+///
+/// # Arguments
+///
+/// * `droid` - The machine controlling the droid, ready to read its first command.
+pub fn explore(droid: &Machine) -> Result<Map, String> {
+    let mut map = Map {
+        cells: Canvas::new(),
+        arrivals: HashMap::new(),
+        target: None,
+    };
+    map.cells.draw((0, 0), 1);
+
+    let mut queue = VecDeque::new();
+    queue.push_back(((0, 0), droid.clone()));
+    while let Some((point, droid)) = queue.pop_front() {
+        for step in &Move::ALL {
+            let next = step.apply(point);
+            if map.cells.get(next).is_some() {
+                continue;
+            }
+
+            let mut fork = droid.clone();
+            let cell = command(&mut fork, *step)?;
+            map.cells.draw(next, cell as Data);
+            if cell == Cell::Wall {
+                continue;
+            }
+
+            map.arrivals.insert(next, *step);
+            if cell == Cell::Target && map.target.is_none() {
+                map.target = Some(next);
+            }
+            queue.push_back((next, fork));
+        }
+    }
+
+    Ok(map)
+}
+
+/// Sends a droid one command and returns its reply.
+fn command(droid: &mut Machine, step: Move) -> Result<Cell, String> {
+    let mut replies = Vec::new();
+    if droid.run_until_input(&mut replies) == Status::Halted {
+        return Err(format!("Droid halted at {}", droid.ip()));
+    }
+
+    droid.step(&mut (step as Data), &mut replies);
+    let halted = droid.run_until_input(&mut replies) == Status::Halted;
+    match replies.as_slice() {
+        [reply] => Cell::from_status(*reply).ok_or_else(|| format!("Unknown status: {}", reply)),
+        _ if halted && replies.is_empty() => Err(format!("Droid halted at {}", droid.ip())),
+        _ => Err(format!("Expected one status, got {:?}", replies)),
+    }
+}
@@ -0,0 +1,90 @@
+//! Decoding output that comes in fixed-size tuples.
+//!
+//! Programs that draw typically output a tuple per tile, such as `x, y, tile`, and others a pair
+//! per move, such as `color, turn`. `Frames` is an `Output` which collects values until it has a
+//! whole tuple, then decodes it and passes it to a handler.
+
+use crate::{Data, Output};
+use std::marker::PhantomData;
+
+/// A value decoded from a fixed number of outputs.
+pub trait Frame: Sized {
+    /// The number of outputs a frame takes.
+    const LEN: usize;
+
+    /// Decodes a frame from exactly `LEN` values.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The outputs, in the order they were written.
+    fn decode(values: &[Data]) -> Self;
+}
+
+impl<const N: usize> Frame for [Data; N] {
+    const LEN: usize = N;
+
+    fn decode(values: &[Data]) -> Self {
+        let mut frame = [0; N];
+        frame.copy_from_slice(values);
+        frame
+    }
+}
+
+impl Frame for (Data, Data) {
+    const LEN: usize = 2;
+
+    fn decode(values: &[Data]) -> Self {
+        (values[0], values[1])
+    }
+}
+
+impl Frame for (Data, Data, Data) {
+    const LEN: usize = 3;
+
+    fn decode(values: &[Data]) -> Self {
+        (values[0], values[1], values[2])
+    }
+}
+
+/// An output which groups values into frames of type `T` and passes each to a handler.
+pub struct Frames<T, F> {
+    handler: F,
+    /// The values of the frame being collected.
+    pending: Vec<Data>,
+    frame: PhantomData<fn(T)>,
+}
+
+impl<T: Frame, F: FnMut(T)> Frames<T, F> {
+    /// Creates an output which calls `handler` with each complete frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The function to call with each frame.
+    pub fn new(handler: F) -> Self {
+        Frames {
+            handler,
+            pending: Vec::with_capacity(T::LEN),
+            frame: PhantomData,
+        }
+    }
+
+    /// Returns the values written since the last complete frame.
+    pub fn pending(&self) -> &[Data] {
+        &self.pending
+    }
+
+    /// Returns the handler.
+    pub fn into_inner(self) -> F {
+        self.handler
+    }
+}
+
+impl<T: Frame, F: FnMut(T)> Output for Frames<T, F> {
+    fn write(&mut self, val: Data) {
+        self.pending.push(val);
+        if self.pending.len() == T::LEN {
+            (self.handler)(T::decode(&self.pending));
+            self.pending.clear();
+        }
+    }
+}
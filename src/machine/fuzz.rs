@@ -0,0 +1,372 @@
+//! Differential fuzzing of `Machine` against the reference interpreter.
+
+use crate::reference::{Fault, Reference, Status};
+use crate::{panic_message, Data, Input, Machine};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The default number of instructions each case may execute.
+pub const BUDGET: usize = 10_000;
+
+/// Where regression fixtures live, relative to the crate root.
+pub const FIXTURES: &str = "tests/fixtures/fuzz";
+
+/// A program together with the values it reads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Case {
+    pub program: Vec<Data>,
+    pub inputs: Vec<Data>,
+}
+
+/// A difference in behaviour between `Machine` and the reference interpreter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The machine panicked on a program the reference ran without fault.
+    Panicked(String),
+    /// The reference faulted, but the machine carried on.
+    MissedFault(Fault),
+    /// Both ran without fault, but they disagree about the named part of the final state.
+    Diverged(&'static str),
+}
+
+impl Mismatch {
+    /// Returns whether two mismatches are of the same kind, ignoring values such as the exact
+    /// address of a fault.
+    pub fn same_kind(&self, other: &Mismatch) -> bool {
+        match (self, other) {
+            (Mismatch::Panicked(_), Mismatch::Panicked(_)) => true,
+            (Mismatch::MissedFault(a), Mismatch::MissedFault(b)) => {
+                std::mem::discriminant(a) == std::mem::discriminant(b)
+            }
+            (Mismatch::Diverged(a), Mismatch::Diverged(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::Panicked(message) => write!(f, "machine panicked: {}", message),
+            Mismatch::MissedFault(fault) => write!(f, "machine did not fault on {}", fault),
+            Mismatch::Diverged(what) => write!(f, "machine and reference disagree on {}", what),
+        }
+    }
+}
+
+/// Runs a case under both interpreters and returns how they differ, if at all. Cases which the
+/// reference cannot run because they touch addresses above its limit are skipped.
+///
+/// # Arguments
+///
+/// * `case` - The case to run.
+/// * `budget` - The number of instructions either interpreter may execute.
+pub fn check(case: &Case, budget: usize) -> Option<Mismatch> {
+    let mut reference = Reference::new(&case.program, &case.inputs);
+    let status = reference.run(budget);
+
+    // The machine only notices a bad jump target when it fetches the next instruction, so it
+    // gets one extra step to fault.
+    let budget = match status {
+        Status::Fault(Fault::AddressLimit(_)) => return None,
+        Status::Fault(_) => budget + 1,
+        _ => budget,
+    };
+
+    let run = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut machine = Machine::from_memory(&case.program);
+        let mut output = Vec::new();
+        let mut input = Feed::new(&case.inputs);
+        let halted = machine.execute_for(&mut input, &mut output, budget);
+        (machine, output, halted)
+    }));
+
+    match (status, run) {
+        (Status::Fault(_), Err(_)) => None,
+        (Status::Fault(fault), Ok(_)) => Some(Mismatch::MissedFault(fault)),
+        (_, Err(payload)) => Some(Mismatch::Panicked(panic_message(&*payload))),
+        (status, Ok((machine, output, halted))) => {
+            if halted != (status == Status::Halted) {
+                Some(Mismatch::Diverged("halting"))
+            } else if output != reference.outputs {
+                Some(Mismatch::Diverged("output"))
+            } else if machine.ip != reference.ip || machine.base != reference.base {
+                Some(Mismatch::Diverged("registers"))
+            } else if (0..machine.memory.len().max(reference.len()))
+                .any(|addr| machine.read(addr) != reference.read(addr))
+            {
+                Some(Mismatch::Diverged("memory"))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Shrinks a case while it still produces a mismatch of the same kind.
+///
+/// # Arguments
+///
+/// * `case` - The failing case.
+/// * `budget` - The number of instructions either interpreter may execute.
+pub fn minimize(case: &Case, budget: usize) -> Case {
+    let mismatch = match check(case, budget) {
+        Some(mismatch) => mismatch,
+        None => return case.clone(),
+    };
+
+    let fails = |candidate: &Case| check(candidate, budget).is_some_and(|m| m.same_kind(&mismatch));
+
+    let mut best = case.clone();
+    let mut progress = true;
+    while progress {
+        progress = false;
+
+        // Remove ever smaller chunks of the program and the inputs.
+        let mut chunk = best.program.len().max(1);
+        while chunk > 0 {
+            let mut start = 0;
+            while start < best.program.len() {
+                let mut candidate = best.clone();
+                let end = (start + chunk).min(candidate.program.len());
+                candidate.program.drain(start..end);
+
+                if fails(&candidate) {
+                    best = candidate;
+                    progress = true;
+                } else {
+                    start += chunk;
+                }
+            }
+
+            chunk /= 2;
+        }
+
+        for i in (0..best.inputs.len()).rev() {
+            let mut candidate = best.clone();
+            candidate.inputs.remove(i);
+            if fails(&candidate) {
+                best = candidate;
+                progress = true;
+            }
+        }
+
+        // Bring each remaining value closer to zero.
+        for i in 0..best.program.len() {
+            for value in shrink(best.program[i]) {
+                let mut candidate = best.clone();
+                candidate.program[i] = value;
+                if fails(&candidate) {
+                    best = candidate;
+                    progress = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// Returns smaller candidates for a value, most aggressive first.
+fn shrink(value: Data) -> Vec<Data> {
+    let mut candidates = vec![0, value / 2, value - value.signum()];
+    candidates.retain(|v| v.abs() < value.abs());
+    candidates.dedup();
+    candidates
+}
+
+/// Feeds the case's inputs to the machine in the same way as the reference.
+struct Feed<'a> {
+    values: &'a [Data],
+    read: usize,
+}
+
+impl<'a> Feed<'a> {
+    fn new(values: &'a [Data]) -> Self {
+        Feed { values, read: 0 }
+    }
+}
+
+impl<'a> Input for Feed<'a> {
+    fn get(&mut self) -> Data {
+        if self.values.is_empty() {
+            return 0;
+        }
+
+        let value = self.values[self.read % self.values.len()];
+        self.read += 1;
+        value
+    }
+}
+
+/// A deterministic pseudo-random number generator (SplitMix64).
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+
+    /// Returns true with probability `1 / n`.
+    pub fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+}
+
+/// Generates a random case. Programs are mostly well-formed instructions whose parameters point
+/// back into the program, with the occasional invalid opcode, mode or out-of-range value.
+///
+/// # Arguments
+///
+/// * `seed` - The seed for the generator. The same seed always produces the same case.
+pub fn generate(seed: u64) -> Case {
+    const OPCODES: [(Data, usize); 10] = [
+        (1, 3),
+        (2, 3),
+        (3, 1),
+        (4, 1),
+        (5, 2),
+        (6, 2),
+        (7, 3),
+        (8, 3),
+        (9, 1),
+        (99, 0),
+    ];
+
+    let mut rng = Rng::new(seed);
+    let length = 4 + rng.below(60) as usize;
+    let mut program = Vec::with_capacity(length);
+
+    while program.len() < length {
+        if rng.one_in(20) {
+            program.push(rng.below(200) as Data - 100);
+            continue;
+        }
+
+        let (opcode, params) = OPCODES[rng.below(OPCODES.len() as u64) as usize];
+        let mut instruction = opcode;
+        for n in 0..params {
+            let mode = if rng.one_in(50) {
+                3
+            } else {
+                rng.below(3) as Data
+            };
+            instruction += mode * 10_i64.pow(n as u32 + 2);
+        }
+
+        program.push(instruction);
+        for _ in 0..params {
+            let value = match rng.below(10) {
+                0 => rng.below(2000) as Data - 1000,
+                1 => -(rng.below(5) as Data),
+                _ => rng.below(length as u64 + 4) as Data,
+            };
+            program.push(value);
+        }
+    }
+
+    let inputs = (0..rng.below(4))
+        .map(|_| rng.below(21) as Data - 10)
+        .collect();
+    Case { program, inputs }
+}
+
+impl Case {
+    /// Builds a case from raw bytes, reading each pair as a little endian 16-bit cell. The inputs
+    /// are fixed, so that every byte shapes the program.
+    pub fn from_bytes(data: &[u8]) -> Case {
+        Case {
+            program: data
+                .chunks_exact(2)
+                .map(|pair| i16::from_le_bytes([pair[0], pair[1]]) as Data)
+                .collect(),
+            inputs: vec![1, 0, -1],
+        }
+    }
+
+    /// Formats the case as a fixture, with a comment describing the mismatch.
+    pub fn to_fixture(&self, comment: &str) -> String {
+        format!(
+            "# {}\nprogram: {}\ninputs: {}\n",
+            comment,
+            join(&self.program),
+            join(&self.inputs)
+        )
+    }
+
+    /// Writes the case as a fixture and returns its path.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to write to, which is created if need be.
+    /// * `name` - The name of the fixture, without extension.
+    /// * `comment` - A description of the mismatch.
+    pub fn save(&self, dir: &Path, name: &str, comment: &str) -> io::Result<PathBuf> {
+        let path = dir.join(format!("{}.txt", name));
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&path, self.to_fixture(comment))?;
+        Ok(path)
+    }
+}
+
+impl FromStr for Case {
+    type Err = String;
+
+    /// Parses a fixture written by `to_fixture`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut case = Case {
+            program: Vec::new(),
+            inputs: Vec::new(),
+        };
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, values) = line
+                .split_once(':')
+                .ok_or_else(|| format!("Expected `key: values`, found {:?}", line))?;
+            let values = values
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(Data::from_str)
+                .collect::<Result<Vec<Data>, _>>()
+                .map_err(|e| format!("Bad value in {:?}: {}", line, e))?;
+
+            match key.trim() {
+                "program" => case.program = values,
+                "inputs" => case.inputs = values,
+                key => return Err(format!("Unknown key {:?}", key)),
+            }
+        }
+
+        Ok(case)
+    }
+}
+
+fn join(values: &[Data]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
@@ -0,0 +1,545 @@
+//! A stub for the GDB remote serial protocol, so gdb, or anything else that speaks it, can
+//! debug a machine.
+//!
+//! Memory is mapped into a byte addressed space with each cell taking 8 bytes, little endian,
+//! so cell `n` is at address `8 * n`. The target has two 64 bit registers, `ip` and `base`,
+//! which hold byte addresses in the same space: `x/gd $pc` reads the next instruction's word and
+//! `x/gd $base` the cell the relative base points at.
+//!
+//! Supported are reading and writing registers and memory, software and hardware breakpoints
+//! (which are the same thing here), single stepping, continuing, interrupting with Ctrl-C and
+//! restarting. The program's output is printed on gdb's console. Input comes from a queue,
+//! which the `monitor input <values>` command adds to. A program that needs input when the queue
+//! is empty stops with `SIGTTIN`, and one that hits an invalid instruction with `SIGILL`.
+
+use crate::{Data, Machine};
+use std::collections::{BTreeSet, VecDeque};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::panic::{self, AssertUnwindSafe};
+
+/// The size of a cell in the target's address space.
+pub const CELL: usize = 8;
+
+/// The target description, which names the registers.
+pub const TARGET_XML: &str = "<?xml version=\"1.0\"?>\
+<!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+<target version=\"1.0\">\
+<feature name=\"org.intcode.core\">\
+<reg name=\"ip\" bitsize=\"64\" type=\"code_ptr\" regnum=\"0\"/>\
+<reg name=\"base\" bitsize=\"64\" type=\"data_ptr\" regnum=\"1\"/>\
+</feature>\
+</target>";
+
+/// The number of cells gdb may write to, which stops a stray write from exhausting memory.
+const CELLS: usize = 1 << 24;
+
+/// The largest packet gdb may send.
+const PACKET_SIZE: usize = 0x4000;
+
+/// The number of instructions executed between checks for an interrupt while continuing.
+const CHECK: usize = 1024;
+
+/// The byte gdb sends to interrupt a running target.
+const INTERRUPT: u8 = 0x03;
+
+/// Why the machine stopped, as reported to gdb.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// Stepped, or hit a breakpoint.
+    Trap,
+    Interrupted,
+    /// The next instruction reads input and the queue is empty.
+    NeedsInput,
+    /// The next instruction is invalid.
+    Fault,
+    Halted,
+}
+
+impl Stop {
+    fn reply(self) -> String {
+        match self {
+            Stop::Trap => "S05".to_string(),
+            Stop::Interrupted => "S02".to_string(),
+            Stop::NeedsInput => "S15".to_string(),
+            Stop::Fault => "S04".to_string(),
+            Stop::Halted => "W00".to_string(),
+        }
+    }
+}
+
+/// A machine being debugged.
+pub struct Stub {
+    machine: Machine,
+    inputs: VecDeque<Data>,
+    /// The cells breakpoints are set on.
+    breakpoints: BTreeSet<usize>,
+    /// Every value the program has output.
+    pub outputs: Vec<Data>,
+    last: Stop,
+}
+
+impl Stub {
+    /// Creates a stub for a machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The machine to debug.
+    /// * `inputs` - The values to give the program when it reads input.
+    pub fn new(machine: Machine, inputs: Vec<Data>) -> Stub {
+        Stub {
+            machine,
+            inputs: inputs.into(),
+            breakpoints: BTreeSet::new(),
+            outputs: Vec::new(),
+            last: Stop::Trap,
+        }
+    }
+
+    /// Returns the machine being debugged.
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// Serves a single debugger until it detaches, kills the target or disconnects.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The connection to the debugger.
+    pub fn serve(&mut self, stream: TcpStream) -> io::Result<()> {
+        let mut connection = Connection {
+            stream,
+            pending: VecDeque::new(),
+            ack: true,
+        };
+
+        self.session(&mut connection)
+    }
+
+    fn session(&mut self, connection: &mut Connection) -> io::Result<()> {
+        while let Some(packet) = connection.receive()? {
+            let reply = match packet.as_str() {
+                "k" => return Ok(()),
+                "D" | "D;1" => {
+                    connection.send("OK")?;
+                    return Ok(());
+                }
+                "QStartNoAckMode" => {
+                    connection.send("OK")?;
+                    connection.ack = false;
+                    continue;
+                }
+                "R00" | "R" => {
+                    self.restart();
+                    continue;
+                }
+                _ => self.handle(&packet, connection)?,
+            };
+
+            connection.send(&reply)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the reply to a packet.
+    fn handle(&mut self, packet: &str, connection: &mut Connection) -> io::Result<String> {
+        let (command, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply = match command {
+            "?" => self.last.reply(),
+            "g" => self.registers().iter().map(|&r| hex_word(r)).collect(),
+            "G" => self.write_registers(args),
+            "p" => match parse_hex(args).and_then(|n| self.registers().get(n).copied()) {
+                Some(value) => hex_word(value),
+                None => "E01".to_string(),
+            },
+            "P" => self.write_register(args),
+            "m" => self.read_memory(args),
+            "M" => self.write_memory(args),
+            "Z" | "z" => self.breakpoint(command == "Z", args),
+            "c" => self.resume(false, connection)?.reply(),
+            "s" => self.resume(true, connection)?.reply(),
+            "H" | "T" => "OK".to_string(),
+            "q" | "v" => return self.query(packet, connection),
+            _ => String::new(),
+        };
+
+        Ok(reply)
+    }
+
+    /// Returns the reply to a `q` or `v` packet.
+    fn query(&mut self, packet: &str, connection: &mut Connection) -> io::Result<String> {
+        let reply = match packet {
+            _ if packet.starts_with("qSupported") => format!(
+                "PacketSize={:x};qXfer:features:read+;QStartNoAckMode+;vContSupported+",
+                PACKET_SIZE
+            ),
+            _ if packet.starts_with("qXfer:features:read:target.xml:") => transfer(
+                TARGET_XML,
+                &packet["qXfer:features:read:target.xml:".len()..],
+            ),
+            _ if packet.starts_with("qRcmd,") => {
+                let command = unhex(&packet["qRcmd,".len()..]).unwrap_or_default();
+                let text = self.monitor(&String::from_utf8_lossy(&command));
+                if !text.is_empty() {
+                    connection.send(&format!("O{}", hex(text.as_bytes())))?;
+                }
+                "OK".to_string()
+            }
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            "qOffsets" => "Text=0;Data=0;Bss=0".to_string(),
+            "vCont?" => "vCont;c;C;s;S".to_string(),
+            _ if packet.starts_with("vCont;") => {
+                // Only the first action matters, as there is a single thread.
+                let step = packet[6..].starts_with('s') || packet[6..].starts_with('S');
+                self.resume(step, connection)?.reply()
+            }
+            _ => String::new(),
+        };
+
+        Ok(reply)
+    }
+
+    /// Runs a `monitor` command and returns the text to print.
+    fn monitor(&mut self, command: &str) -> String {
+        let mut words = command.split_whitespace();
+        match (words.next(), words.collect::<Vec<&str>>().join(" ")) {
+            (Some("input"), values) => {
+                let parsed: Result<Vec<Data>, _> = values
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|v| !v.is_empty())
+                    .map(str::parse)
+                    .collect();
+                match parsed {
+                    Ok(values) => {
+                        self.inputs.extend(values);
+                        format!("{} values queued\n", self.inputs.len())
+                    }
+                    Err(e) => format!("Bad value: {}\n", e),
+                }
+            }
+            (Some("outputs"), _) => format!("{:?}\n", self.outputs),
+            (Some("reset"), _) => {
+                self.restart();
+                "Machine reset\n".to_string()
+            }
+            _ => "Commands: input <values>, outputs, reset\n".to_string(),
+        }
+    }
+
+    fn restart(&mut self) {
+        self.machine.reset();
+        self.outputs.clear();
+        self.last = Stop::Trap;
+    }
+
+    /// Returns the registers as gdb sees them.
+    fn registers(&self) -> [u64; 2] {
+        [
+            self.machine.ip().wrapping_mul(CELL) as u64,
+            self.machine.base().wrapping_mul(CELL as Data) as u64,
+        ]
+    }
+
+    fn write_registers(&mut self, args: &str) -> String {
+        // Packets are decoded lossily, so may hold characters that the halves would split.
+        if args.len() != 32 || !args.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return "E01".to_string();
+        }
+
+        let values = (parse_word(&args[..16]), parse_word(&args[16..]));
+        match values {
+            (Some(ip), Some(base)) if self.set_register(0, ip) && self.set_register(1, base) => {
+                "OK".to_string()
+            }
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let parsed = args
+            .split_once('=')
+            .and_then(|(n, value)| Some((parse_hex(n)?, parse_word(value)?)));
+        match parsed {
+            Some((n, value)) if self.set_register(n, value) => "OK".to_string(),
+            _ => "E01".to_string(),
+        }
+    }
+
+    /// Sets a register to a byte address, which must fall on a cell. Returns whether it did.
+    fn set_register(&mut self, n: usize, value: u64) -> bool {
+        let value = value as Data;
+        if value % CELL as Data != 0 {
+            return false;
+        }
+
+        match n {
+            0 if value >= 0 => self.machine.set_ip(value as usize / CELL),
+            1 => self.machine.set_base(value / CELL as Data),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn read_memory(&self, args: &str) -> String {
+        match parse_range(args) {
+            Some((addr, len)) if len <= PACKET_SIZE / 2 => hex(&(addr..addr + len)
+                .map(|a| self.byte(a))
+                .collect::<Vec<u8>>()),
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let parsed = args.split_once(':').and_then(|(range, data)| {
+            let (addr, len) = parse_range(range)?;
+            Some((addr, unhex(data).filter(|d| d.len() == len)?))
+        });
+
+        match parsed {
+            Some((addr, data)) if addr + data.len() <= CELLS * CELL => {
+                for (a, byte) in (addr..).zip(data) {
+                    self.set_byte(a, byte);
+                }
+                "OK".to_string()
+            }
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn byte(&self, addr: usize) -> u8 {
+        (self.machine.read(addr / CELL) as u64 >> (8 * (addr % CELL))) as u8
+    }
+
+    fn set_byte(&mut self, addr: usize, byte: u8) {
+        let shift = 8 * (addr % CELL);
+        let cell = self.machine.read(addr / CELL) as u64;
+        let value = cell & !(0xff << shift) | (byte as u64) << shift;
+        self.machine.set_force(addr / CELL, value as Data);
+    }
+
+    fn breakpoint(&mut self, set: bool, args: &str) -> String {
+        let mut fields = args.split(',');
+        let kind = fields.next();
+        let addr = fields.next().and_then(parse_hex);
+        match (kind, addr) {
+            (Some("0"), Some(addr)) | (Some("1"), Some(addr)) if addr % CELL == 0 => {
+                if set {
+                    self.breakpoints.insert(addr / CELL);
+                } else {
+                    self.breakpoints.remove(&(addr / CELL));
+                }
+                "OK".to_string()
+            }
+            (Some("0"), Some(_)) | (Some("1"), Some(_)) => "E01".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Runs the machine until it stops, sending its output to the debugger as it goes.
+    ///
+    /// # Arguments
+    ///
+    /// * `step` - Whether to execute a single instruction.
+    /// * `connection` - The connection to the debugger.
+    fn resume(&mut self, step: bool, connection: &mut Connection) -> io::Result<Stop> {
+        let mut count = 0;
+        let stop = loop {
+            // Execution resumes from a breakpoint by executing the instruction there.
+            if count > 0 && self.breakpoints.contains(&self.machine.ip()) {
+                break Stop::Trap;
+            }
+            if count % CHECK == CHECK - 1 && connection.interrupted()? {
+                break Stop::Interrupted;
+            }
+            if self.machine.is_awaiting_input() && self.inputs.is_empty() {
+                break Stop::NeedsInput;
+            }
+
+            let mut output = Vec::new();
+            let (machine, inputs) = (&mut self.machine, &mut self.inputs);
+            // A fault stops the machine and is reported to the debugger.
+            let halted =
+                panic::catch_unwind(AssertUnwindSafe(|| machine.step(inputs, &mut output)));
+            for value in output {
+                self.outputs.push(value);
+                connection.send(&format!("O{}", hex(format!("{}\n", value).as_bytes())))?;
+            }
+
+            count += 1;
+            match halted {
+                Ok(true) => break Stop::Halted,
+                Err(_) => break Stop::Fault,
+                Ok(false) if step => break Stop::Trap,
+                Ok(false) => {}
+            }
+        };
+
+        self.last = stop;
+        Ok(stop)
+    }
+}
+
+/// A connection to a debugger.
+struct Connection {
+    stream: TcpStream,
+    /// Bytes read but not yet used.
+    pending: VecDeque<u8>,
+    /// Whether packets are acknowledged.
+    ack: bool,
+}
+
+impl Connection {
+    /// Returns the next byte, or `None` if the debugger disconnected.
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        if self.pending.is_empty() {
+            let mut buffer = [0; 4096];
+            let read = self.stream.read(&mut buffer)?;
+            self.pending.extend(&buffer[..read]);
+        }
+
+        Ok(self.pending.pop_front())
+    }
+
+    /// Receives the next packet, or `None` if the debugger disconnected.
+    fn receive(&mut self) -> io::Result<Option<String>> {
+        loop {
+            // Anything outside a packet, such as acknowledgements and stray interrupts, is
+            // ignored.
+            match self.byte()? {
+                Some(b'$') => {}
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+
+            let mut data = Vec::new();
+            loop {
+                match self.byte()? {
+                    Some(b'#') => break,
+                    Some(b) => data.push(b),
+                    None => return Ok(None),
+                }
+            }
+
+            let mut checksum = [0; 2];
+            for digit in checksum.iter_mut() {
+                *digit = self.byte()?.unwrap_or(0);
+            }
+
+            let valid = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                == Some(sum(&data));
+            if self.ack {
+                self.stream.write_all(if valid { b"+" } else { b"-" })?;
+            }
+
+            if valid || !self.ack {
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+        }
+    }
+
+    /// Sends a packet, resending it until it is acknowledged.
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, sum(data.as_bytes()));
+        loop {
+            self.stream.write_all(packet.as_bytes())?;
+            if !self.ack {
+                return Ok(());
+            }
+
+            loop {
+                match self.byte()? {
+                    Some(b'+') => return Ok(()),
+                    Some(b'-') => break,
+                    Some(_) => {}
+                    None => return Err(ErrorKind::UnexpectedEof.into()),
+                }
+            }
+        }
+    }
+
+    /// Returns whether the debugger asked to interrupt the target, without waiting.
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut buffer = [0; 4096];
+        let read = self.stream.read(&mut buffer);
+        self.stream.set_nonblocking(false)?;
+
+        match read {
+            Ok(read) => self.pending.extend(&buffer[..read]),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+
+        match self.pending.iter().position(|&b| b == INTERRUPT) {
+            Some(n) => {
+                self.pending.remove(n);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Returns the reply to a `qXfer` read of a document at `offset,length`.
+fn transfer(document: &str, args: &str) -> String {
+    match parse_range(args) {
+        Some((offset, _)) if offset >= document.len() => "l".to_string(),
+        Some((offset, length)) if offset + length >= document.len() => {
+            format!("l{}", &document[offset..])
+        }
+        Some((offset, length)) => format!("m{}", &document[offset..offset + length]),
+        None => "E01".to_string(),
+    }
+}
+
+/// Returns the checksum of a packet's data.
+fn sum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, &b| sum.wrapping_add(b))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Formats a register value as the target's little endian bytes.
+fn hex_word(value: u64) -> String {
+    hex(&value.to_le_bytes())
+}
+
+/// Parses a register value sent as little endian bytes.
+fn parse_word(text: &str) -> Option<u64> {
+    let mut bytes = [0; 8];
+    let parsed = unhex(text).filter(|b| b.len() == 8)?;
+    bytes.copy_from_slice(&parsed);
+    Some(u64::from_le_bytes(bytes))
+}
+
+fn parse_hex(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 16).ok()
+}
+
+/// Parses an `addr,length` pair.
+fn parse_range(text: &str) -> Option<(usize, usize)> {
+    let (addr, len) = text.split_once(',')?;
+    let (addr, len) = (parse_hex(addr)?, parse_hex(len)?);
+    addr.checked_add(len)?;
+    Some((addr, len))
+}
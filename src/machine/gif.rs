@@ -0,0 +1,146 @@
+//! A minimal animated GIF encoder, for recording what programs draw.
+//!
+//! Frames are given as palette indices and encoded with LZW, but without ever building up a
+//! dictionary: a clear code is sent before the table would need wider codes, so every pixel is
+//! written as a literal of a fixed width. Images come out larger than a full encoder would make
+//! them, though still smaller than the raw pixels, and any GIF decoder reads them.
+
+/// An animated GIF being encoded.
+#[derive(Clone, Debug)]
+pub struct Gif {
+    width: usize,
+    height: usize,
+    /// The bits in the smallest code, and so in a palette index.
+    bits: u32,
+    /// The delay after each frame, in hundredths of a second.
+    delay: u16,
+    data: Vec<u8>,
+    frames: usize,
+}
+
+impl Gif {
+    /// Starts an animation which loops forever.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of each frame, in pixels, up to 65535.
+    /// * `height` - The height of each frame, in pixels, up to 65535.
+    /// * `palette` - The RGB colors pixels may take, at most 256.
+    /// * `delay` - The delay after each frame, in hundredths of a second.
+    pub fn new(width: usize, height: usize, palette: &[[u8; 3]], delay: u16) -> Gif {
+        assert!(width <= 0xffff && height <= 0xffff, "Image too large");
+        assert!(
+            !palette.is_empty() && palette.len() <= 256,
+            "Palette must have 1 to 256 colors"
+        );
+
+        // The color table must have a power of two entries, and codes at least two bits.
+        let bits = (usize::BITS - (palette.len() - 1).leading_zeros()).max(2);
+
+        let mut data = b"GIF89a".to_vec();
+        data.extend_from_slice(&(width as u16).to_le_bytes());
+        data.extend_from_slice(&(height as u16).to_le_bytes());
+        data.extend_from_slice(&[0x80 | (bits as u8 - 1) << 4 | (bits as u8 - 1), 0, 0]);
+        for n in 0..1 << bits {
+            data.extend_from_slice(&palette.get(n).copied().unwrap_or([0; 3]));
+        }
+
+        // Loops forever.
+        data.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        Gif {
+            width,
+            height,
+            bits,
+            delay,
+            data,
+            frames: 0,
+        }
+    }
+
+    /// Adds a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - The palette index of each pixel, row by row from the top left.
+    pub fn add_frame(&mut self, pixels: &[u8]) {
+        assert_eq!(
+            pixels.len(),
+            self.width * self.height,
+            "Frame has the wrong number of pixels"
+        );
+
+        let delay = self.delay.to_le_bytes();
+        self.data
+            .extend_from_slice(&[0x21, 0xf9, 0x04, 0x00, delay[0], delay[1], 0x00, 0x00]);
+        self.data.extend_from_slice(&[0x2c, 0, 0, 0, 0]);
+        self.data
+            .extend_from_slice(&(self.width as u16).to_le_bytes());
+        self.data
+            .extend_from_slice(&(self.height as u16).to_le_bytes());
+        self.data.push(0);
+
+        self.data.push(self.bits as u8);
+        let codes = self.codes(pixels);
+        for block in codes.chunks(255) {
+            self.data.push(block.len() as u8);
+            self.data.extend_from_slice(block);
+        }
+        self.data.push(0);
+
+        self.frames += 1;
+    }
+
+    /// Returns the width and height of the frames.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the number of frames added.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns the encoded animation.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.data.push(0x3b);
+        self.data
+    }
+
+    /// Encodes the pixels of a frame as LZW codes packed into bytes.
+    fn codes(&self, pixels: &[u8]) -> Vec<u8> {
+        let clear = 1 << self.bits;
+        let width = self.bits + 1;
+        // After a clear, each literal but the first adds a table entry, and codes widen once
+        // the next entry would need another bit.
+        let run = clear as usize - 2;
+
+        let mut bytes = Vec::new();
+        let mut buffer: u32 = 0;
+        let mut filled = 0;
+        let mut emit = |code: u32| {
+            buffer |= code << filled;
+            filled += width;
+            while filled >= 8 {
+                bytes.push(buffer as u8);
+                buffer >>= 8;
+                filled -= 8;
+            }
+        };
+
+        for chunk in pixels.chunks(run) {
+            emit(clear);
+            for pixel in chunk {
+                assert!(u32::from(*pixel) < clear, "Pixel outside the palette");
+                emit(u32::from(*pixel));
+            }
+        }
+        emit(clear + 1);
+
+        if filled > 0 {
+            bytes.push(buffer as u8);
+        }
+
+        bytes
+    }
+}
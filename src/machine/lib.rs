@@ -1,16 +1,113 @@
+pub mod arcade;
+pub mod batch;
+pub mod canvas;
+pub mod combinatorics;
+pub mod compile;
+pub mod conformance;
+pub mod dap;
+pub mod decompile;
+pub mod disassemble;
+pub mod explore;
+pub mod frames;
+pub mod fuzz;
+pub mod gdb;
+pub mod gif;
+pub mod json;
+pub mod loader;
+pub mod memory;
+pub mod optimize;
+pub mod record;
+pub mod reference;
+pub mod robot;
+pub mod search;
+pub mod solver;
+pub mod symbolic;
+
+use memory::{Memory, PAGE};
+use std::any::Any;
+use std::collections::VecDeque;
 use std::error::Error;
-use std::fs::File;
-use std::io::Write;
-use std::str::FromStr;
+use std::ops::Range;
 use std::sync::mpsc::{Receiver, Sender};
 
 type Data = i64;
 
+/// A machine running an Intcode program. Clones share memory until they write to it, so
+/// forking a machine is cheap.
 #[derive(Clone)]
 pub struct Machine {
-    memory: Vec<Option<Data>>,
+    memory: Memory,
     ip: usize,
     base: Data,
+    /// The memory the machine was created with, whose pages are shared with clones and resets.
+    initial: Memory,
+}
+
+/// A cell whose value differs between two machines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Difference {
+    pub addr: usize,
+    pub left: Data,
+    pub right: Data,
+}
+
+/// The reason `Machine::run_until_input`, `Machine::run_polled` or `Machine::execute_observed`
+/// returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Halted,
+    /// The next instruction reads input.
+    AwaitingInput,
+    /// An observer paused execution.
+    Paused,
+}
+
+/// Whether execution goes on after an observer is called.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    /// Stop once the current instruction completes.
+    Pause,
+}
+
+/// Callbacks made as a machine executes, each with the address of the instruction concerned and,
+/// except for `on_input_request`, after that instruction has taken effect. All of them do nothing
+/// by default.
+pub trait Observer {
+    /// Called when an instruction is about to read input, before the input source is asked for
+    /// a value, so before any wait for one. Pausing here stops the machine before the
+    /// instruction, which runs, calling this again, once execution resumes.
+    fn on_input_request(&mut self, _ip: usize) -> Control {
+        Control::Continue
+    }
+
+    /// Called when an instruction reads input, with the value read.
+    fn on_input(&mut self, _ip: usize, _value: Data) -> Control {
+        Control::Continue
+    }
+
+    /// Called when an instruction writes output, with the value written.
+    fn on_output(&mut self, _ip: usize, _value: Data) -> Control {
+        Control::Continue
+    }
+
+    /// Called when the relative base is adjusted, with its new value.
+    fn on_base(&mut self, _ip: usize, _base: Data) -> Control {
+        Control::Continue
+    }
+
+    /// Called when a jump is taken.
+    fn on_jump(&mut self, _ip: usize, _target: usize) -> Control {
+        Control::Continue
+    }
+
+    /// Called when the machine halts.
+    fn on_halt(&mut self, _ip: usize) {}
+
+    /// Called after every instruction other than a halt, following any other callback for it.
+    fn on_step(&mut self, _ip: usize) -> Control {
+        Control::Continue
+    }
 }
 
 pub trait Input {
@@ -21,26 +118,71 @@ pub trait Output {
     fn write(&mut self, val: Data);
 }
 
+/// An input source which may have no data yet, for use with `Machine::run_polled` or, through
+/// `with_default`, as an `Input`.
+pub trait PollInput {
+    /// Returns the next value without blocking, or `None` if there is none yet.
+    fn poll(&mut self) -> Option<Data>;
+
+    /// Wraps the source in an `Input` which reads `default` whenever there is no data.
+    ///
+    /// # Arguments
+    ///
+    /// * `default` - The value to read in place of missing data, such as -1.
+    fn with_default(self, default: Data) -> WithDefault<Self>
+    where
+        Self: Sized,
+    {
+        WithDefault {
+            source: self,
+            default,
+        }
+    }
+}
+
+/// An input which substitutes a default value when its source has no data.
+#[derive(Clone, Debug)]
+pub struct WithDefault<P> {
+    pub source: P,
+    pub default: Data,
+}
+
 impl Machine {
     pub fn new(program: &str) -> Result<Machine, Box<dyn Error>> {
-        let memory = program
-            .split(',')
-            .map(|s| i64::from_str(s))
-            .collect::<Result<Vec<Data>, _>>()?;
+        let memory = loader::parse_program(program)?;
+        Ok(Machine::from_memory(&memory))
+    }
 
-        Ok(Machine {
-            memory: memory.iter().map(|v| Some(*v)).collect(),
+    /// Creates a machine from an already parsed program.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory` - The initial memory.
+    pub fn from_memory(memory: &[Data]) -> Machine {
+        let initial = Memory::from_values(memory);
+        Machine {
+            memory: initial.clone(),
             ip: 0,
             base: 0,
-        })
+            initial,
+        }
     }
 
-    pub fn set_force(&mut self, addr: usize, val: Data) {
-        if self.memory.len() <= addr {
-            self.memory.resize(addr + 1, None);
-        }
+    /// Returns a copy of the memory the machine was created with.
+    pub fn program(&self) -> Vec<Data> {
+        // The initial memory holds every cell of the program, and nothing else.
+        self.initial.cells().map(|(_, value)| value).collect()
+    }
 
-        self.memory[addr] = Some(val);
+    /// Restores the memory and registers the machine was created with.
+    pub fn reset(&mut self) {
+        self.memory = self.initial.clone();
+        self.ip = 0;
+        self.base = 0;
+    }
+
+    pub fn set_force(&mut self, addr: usize, val: Data) {
+        self.memory.set(addr, val);
     }
 
     /// Reads a value at a given address.
@@ -49,8 +191,7 @@ impl Machine {
     ///
     /// * `addr` - The address to read at.
     pub fn read(&self, addr: usize) -> Data {
-        let value = self.memory.get(addr);
-        value.map_or(0, |opt| opt.unwrap_or(0))
+        self.memory.get(addr).unwrap_or(0)
     }
 
     /// Runs the machine to completion and returns the output.
@@ -65,33 +206,235 @@ impl Machine {
         }
     }
 
+    /// Runs the machine for at most `limit` steps and returns whether it halted.
+    ///
+    /// * `input` - The input data source.
+    /// * `output` - The output data sink.
+    /// * `limit` - The maximum number of steps, counting the final halt as one.
+    pub fn execute_for<I: Input, O: Output>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+        limit: usize,
+    ) -> bool {
+        for _ in 0..limit {
+            if self.step(input, output) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Runs the machine until it halts or its next instruction reads input. Nothing is read, so
+    /// the caller may supply the value however it likes, e.g. with `step`.
+    ///
+    /// * `output` - The output data sink.
+    pub fn run_until_input<O: Output>(&mut self, output: &mut O) -> Status {
+        loop {
+            if self.is_awaiting_input() {
+                return Status::AwaitingInput;
+            }
+
+            if self.step(&mut NoInput, output) {
+                return Status::Halted;
+            }
+        }
+    }
+
+    /// Runs the machine until it halts or its next instruction reads input which is not yet
+    /// available. Returning `AwaitingInput` leaves the machine ready to resume from that
+    /// instruction once there is data.
+    ///
+    /// * `input` - The input data source.
+    /// * `output` - The output data sink.
+    pub fn run_polled<P: PollInput + ?Sized, O: Output>(
+        &mut self,
+        input: &mut P,
+        output: &mut O,
+    ) -> Status {
+        loop {
+            match self.run_until_input(output) {
+                Status::AwaitingInput => match input.poll() {
+                    Some(mut value) => {
+                        self.step(&mut value, output);
+                    }
+                    None => return Status::AwaitingInput,
+                },
+                status => return status,
+            }
+        }
+    }
+
+    /// Returns whether the next instruction reads input.
+    pub fn is_awaiting_input(&self) -> bool {
+        self.opcode() == 3
+    }
+
+    /// Returns the address the next instruction writes to, or `None` if it writes nothing or
+    /// the parameter it writes through is invalid.
+    pub fn write_address(&self) -> Option<usize> {
+        let offset = match self.opcode() {
+            1 | 2 | 7 | 8 => 3,
+            3 => 1,
+            _ => return None,
+        };
+
+        self.locate(offset).ok()
+    }
+
+    /// Returns the instruction pointer.
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    /// Moves the instruction pointer.
+    ///
+    /// # Arguments
+    ///
+    /// * `ip` - The address of the next instruction to execute.
+    pub fn set_ip(&mut self, ip: usize) {
+        self.ip = ip;
+    }
+
+    /// Returns the relative base.
+    pub fn base(&self) -> Data {
+        self.base
+    }
+
+    /// Sets the relative base.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The new relative base.
+    pub fn set_base(&mut self, base: Data) {
+        self.base = base;
+    }
+
+    /// Returns the number of cells up to and including the highest one initialized.
+    pub fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Returns whether no cell is initialized.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the values in a range of addresses. Cells that were never written read as 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The addresses to read.
+    pub fn read_range(&self, range: Range<usize>) -> Vec<Data> {
+        range.map(|addr| self.read(addr)).collect()
+    }
+
+    /// Iterates over the initialized cells, as pairs of address and value, in address order.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, Data)> + '_ {
+        self.memory.cells()
+    }
+
+    /// Returns the number of memory pages shared with another machine, such as one it was
+    /// cloned from, which neither has written to since.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The machine to compare with.
+    pub fn shared_pages(&self, other: &Machine) -> usize {
+        self.memory.shared_pages(&other.memory)
+    }
+
+    /// Returns the cells whose values differ from those of another machine, in address order.
+    /// Cells that were never written count as 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The machine to compare with, whose values are reported as `right`.
+    pub fn diff(&self, other: &Machine) -> Vec<Difference> {
+        // Shared pages cannot differ, so are skipped whole.
+        (0..self.memory.extent().max(other.memory.extent()))
+            .step_by(PAGE)
+            .filter(|&start| !self.memory.same_page(&other.memory, start))
+            .flat_map(|start| start..start + PAGE)
+            .filter_map(|addr| {
+                let (left, right) = (self.read(addr), other.read(addr));
+                (left != right).then_some(Difference { addr, left, right })
+            })
+            .collect()
+    }
+
     /// Executes a single step of the machine and returns whether the machine halted.
     ///
     /// * `input` - The input data source.
     /// * `output` - The output data sink.
-    fn step<I: Input, O: Output>(&mut self, input: &mut I, output: &mut O) -> bool {
-        let mut stop = false;
+    pub fn step<I: Input, O: Output>(&mut self, input: &mut I, output: &mut O) -> bool {
+        self.step_observed(input, output, &mut ()) == Some(Status::Halted)
+    }
+
+    /// Runs the machine until it halts or an observer pauses it.
+    ///
+    /// * `input` - The input data source.
+    /// * `output` - The output data sink.
+    /// * `observer` - The observer to call as the machine executes.
+    pub fn execute_observed<I: Input, O: Output, B: Observer + ?Sized>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+        observer: &mut B,
+    ) -> Status {
+        loop {
+            if let Some(status) = self.step_observed(input, output, observer) {
+                return status;
+            }
+        }
+    }
+
+    /// Executes a single step of the machine, calling an observer for what it does. Returns
+    /// `Halted` if the machine halted, `Paused` if the observer paused it, and `None` otherwise.
+    ///
+    /// * `input` - The input data source.
+    /// * `output` - The output data sink.
+    /// * `observer` - The observer to call.
+    pub fn step_observed<I: Input, O: Output, B: Observer + ?Sized>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+        observer: &mut B,
+    ) -> Option<Status> {
+        let ip = self.ip;
+        let mut control = Control::Continue;
 
         match self.opcode() {
             1 => {
-                self.write(3, self.param(1) + self.param(2));
+                let sum = self.param(1).checked_add(self.param(2));
+                self.write(3, sum.expect("Arithmetic overflow"));
                 self.ip += 4;
             }
             2 => {
-                self.write(3, self.param(1) * self.param(2));
+                let product = self.param(1).checked_mul(self.param(2));
+                self.write(3, product.expect("Arithmetic overflow"));
                 self.ip += 4;
             }
             3 => {
-                self.write(1, input.get());
+                if observer.on_input_request(ip) == Control::Pause {
+                    return Some(Status::Paused);
+                }
+                let value = input.get();
+                self.write(1, value);
                 self.ip += 2;
+                control = observer.on_input(ip, value);
             }
             4 => {
-                output.write(self.param(1));
+                let value = self.param(1);
+                output.write(value);
                 self.ip += 2;
+                control = observer.on_output(ip, value);
             }
             5 => {
                 if self.param(1) != 0 {
                     self.ip = self.param(2) as usize;
+                    control = observer.on_jump(ip, self.ip);
                 } else {
                     self.ip += 3;
                 }
@@ -99,6 +442,7 @@ impl Machine {
             6 => {
                 if self.param(1) == 0 {
                     self.ip = self.param(2) as usize;
+                    control = observer.on_jump(ip, self.ip);
                 } else {
                     self.ip += 3;
                 }
@@ -114,14 +458,28 @@ impl Machine {
                 self.ip += 4;
             }
             9 => {
-                self.base += self.param(1);
+                self.base = self
+                    .base
+                    .checked_add(self.param(1))
+                    .expect("Arithmetic overflow");
                 self.ip += 2;
+                control = observer.on_base(ip, self.base);
+            }
+            99 => {
+                observer.on_halt(ip);
+                return Some(Status::Halted);
             }
-            99 => stop = true,
             _ => panic!("Unknown op code: {}", self.opcode()),
         }
 
-        stop
+        if observer.on_step(ip) == Control::Pause {
+            control = Control::Pause;
+        }
+
+        match control {
+            Control::Continue => None,
+            Control::Pause => Some(Status::Paused),
+        }
     }
 
     /// Gets the data associated with a given parameter.
@@ -130,13 +488,7 @@ impl Machine {
     ///
     /// * `offset` - The offset of the parameter.
     fn param(&self, offset: usize) -> Data {
-        let address = match self.mode(offset) {
-            Mode::Position => self.read(self.ip + offset),
-            Mode::Immediate => (self.ip + offset) as i64,
-            Mode::Relative => self.read(self.ip + offset) + self.base,
-        };
-
-        self.read(address as usize)
+        self.read(self.address(offset))
     }
 
     /// Writes a value using a parameter.
@@ -146,17 +498,40 @@ impl Machine {
     /// * `offset` - The offset of the parameter.
     /// * `value` - The value to write.
     fn write(&mut self, offset: usize, value: Data) {
-        let address = match self.mode(offset) {
+        let address = self.address(offset);
+        self.memory.set(address, value);
+    }
+
+    /// Returns the address a parameter refers to.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset of the parameter.
+    fn address(&self, offset: usize) -> usize {
+        self.locate(offset)
+            .unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    /// Returns the address a parameter refers to, or why it refers to none.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset of the parameter.
+    fn locate(&self, offset: usize) -> Result<usize, String> {
+        let address = match self.mode(offset)? {
             Mode::Position => self.read(self.ip + offset),
             Mode::Immediate => (self.ip + offset) as i64,
-            Mode::Relative => self.read(self.ip + offset) + self.base,
+            Mode::Relative => self
+                .read(self.ip + offset)
+                .checked_add(self.base)
+                .ok_or("Arithmetic overflow")?,
         };
 
-        if self.memory.len() <= address as usize {
-            self.memory.resize(address as usize + 1, None);
+        if address < 0 {
+            return Err(format!("Negative address: {}", address));
         }
 
-        self.memory[address as usize] = Some(value);
+        Ok(address as usize)
     }
 
     /// Returns the mode of the parameter specified at a given offset.
@@ -164,18 +539,18 @@ impl Machine {
     /// # Arguments
     ///
     /// * `offset` - The parameter offset to get the mode for.
-    fn mode(&self, offset: usize) -> Mode {
+    fn mode(&self, offset: usize) -> Result<Mode, String> {
         let op = self.read(self.ip);
         // Typically to get the N-th digit, we would do:
         //      num / (10 ^ n - 1) % 10
         // But we have a 2 digit offset, hence, offset = 1 => N = 3
-        let mode = op / (10 as i64).pow(offset as u32 + 1) % 10;
+        let mode = op / 10_i64.pow(offset as u32 + 1) % 10;
 
         match mode {
-            0 => Mode::Position,
-            1 => Mode::Immediate,
-            2 => Mode::Relative,
-            _ => panic!("Unknown mode encountered: {}", mode),
+            0 => Ok(Mode::Position),
+            1 => Ok(Mode::Immediate),
+            2 => Ok(Mode::Relative),
+            _ => Err(format!("Unknown mode encountered: {}", mode)),
         }
     }
 
@@ -185,6 +560,63 @@ impl Machine {
     }
 }
 
+/// Observes nothing.
+impl Observer for () {}
+
+impl<T: Observer + ?Sized> Observer for &mut T {
+    fn on_input_request(&mut self, ip: usize) -> Control {
+        (**self).on_input_request(ip)
+    }
+
+    fn on_input(&mut self, ip: usize, value: Data) -> Control {
+        (**self).on_input(ip, value)
+    }
+
+    fn on_output(&mut self, ip: usize, value: Data) -> Control {
+        (**self).on_output(ip, value)
+    }
+
+    fn on_base(&mut self, ip: usize, base: Data) -> Control {
+        (**self).on_base(ip, base)
+    }
+
+    fn on_jump(&mut self, ip: usize, target: usize) -> Control {
+        (**self).on_jump(ip, target)
+    }
+
+    fn on_halt(&mut self, ip: usize) {
+        (**self).on_halt(ip)
+    }
+
+    fn on_step(&mut self, ip: usize) -> Control {
+        (**self).on_step(ip)
+    }
+}
+
+/// Returns the message a panic was raised with, such as why a machine faulted.
+///
+/// # Arguments
+///
+/// * `payload` - The payload caught by `std::panic::catch_unwind`.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown fault")
+    }
+}
+
+/// An input source for `run_until_input`, which stops before any instruction reads.
+struct NoInput;
+
+impl Input for NoInput {
+    fn get(&mut self) -> Data {
+        unreachable!("Input read while running until input")
+    }
+}
+
 enum Mode {
     Immediate,
     Position,
@@ -205,7 +637,7 @@ impl Output for i64 {
 
 impl Output for Sender<i64> {
     fn write(&mut self, val: i64) {
-        self.send(val);
+        let _ = self.send(val);
     }
 }
 
@@ -220,3 +652,46 @@ impl Input for Receiver<i64> {
         self.recv().unwrap()
     }
 }
+
+impl Input for VecDeque<i64> {
+    fn get(&mut self) -> i64 {
+        self.pop_front().expect("Input queue is empty")
+    }
+}
+
+impl<P: PollInput> Input for WithDefault<P> {
+    fn get(&mut self) -> Data {
+        self.source.poll().unwrap_or(self.default)
+    }
+}
+
+impl PollInput for Receiver<i64> {
+    /// Returns `None` once the sender has gone as well as while there is no data.
+    fn poll(&mut self) -> Option<Data> {
+        self.try_recv().ok()
+    }
+}
+
+impl PollInput for VecDeque<i64> {
+    fn poll(&mut self) -> Option<Data> {
+        self.pop_front()
+    }
+}
+
+impl<T: PollInput + ?Sized> PollInput for &mut T {
+    fn poll(&mut self) -> Option<Data> {
+        (**self).poll()
+    }
+}
+
+impl<T: Input + ?Sized> Input for &mut T {
+    fn get(&mut self) -> i64 {
+        (**self).get()
+    }
+}
+
+impl<T: Output + ?Sized> Output for &mut T {
+    fn write(&mut self, val: i64) {
+        (**self).write(val)
+    }
+}
@@ -0,0 +1,295 @@
+//! Loading of Intcode programs from text.
+//!
+//! Values are separated by commas, and may be surrounded by any amount of whitespace. A `#`
+//! starts a comment which runs to the end of the line. A line break between two values that are
+//! not separated by a comma starts a new program, so a single file can hold several programs.
+
+use crate::Data;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// The size of the chunks read from a `Read` source.
+const CHUNK: usize = 64 * 1024;
+
+/// A syntax error, with the line and column at which it was found. Both are counted from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// An error loading programs from a source.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<ParseError> for LoadError {
+    fn from(e: ParseError) -> Self {
+        LoadError::Parse(e)
+    }
+}
+
+/// Parses every program in a string.
+pub fn parse(source: &str) -> Result<Vec<Vec<Data>>, ParseError> {
+    let mut parser = Parser::new();
+    parser.feed(source.as_bytes())?;
+    parser.finish()
+}
+
+/// Parses a string which must hold exactly one program.
+pub fn parse_program(source: &str) -> Result<Vec<Data>, ParseError> {
+    let mut parser = Parser::new();
+    parser.feed(source.as_bytes())?;
+    let end = (parser.line, parser.column);
+    let starts = parser.starts.clone();
+    let mut programs = parser.finish()?;
+    match programs.len() {
+        0 => Err(error(end, "Expected a program")),
+        1 => Ok(programs.remove(0)),
+        n => Err(error(
+            starts[1],
+            &format!("Expected a single program, found {}", n),
+        )),
+    }
+}
+
+/// Reads and parses every program from a source, without holding all of its text in memory.
+pub fn load<R: Read>(mut source: R) -> Result<Vec<Vec<Data>>, LoadError> {
+    let mut buffer = vec![0; CHUNK];
+    let mut parser = Parser::new();
+
+    loop {
+        match source.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => parser.feed(&buffer[..n])?,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(parser.finish()?)
+}
+
+/// Loads every program in a file. With the `mmap` feature, the file is memory-mapped rather
+/// than read.
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<Vec<Data>>, LoadError> {
+    let file = File::open(path)?;
+
+    #[cfg(feature = "mmap")]
+    {
+        // An empty file cannot be mapped, but holds no programs anyway.
+        if file.metadata()?.len() == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Safety: the map is only read while parsing, and dropped before returning. If another
+        // process truncates the file in the meantime, parsing may fault.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let mut parser = Parser::new();
+        parser.feed(&map)?;
+        Ok(parser.finish()?)
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    load(file)
+}
+
+/// An incremental parser, which accepts its input in chunks of any size.
+pub struct Parser {
+    programs: Vec<Vec<Data>>,
+    current: Vec<Data>,
+    /// The position of the first value of each program.
+    starts: Vec<(usize, usize)>,
+    token: Vec<u8>,
+    token_start: (usize, usize),
+    line: usize,
+    column: usize,
+    /// The position of a comma which has not yet been followed by a value.
+    pending_comma: Option<(usize, usize)>,
+    newline_since_value: bool,
+    in_comment: bool,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::new()
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Parser {
+            programs: Vec::new(),
+            current: Vec::new(),
+            starts: Vec::new(),
+            token: Vec::new(),
+            token_start: (1, 1),
+            line: 1,
+            column: 1,
+            pending_comma: None,
+            newline_since_value: false,
+            in_comment: false,
+        }
+    }
+
+    /// Parses the next chunk of input.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        for byte in bytes {
+            self.byte(*byte)?;
+        }
+
+        Ok(())
+    }
+
+    /// Finishes parsing and returns the programs.
+    pub fn finish(mut self) -> Result<Vec<Vec<Data>>, ParseError> {
+        self.end_token()?;
+
+        if let Some((line, column)) = self.pending_comma {
+            return Err(ParseError {
+                line,
+                column,
+                message: String::from("Expected a value after ','"),
+            });
+        }
+
+        if !self.current.is_empty() {
+            self.programs.push(self.current);
+        }
+
+        Ok(self.programs)
+    }
+
+    fn byte(&mut self, byte: u8) -> Result<(), ParseError> {
+        let position = (self.line, self.column);
+
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if byte & 0xc0 != 0x80 {
+            // Continuation bytes of a UTF-8 character do not start a new column.
+            self.column += 1;
+        }
+
+        if self.in_comment {
+            self.in_comment = byte != b'\n';
+            if !self.in_comment {
+                self.newline_since_value = true;
+            }
+
+            return Ok(());
+        }
+
+        match byte {
+            b',' => {
+                self.end_token()?;
+                if self.pending_comma.is_some() || self.current.is_empty() {
+                    return Err(error(position, "Expected a value before ','"));
+                }
+
+                self.pending_comma = Some(position);
+                self.newline_since_value = false;
+            }
+            b'#' => {
+                self.end_token()?;
+                self.in_comment = true;
+            }
+            b'\n' => {
+                self.end_token()?;
+                self.newline_since_value = true;
+            }
+            _ if byte.is_ascii_whitespace() => self.end_token()?,
+            _ => {
+                if self.token.is_empty() {
+                    self.start_token(position)?;
+                }
+
+                self.token.push(byte);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a value may start at the given position, starting a new program if the
+    /// previous value ended a line.
+    fn start_token(&mut self, position: (usize, usize)) -> Result<(), ParseError> {
+        if self.pending_comma.is_none() && !self.current.is_empty() {
+            if !self.newline_since_value {
+                return Err(error(position, "Expected ',' between values"));
+            }
+
+            self.programs.push(std::mem::take(&mut self.current));
+        }
+
+        if self.current.is_empty() {
+            self.starts.push(position);
+        }
+
+        self.token_start = position;
+        Ok(())
+    }
+
+    fn end_token(&mut self) -> Result<(), ParseError> {
+        if self.token.is_empty() {
+            return Ok(());
+        }
+
+        let text = String::from_utf8_lossy(&self.token);
+        let value = text.parse::<Data>().map_err(|e| {
+            error(
+                self.token_start,
+                &format!("Invalid value {:?}: {}", text, e),
+            )
+        })?;
+
+        self.current.push(value);
+        self.token.clear();
+        self.pending_comma = None;
+        self.newline_since_value = false;
+        Ok(())
+    }
+}
+
+fn error(position: (usize, usize), message: &str) -> ParseError {
+    ParseError {
+        line: position.0,
+        column: position.1,
+        message: message.to_string(),
+    }
+}
@@ -0,0 +1,95 @@
+//! Paged, copy-on-write memory.
+//!
+//! Memory is split into pages of `PAGE` cells, each shared between clones until one of them
+//! writes to it. Forking a machine therefore costs a pointer per page, and each fork only pays
+//! for copying the pages it changes.
+
+use crate::Data;
+use std::sync::Arc;
+
+/// The number of cells in a page.
+pub const PAGE: usize = 256;
+
+/// A page of cells. Cells that were never written are `None`.
+type Page = [Option<Data>; PAGE];
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Memory {
+    /// The pages, where `None` is a page that was never written.
+    pages: Vec<Option<Arc<Page>>>,
+}
+
+impl Memory {
+    pub(crate) fn from_values(values: &[Data]) -> Memory {
+        let pages = values
+            .chunks(PAGE)
+            .map(|chunk| {
+                let mut page = [None; PAGE];
+                for (cell, value) in page.iter_mut().zip(chunk) {
+                    *cell = Some(*value);
+                }
+                Some(Arc::new(page))
+            })
+            .collect();
+
+        Memory { pages }
+    }
+
+    /// Returns the value of a cell, if it was ever written.
+    pub(crate) fn get(&self, addr: usize) -> Option<Data> {
+        match self.pages.get(addr / PAGE) {
+            Some(Some(page)) => page[addr % PAGE],
+            _ => None,
+        }
+    }
+
+    /// Writes a cell, copying its page first if it is shared.
+    pub(crate) fn set(&mut self, addr: usize, value: Data) {
+        let index = addr / PAGE;
+        if self.pages.len() <= index {
+            self.pages.resize(index + 1, None);
+        }
+
+        let page = self.pages[index].get_or_insert_with(|| Arc::new([None; PAGE]));
+        Arc::make_mut(page)[addr % PAGE] = Some(value);
+    }
+
+    /// Returns the number of cells up to and including the highest one written.
+    pub(crate) fn len(&self) -> usize {
+        self.cells().next_back().map_or(0, |(addr, _)| addr + 1)
+    }
+
+    /// Returns the number of cells the pages cover.
+    pub(crate) fn extent(&self) -> usize {
+        self.pages.len() * PAGE
+    }
+
+    /// Iterates over the written cells, as pairs of address and value, in address order.
+    pub(crate) fn cells(&self) -> impl DoubleEndedIterator<Item = (usize, Data)> + '_ {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(n, page)| page.as_ref().map(|page| (n * PAGE, page)))
+            .flat_map(|(start, page)| {
+                page.iter()
+                    .enumerate()
+                    .filter_map(move |(i, cell)| cell.map(|v| (start + i, v)))
+            })
+    }
+
+    /// Returns whether the page holding `addr` is the same, shared, page in both memories.
+    pub(crate) fn same_page(&self, other: &Memory, addr: usize) -> bool {
+        let index = addr / PAGE;
+        match (self.pages.get(index), other.pages.get(index)) {
+            (Some(Some(a)), Some(Some(b))) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Returns the number of pages shared with another memory.
+    pub(crate) fn shared_pages(&self, other: &Memory) -> usize {
+        (0..self.pages.len())
+            .filter(|&n| self.same_page(other, n * PAGE))
+            .count()
+    }
+}
@@ -0,0 +1,400 @@
+//! An optimizer for Intcode programs.
+//!
+//! Intcode programs use addresses as data, so the optimizer never moves a cell. Instead it
+//! rewrites instructions in place:
+//!
+//! * Parameters that read a constant cell, one no instruction ever writes, become immediate.
+//! * Arithmetic and comparisons on constants are folded into a single store of the result.
+//! * Jumps whose conditions are constant take immediate operands, and jumps that are always
+//!   taken go straight to the final target of any jumps they land on.
+//! * Cells that are neither run, read nor written, such as code after an unconditional jump, are
+//!   cleared, and any trailing zeroes dropped.
+//!
+//! All of this relies on knowing every address the program reads, writes and jumps to. Programs
+//! that access memory through the relative base, compute addresses at run time, or rewrite their
+//! own instructions are left as they are.
+//!
+//! Only cells that hold code, or that nothing reads, ever change. The program's output, and the
+//! final values of the cells it uses as data, stay the same.
+
+use crate::compile::{decode, Instruction, Op, Operand};
+use crate::{panic_message, Data, Machine};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+
+/// The number of jumps followed when threading a jump, which stops chains that loop.
+const THREADING: usize = 16;
+
+/// What a program does with its memory, as far as can be told without running it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Analysis {
+    /// The reachable instructions, by address.
+    pub code: BTreeMap<usize, Instruction>,
+    /// The cells read as data.
+    pub read: BTreeSet<usize>,
+    /// The cells that may be written.
+    pub written: BTreeSet<usize>,
+}
+
+impl Analysis {
+    /// Returns whether a cell is never written, and so always holds its initial value.
+    pub fn is_constant(&self, addr: usize) -> bool {
+        !self.written.contains(&addr)
+    }
+
+    /// Returns the number of reachable instructions that include a cell.
+    fn uses(&self, addr: usize) -> usize {
+        self.code
+            .values()
+            .filter(|i| (i.addr..i.next()).contains(&addr))
+            .count()
+    }
+
+    /// Returns whether every cell of an instruction may be rewritten: none is read or
+    /// written as data, or shared with another instruction.
+    fn is_private(&self, instruction: &Instruction) -> bool {
+        (instruction.addr..instruction.next()).all(|cell| {
+            !self.read.contains(&cell) && !self.written.contains(&cell) && self.uses(cell) == 1
+        })
+    }
+}
+
+/// An optimized program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Optimized {
+    pub program: Vec<Data>,
+    /// The cells whose values differ from those of the original program.
+    pub changed: BTreeSet<usize>,
+}
+
+impl Optimized {
+    /// Formats the program in the form `Machine::new` reads.
+    pub fn source(&self) -> String {
+        self.program
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+/// Works out what a program does with its memory. Returns `None` if that depends on values
+/// only known at run time, or if the program rewrites its own code.
+///
+/// # Arguments
+///
+/// * `memory` - The program.
+pub fn analyze(memory: &[Data]) -> Option<Analysis> {
+    let cell = |addr: usize| memory.get(addr).copied().unwrap_or(0);
+    let mut written = BTreeSet::new();
+
+    // Each pass assumes the cells written so far are the only ones, until that holds.
+    loop {
+        let mut analysis = Analysis {
+            written: written.clone(),
+            ..Analysis::default()
+        };
+        let mut writes = BTreeSet::new();
+        let mut pending = vec![0];
+
+        while let Some(addr) = pending.pop() {
+            if analysis.code.contains_key(&addr) {
+                continue;
+            }
+            if written.contains(&addr) {
+                return None;
+            }
+
+            // Running into something other than an instruction stops the machine.
+            let instruction = match decode(memory, addr) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+            if (addr..instruction.next()).any(|c| written.contains(&c)) {
+                return None;
+            }
+
+            // Returns the address an operand refers to, if it is known.
+            let target = |operand: Operand| match operand {
+                Operand::Cell(slot) => Some(slot),
+                Operand::Position(slot) if cell(slot) >= 0 => Some(cell(slot) as usize),
+                _ => None,
+            };
+            let mut read = |operand: Operand| {
+                let addr = target(operand)?;
+                if let Operand::Position(_) = operand {
+                    analysis.read.insert(addr);
+                }
+                Some(addr)
+            };
+
+            let mut successors = vec![instruction.next()];
+            match instruction.op {
+                Op::Add(a, b, c)
+                | Op::Multiply(a, b, c)
+                | Op::Less(a, b, c)
+                | Op::Equal(a, b, c) => {
+                    read(a)?;
+                    read(b)?;
+                    writes.insert(target(c)?);
+                }
+                Op::Input(a) => {
+                    writes.insert(target(a)?);
+                }
+                Op::Output(a) | Op::AdjustBase(a) => {
+                    read(a)?;
+                }
+                Op::JumpIfTrue(a, b) | Op::JumpIfFalse(a, b) => {
+                    let condition = read(a)?;
+                    let destination = cell(read(b)?);
+                    if written.contains(&read(b)?) {
+                        return None;
+                    }
+
+                    let taken = (!written.contains(&condition)).then(|| {
+                        (cell(condition) != 0) == matches!(instruction.op, Op::JumpIfTrue(..))
+                    });
+                    if taken == Some(true) {
+                        successors.clear();
+                    }
+
+                    // A negative target stops the machine.
+                    if taken != Some(false) && destination >= 0 {
+                        successors.push(destination as usize);
+                    }
+                }
+                Op::Halt => successors.clear(),
+            }
+
+            pending.extend(successors);
+            analysis.code.insert(addr, instruction);
+        }
+
+        if writes.is_subset(&written) {
+            return Some(analysis);
+        }
+        written.extend(writes);
+    }
+}
+
+/// Optimizes a program. A program the optimizer cannot analyze comes back unchanged.
+///
+/// # Arguments
+///
+/// * `memory` - The program.
+pub fn optimize(memory: &[Data]) -> Optimized {
+    let passes: [fn(&mut [Data], &Analysis); 4] = [
+        fold_operands,
+        fold_arithmetic,
+        simplify_jumps,
+        remove_dead_cells,
+    ];
+
+    let mut program = memory.to_vec();
+    for pass in &passes {
+        match analyze(&program) {
+            Some(analysis) => pass(&mut program, &analysis),
+            None => break,
+        }
+    }
+
+    while program.last() == Some(&0) {
+        program.pop();
+    }
+
+    let len = memory.len().max(program.len());
+    let cell = |memory: &[Data], addr: usize| memory.get(addr).copied().unwrap_or(0);
+    Optimized {
+        changed: (0..len)
+            .filter(|&addr| cell(memory, addr) != cell(&program, addr))
+            .collect(),
+        program,
+    }
+}
+
+/// Makes parameters that read constant cells immediate.
+fn fold_operands(program: &mut [Data], analysis: &Analysis) {
+    for instruction in analysis.code.values() {
+        if !analysis.is_private(instruction) {
+            continue;
+        }
+
+        let (operands, count) = match instruction.op {
+            Op::Add(a, b, _) | Op::Multiply(a, b, _) | Op::Less(a, b, _) | Op::Equal(a, b, _) => {
+                ([Some(a), Some(b)], 2)
+            }
+            Op::JumpIfTrue(a, b) | Op::JumpIfFalse(a, b) => ([Some(a), Some(b)], 2),
+            Op::Output(a) | Op::AdjustBase(a) => ([Some(a), None], 1),
+            _ => continue,
+        };
+
+        for (n, operand) in operands.iter().take(count).enumerate() {
+            if let Some(Operand::Position(slot)) = *operand {
+                let addr = program[slot] as usize;
+                if analysis.is_constant(addr) {
+                    program[slot] = program.get(addr).copied().unwrap_or(0);
+                    program[instruction.addr] += 10_i64.pow(n as u32 + 2);
+                }
+            }
+        }
+    }
+}
+
+/// Replaces arithmetic and comparisons on immediate values with a store of the result.
+fn fold_arithmetic(program: &mut [Data], analysis: &Analysis) {
+    for instruction in analysis.code.values() {
+        let (a, b) = match instruction.op {
+            Op::Add(Operand::Cell(a), Operand::Cell(b), _)
+            | Op::Multiply(Operand::Cell(a), Operand::Cell(b), _)
+            | Op::Less(Operand::Cell(a), Operand::Cell(b), _)
+            | Op::Equal(Operand::Cell(a), Operand::Cell(b), _) => (program[a], program[b]),
+            _ => continue,
+        };
+
+        // Overflow stops the machine, so is left to happen.
+        let value = match instruction.op {
+            Op::Add(..) => a.checked_add(b),
+            Op::Multiply(..) => a.checked_mul(b),
+            Op::Less(..) => Some((a < b) as Data),
+            _ => Some((a == b) as Data),
+        };
+
+        let folded =
+            matches!(instruction.op, Op::Add(..)) && instruction.word % 10_000 == 1101 && b == 0;
+        if let (Some(value), false) = (value, folded) {
+            if analysis.is_private(instruction) {
+                let addr = instruction.addr;
+                program[addr] = instruction.word / 10_000 * 10_000 + 1101;
+                program[addr + 1] = value;
+                program[addr + 2] = 0;
+            }
+        }
+    }
+}
+
+/// Settles jumps on constant conditions, and sends jumps to jumps straight to their target.
+fn simplify_jumps(program: &mut [Data], analysis: &Analysis) {
+    // Returns the target of an unconditional jump to a constant address at `addr`.
+    let always = |program: &[Data], addr: usize| {
+        let instruction = analysis.code.get(&addr)?;
+        let (condition, target, if_true) = match instruction.op {
+            Op::JumpIfTrue(Operand::Cell(c), Operand::Cell(t)) => (c, t, true),
+            Op::JumpIfFalse(Operand::Cell(c), Operand::Cell(t)) => (c, t, false),
+            _ => return None,
+        };
+
+        // Slots past the end of a truncated program are unknown, not zero.
+        if (*program.get(condition)? != 0) != if_true {
+            return None;
+        }
+
+        program
+            .get(target)
+            .copied()
+            .filter(|&t| t >= 0)
+            .map(|t| t as usize)
+    };
+
+    for instruction in analysis.code.values() {
+        if !analysis.is_private(instruction) {
+            continue;
+        }
+
+        let addr = instruction.addr;
+        let target = match always(program, addr) {
+            Some(target) => target,
+            None => continue,
+        };
+
+        let mut last = target;
+        for _ in 0..THREADING {
+            match always(program, last) {
+                Some(next) if next != last => last = next,
+                _ => break,
+            }
+        }
+
+        program[addr] = 1105;
+        program[addr + 1] = 1;
+        program[addr + 2] = last as Data;
+    }
+}
+
+/// Clears cells that are neither run, read nor written.
+fn remove_dead_cells(program: &mut [Data], analysis: &Analysis) {
+    let live = |addr: usize| {
+        analysis.read.contains(&addr) || analysis.written.contains(&addr) || analysis.uses(addr) > 0
+    };
+
+    for (addr, value) in program.iter_mut().enumerate() {
+        if !live(addr) {
+            *value = 0;
+        }
+    }
+}
+
+/// Checks that an optimized program behaves as the original does on `Machine`: that it produces
+/// the same output for each set of inputs, halts or fails in the same way, and leaves the same
+/// values in every cell the optimizer did not change.
+///
+/// # Arguments
+///
+/// * `original` - The original program.
+/// * `optimized` - The optimized program.
+/// * `inputs` - The sets of inputs to run both with.
+/// * `budget` - The maximum number of instructions either may execute.
+pub fn check(
+    original: &[Data],
+    optimized: &Optimized,
+    inputs: &[Vec<Data>],
+    budget: usize,
+) -> Result<(), String> {
+    let run = |program: &[Data], inputs: &[Data]| {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut machine = Machine::from_memory(program);
+            let mut output = Vec::new();
+            let mut input: VecDeque<Data> = inputs.iter().copied().collect();
+            let halted = machine.execute_for(&mut input, &mut output, budget);
+            (machine, output, halted)
+        }))
+    };
+
+    inputs.iter().try_for_each(|inputs| {
+        match (run(original, inputs), run(&optimized.program, inputs)) {
+            (Err(_), Err(_)) => Ok(()),
+            (Ok(_), Err(payload)) => Err(format!(
+                "With inputs {:?}, only the optimized program failed: {}",
+                inputs,
+                panic_message(&*payload)
+            )),
+            (Err(payload), Ok(_)) => Err(format!(
+                "With inputs {:?}, only the original program failed: {}",
+                inputs,
+                panic_message(&*payload)
+            )),
+            (Ok((a, a_output, a_halted)), Ok((b, b_output, b_halted))) => {
+                if a_halted != b_halted {
+                    return Err(format!("With inputs {:?}, only one program halted", inputs));
+                }
+                if a_output != b_output {
+                    return Err(format!(
+                        "With inputs {:?}, outputs differ: {:?} and {:?}",
+                        inputs, a_output, b_output
+                    ));
+                }
+
+                match a
+                    .diff(&b)
+                    .into_iter()
+                    .find(|d| !optimized.changed.contains(&d.addr))
+                {
+                    Some(d) => Err(format!(
+                        "With inputs {:?}, memory differs at {}",
+                        inputs, d.addr
+                    )),
+                    None => Ok(()),
+                }
+            }
+        }
+    })
+}
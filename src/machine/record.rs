@@ -0,0 +1,480 @@
+//! Recording and replaying the values machines exchange.
+//!
+//! A `Recorder` collects the inputs, outputs and halts of any number of machines, each tagged
+//! with the machine and the number of instructions it had executed, in the order they happened.
+//! It wraps each machine's input and output in an ordinary `Input` and `Output`, and counts
+//! instructions and sees halts through an `Observer`, so machines run with
+//! `Machine::execute_observed` however they otherwise would, such as on separate threads joined
+//! by channels. Those share one recorder, so the session captures how their IO interleaved.
+//!
+//! A `Replay` feeds a session's inputs back to the same machines and panics as soon as one
+//! outputs a different value, or does anything at a different point, than it did when recorded.
+//! Each machine waits its turn, so the interleaving is the recorded one however the threads are
+//! scheduled.
+//!
+//! Sessions are saved as text, one event per line:
+//!
+//! ```text
+//! # intcode io session
+//! 0 2 in 5
+//! 0 7 out 10
+//! 0 9 halt
+//! ```
+
+use crate::{Control, Data, Input, Observer, Output};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// The first line of a saved session.
+const HEADER: &str = "# intcode io session";
+
+/// What happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Input(Data),
+    Output(Data),
+    Halt,
+}
+
+/// Something a machine did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// The machine, as numbered by whoever attached it.
+    pub machine: usize,
+    /// The number of instructions the machine executed before this one.
+    pub steps: usize,
+    pub kind: Kind,
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} ", self.machine, self.steps)?;
+        match self.kind {
+            Kind::Input(value) => write!(f, "in {}", value),
+            Kind::Output(value) => write!(f, "out {}", value),
+            Kind::Halt => write!(f, "halt"),
+        }
+    }
+}
+
+impl FromStr for Event {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let number = |n: usize| {
+            fields
+                .get(n)
+                .ok_or_else(|| format!("Missing field in {:?}", s))?
+                .parse::<Data>()
+                .map_err(|e| format!("Bad number in {:?}: {}", s, e))
+        };
+
+        let kind = match (fields.get(2), fields.len()) {
+            (Some(&"in"), 4) => Kind::Input(number(3)?),
+            (Some(&"out"), 4) => Kind::Output(number(3)?),
+            (Some(&"halt"), 3) => Kind::Halt,
+            _ => {
+                return Err(format!(
+                    "Expected <machine> <steps> in|out|halt [value], found {:?}",
+                    s
+                ))
+            }
+        };
+
+        let count = |n: usize| {
+            let value = number(n)?;
+            if value < 0 {
+                return Err(format!("Negative count in {:?}", s));
+            }
+            Ok(value as usize)
+        };
+
+        Ok(Event {
+            machine: count(0)?,
+            steps: count(1)?,
+            kind,
+        })
+    }
+}
+
+/// A recorded sequence of events.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Session {
+    pub events: Vec<Event>,
+}
+
+impl Session {
+    /// Returns the events of a single machine, which can then be replayed without the others.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The machine to keep.
+    pub fn only(&self, machine: usize) -> Session {
+        Session {
+            events: self
+                .events
+                .iter()
+                .filter(|e| e.machine == machine)
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Loads a session saved with `save`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to read.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Session, Box<dyn Error>> {
+        Ok(std::fs::read_to_string(path)?.parse()?)
+    }
+
+    /// Saves the session to a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        Ok(std::fs::write(path, self.to_string())?)
+    }
+}
+
+impl Display for Session {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Session {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(format!("Expected {:?} on the first line", HEADER));
+        }
+
+        let events = lines
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::parse)
+            .collect::<Result<Vec<Event>, String>>()?;
+        Ok(Session { events })
+    }
+}
+
+/// Collects the events of the machines attached to it. Clones share the same session.
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    /// Wraps a machine's input and output so that running it with the results records what it
+    /// does. The last of the three is the observer to run the machine with, which counts its
+    /// instructions and records its halt; without it, every event is taken to happen before the
+    /// first instruction.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The number to tag the machine's events with.
+    /// * `input` - The input data source.
+    /// * `output` - The output data sink.
+    pub fn attach<I: Input, O: Output>(
+        &self,
+        machine: usize,
+        input: I,
+        output: O,
+    ) -> (Recording<I>, Recording<O>, Recording<()>) {
+        let steps = Arc::new(AtomicUsize::new(0));
+        (
+            Recording {
+                recorder: self.clone(),
+                machine,
+                steps: steps.clone(),
+                inner: input,
+            },
+            Recording {
+                recorder: self.clone(),
+                machine,
+                steps: steps.clone(),
+                inner: output,
+            },
+            Recording {
+                recorder: self.clone(),
+                machine,
+                steps,
+                inner: (),
+            },
+        )
+    }
+
+    /// Returns the events recorded so far.
+    pub fn session(&self) -> Session {
+        Session {
+            events: self.events.lock().unwrap().clone(),
+        }
+    }
+
+    fn push(&self, machine: usize, steps: &AtomicUsize, kind: Kind) {
+        self.events.lock().unwrap().push(Event {
+            machine,
+            steps: steps.load(Ordering::Relaxed),
+            kind,
+        });
+    }
+}
+
+/// A machine's input or output, recording what passes through it, or with `()` the observer
+/// which counts its instructions and records its halt.
+pub struct Recording<T> {
+    recorder: Recorder,
+    machine: usize,
+    /// The number of instructions executed so far, shared by the input, output and observer.
+    steps: Arc<AtomicUsize>,
+    inner: T,
+}
+
+impl<T> Recording<T> {
+    /// Returns the wrapped input or output.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the input or output.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<I: Input> Input for Recording<I> {
+    fn get(&mut self) -> Data {
+        let value = self.inner.get();
+        self.recorder
+            .push(self.machine, &self.steps, Kind::Input(value));
+        value
+    }
+}
+
+impl<O: Output> Output for Recording<O> {
+    fn write(&mut self, val: Data) {
+        // Recorded before passing it on, so it precedes whatever the value causes.
+        self.recorder
+            .push(self.machine, &self.steps, Kind::Output(val));
+        self.inner.write(val);
+    }
+}
+
+impl Observer for Recording<()> {
+    fn on_halt(&mut self, _ip: usize) {
+        self.recorder.push(self.machine, &self.steps, Kind::Halt);
+    }
+
+    fn on_step(&mut self, _ip: usize) -> Control {
+        self.steps.fetch_add(1, Ordering::Relaxed);
+        Control::Continue
+    }
+}
+
+/// Where a replay has got to.
+#[derive(Debug)]
+struct Progress {
+    events: Vec<Event>,
+    next: usize,
+    /// The machines whose input and output have been dropped.
+    stopped: BTreeSet<usize>,
+    /// Why a machine diverged from the session, once one has.
+    failure: Option<String>,
+}
+
+/// Feeds a session back to the machines that recorded it. Clones share the same progress.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    progress: Arc<(Mutex<Progress>, Condvar)>,
+}
+
+impl Replay {
+    /// Creates a replay of a session.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The session to replay.
+    pub fn new(session: Session) -> Replay {
+        let progress = Progress {
+            events: session.events,
+            next: 0,
+            stopped: BTreeSet::new(),
+            failure: None,
+        };
+
+        Replay {
+            progress: Arc::new((Mutex::new(progress), Condvar::new())),
+        }
+    }
+
+    /// Returns the input, output and observer a machine should be run with to replay its part.
+    /// Once all three are dropped, the machine is taken to have stopped, and any machine waiting
+    /// for it to do something more panics rather than waiting forever.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The number the machine's events are tagged with.
+    pub fn attach(&self, machine: usize) -> (Replaying, Replaying, Replaying) {
+        let seat = Arc::new(Seat {
+            replay: self.clone(),
+            machine,
+            steps: AtomicUsize::new(0),
+        });
+        (
+            Replaying(seat.clone()),
+            Replaying(seat.clone()),
+            Replaying(seat),
+        )
+    }
+
+    /// Checks that every event was replayed.
+    pub fn finish(&self) -> Result<(), String> {
+        let progress = self.progress.0.lock().unwrap();
+        if let Some(failure) = &progress.failure {
+            return Err(failure.clone());
+        }
+
+        match progress.events.get(progress.next) {
+            Some(event) => Err(format!(
+                "Event {} was never replayed: {}",
+                progress.next, event
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Waits for the next event to be one of a machine's, checks it is the expected one and
+    /// moves past it. Returns the event, or panics if the machine diverged.
+    fn expect(
+        &self,
+        machine: usize,
+        steps: usize,
+        matches: impl Fn(Kind) -> bool,
+        what: &str,
+    ) -> Event {
+        let (lock, turn) = &*self.progress;
+        let mut progress = lock.lock().unwrap();
+
+        let failure = loop {
+            if let Some(failure) = &progress.failure {
+                let failure = format!("Replay abandoned: {}", failure);
+                drop(progress);
+                panic!("{}", failure);
+            }
+
+            match progress.events.get(progress.next).copied() {
+                Some(event) if event.machine != machine => {
+                    if progress.stopped.contains(&event.machine) {
+                        break format!(
+                            "Machine {} waited at step {} for event {}, but machine {} stopped: {}",
+                            machine, steps, progress.next, event.machine, event
+                        );
+                    }
+                    progress = turn.wait(progress).unwrap();
+                }
+                Some(event) if event.steps == steps && matches(event.kind) => {
+                    progress.next += 1;
+                    turn.notify_all();
+                    return event;
+                }
+                Some(event) => {
+                    break format!(
+                        "Machine {} at step {} did {}, but the session has {}",
+                        machine, steps, what, event
+                    )
+                }
+                None => {
+                    break format!(
+                        "Machine {} at step {} did {} after the session ended",
+                        machine, steps, what
+                    )
+                }
+            }
+        };
+
+        // Released before panicking, so the other machines can see why.
+        progress.failure = Some(failure.clone());
+        drop(progress);
+        turn.notify_all();
+        panic!("{}", failure);
+    }
+}
+
+/// A machine taking part in a replay, shared by its input, output and observer.
+#[derive(Debug)]
+struct Seat {
+    replay: Replay,
+    machine: usize,
+    /// The number of instructions the machine has executed.
+    steps: AtomicUsize,
+}
+
+impl Seat {
+    fn expect(&self, matches: impl Fn(Kind) -> bool, what: &str) -> Event {
+        let steps = self.steps.load(Ordering::Relaxed);
+        self.replay.expect(self.machine, steps, matches, what)
+    }
+}
+
+impl Drop for Seat {
+    fn drop(&mut self) {
+        let (lock, turn) = &*self.replay.progress;
+        // A machine which panicked may have poisoned the lock, but its failure is recorded.
+        if let Ok(mut progress) = lock.lock() {
+            progress.stopped.insert(self.machine);
+        }
+        turn.notify_all();
+    }
+}
+
+/// A machine's input, output or observer during a replay, which panics if the machine diverges
+/// from the session.
+#[derive(Debug)]
+pub struct Replaying(Arc<Seat>);
+
+impl Input for Replaying {
+    fn get(&mut self) -> Data {
+        match self.0.expect(|k| matches!(k, Kind::Input(_)), "in").kind {
+            Kind::Input(value) => value,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Output for Replaying {
+    fn write(&mut self, val: Data) {
+        self.0
+            .expect(|k| k == Kind::Output(val), &format!("out {}", val));
+    }
+}
+
+impl Observer for Replaying {
+    fn on_halt(&mut self, _ip: usize) {
+        self.0.expect(|k| k == Kind::Halt, "halt");
+    }
+
+    fn on_step(&mut self, _ip: usize) -> Control {
+        self.0.steps.fetch_add(1, Ordering::Relaxed);
+        Control::Continue
+    }
+}
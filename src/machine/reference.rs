@@ -0,0 +1,220 @@
+//! A reference Intcode interpreter, written for clarity rather than speed.
+//!
+//! Unlike `Machine`, it never panics. Anything the puzzle statements leave undefined, such as an
+//! unknown opcode or a negative address, stops the interpreter with a `Fault` instead.
+
+use crate::Data;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// The highest address the reference interpreter will touch.
+pub const ADDRESS_LIMIT: usize = 1 << 20;
+
+/// The reason the reference interpreter stopped before halting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    UnknownOpcode(Data),
+    UnknownMode(Data),
+    NegativeAddress(Data),
+    Overflow,
+    /// The program accessed an address above `ADDRESS_LIMIT`.
+    AddressLimit(usize),
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fault::UnknownOpcode(op) => write!(f, "unknown opcode {}", op),
+            Fault::UnknownMode(mode) => write!(f, "unknown mode {}", mode),
+            Fault::NegativeAddress(addr) => write!(f, "negative address {}", addr),
+            Fault::Overflow => write!(f, "arithmetic overflow"),
+            Fault::AddressLimit(addr) => write!(f, "address {} is above the limit", addr),
+        }
+    }
+}
+
+/// How a run of the reference interpreter ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Halted,
+    /// The instruction budget ran out.
+    Budget,
+    Fault(Fault),
+}
+
+/// The reference interpreter.
+pub struct Reference {
+    memory: BTreeMap<usize, Data>,
+    pub ip: usize,
+    pub base: Data,
+    pub outputs: Vec<Data>,
+    inputs: Vec<Data>,
+    read: usize,
+}
+
+impl Reference {
+    /// Creates an interpreter for the program.
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - The initial memory.
+    /// * `inputs` - The values read by opcode 3, which repeat once exhausted. If empty, every
+    ///   read produces 0.
+    pub fn new(program: &[Data], inputs: &[Data]) -> Self {
+        Reference {
+            memory: program.iter().copied().enumerate().collect(),
+            ip: 0,
+            base: 0,
+            outputs: Vec::new(),
+            inputs: inputs.to_vec(),
+            read: 0,
+        }
+    }
+
+    /// Reads a value at a given address. Cells that were never written hold 0.
+    pub fn read(&self, addr: usize) -> Data {
+        self.memory.get(&addr).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of cells up to and including the highest one written.
+    pub fn len(&self) -> usize {
+        self.memory.keys().next_back().map_or(0, |addr| addr + 1)
+    }
+
+    /// Returns whether no cell has been written.
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+
+    /// Runs at most `budget` instructions, counting the final halt as one.
+    pub fn run(&mut self, budget: usize) -> Status {
+        for _ in 0..budget {
+            match self.step() {
+                Ok(true) => return Status::Halted,
+                Ok(false) => {}
+                Err(fault) => return Status::Fault(fault),
+            }
+        }
+
+        Status::Budget
+    }
+
+    /// Executes one instruction and returns whether it was a halt.
+    fn step(&mut self) -> Result<bool, Fault> {
+        let instruction = self.load(self.ip)?;
+        let opcode = instruction % 100;
+
+        match opcode {
+            1 => {
+                let sum = self
+                    .param(instruction, 1)?
+                    .checked_add(self.param(instruction, 2)?);
+                self.store(instruction, 3, sum.ok_or(Fault::Overflow)?)?;
+                self.ip += 4;
+            }
+            2 => {
+                let product = self
+                    .param(instruction, 1)?
+                    .checked_mul(self.param(instruction, 2)?);
+                self.store(instruction, 3, product.ok_or(Fault::Overflow)?)?;
+                self.ip += 4;
+            }
+            3 => {
+                let value = if self.inputs.is_empty() {
+                    0
+                } else {
+                    self.inputs[self.read % self.inputs.len()]
+                };
+
+                self.read += 1;
+                self.store(instruction, 1, value)?;
+                self.ip += 2;
+            }
+            4 => {
+                let value = self.param(instruction, 1)?;
+                self.outputs.push(value);
+                self.ip += 2;
+            }
+            5 | 6 => {
+                let condition = self.param(instruction, 1)?;
+                let jump = if opcode == 5 {
+                    condition != 0
+                } else {
+                    condition == 0
+                };
+
+                if jump {
+                    self.ip = self.to_address(self.param(instruction, 2)?)?;
+                } else {
+                    self.ip += 3;
+                }
+            }
+            7 => {
+                let less = self.param(instruction, 1)? < self.param(instruction, 2)?;
+                self.store(instruction, 3, less as Data)?;
+                self.ip += 4;
+            }
+            8 => {
+                let equal = self.param(instruction, 1)? == self.param(instruction, 2)?;
+                self.store(instruction, 3, equal as Data)?;
+                self.ip += 4;
+            }
+            9 => {
+                let offset = self.param(instruction, 1)?;
+                self.base = self.base.checked_add(offset).ok_or(Fault::Overflow)?;
+                self.ip += 2;
+            }
+            99 => return Ok(true),
+            _ => return Err(Fault::UnknownOpcode(opcode)),
+        }
+
+        Ok(false)
+    }
+
+    /// Returns the address that the n-th parameter of the current instruction refers to.
+    fn param_address(&self, instruction: Data, n: u32) -> Result<usize, Fault> {
+        let slot = self.ip + n as usize;
+        match instruction / 10_i64.pow(n + 1) % 10 {
+            0 => self.to_address(self.load(slot)?),
+            1 => self.check_limit(slot),
+            2 => {
+                let addr = self
+                    .load(slot)?
+                    .checked_add(self.base)
+                    .ok_or(Fault::Overflow)?;
+                self.to_address(addr)
+            }
+            mode => Err(Fault::UnknownMode(mode)),
+        }
+    }
+
+    fn param(&self, instruction: Data, n: u32) -> Result<Data, Fault> {
+        self.load(self.param_address(instruction, n)?)
+    }
+
+    fn store(&mut self, instruction: Data, n: u32, value: Data) -> Result<(), Fault> {
+        let addr = self.param_address(instruction, n)?;
+        self.memory.insert(addr, value);
+        Ok(())
+    }
+
+    fn load(&self, addr: usize) -> Result<Data, Fault> {
+        Ok(self.read(self.check_limit(addr)?))
+    }
+
+    fn to_address(&self, value: Data) -> Result<usize, Fault> {
+        if value < 0 {
+            return Err(Fault::NegativeAddress(value));
+        }
+
+        self.check_limit(value as usize)
+    }
+
+    fn check_limit(&self, addr: usize) -> Result<usize, Fault> {
+        if addr > ADDRESS_LIMIT {
+            return Err(Fault::AddressLimit(addr));
+        }
+
+        Ok(addr)
+    }
+}
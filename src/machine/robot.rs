@@ -0,0 +1,213 @@
+//! A hull-painting robot controlled by an Intcode program.
+//!
+//! The robot stands on an infinite grid of panels, all black to begin with. Whenever its brain
+//! reads input, it gets the color of the panel the robot is over: 0 for black and 1 for white.
+//! The brain then outputs pairs of values, the color to paint that panel and the direction to
+//! turn, 0 for left and 1 for right, after which the robot moves forward one panel.
+//!
+//! The brain runs only as far as the robot's next move, so a robot can be driven one move at a
+//! time and inspected between them.
+
+use crate::canvas::{Canvas, Point};
+use crate::{Data, Machine};
+use std::collections::HashSet;
+
+pub const BLACK: Data = 0;
+pub const WHITE: Data = 1;
+
+/// The way the robot faces. Up is towards negative `y`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    fn left(self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Right => Direction::Up,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Down,
+        }
+    }
+
+    fn right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+}
+
+/// A robot and the hull it paints.
+pub struct Robot {
+    brain: Machine,
+    position: Point,
+    facing: Direction,
+    hull: Canvas,
+    painted: HashSet<Point>,
+    /// Outputs that do not yet make a whole move.
+    pending: Vec<Data>,
+    moves: usize,
+    halted: bool,
+}
+
+impl Robot {
+    /// Creates a robot at `(0, 0)`, facing up.
+    ///
+    /// # Arguments
+    ///
+    /// * `brain` - The machine running the control program.
+    pub fn new(brain: Machine) -> Self {
+        Robot {
+            brain,
+            position: (0, 0),
+            facing: Direction::Up,
+            hull: Canvas::new(),
+            painted: HashSet::new(),
+            pending: Vec::with_capacity(2),
+            moves: 0,
+            halted: false,
+        }
+    }
+
+    /// Sets the color of the panel the robot starts on, which does not count as painting it.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color of the panel.
+    pub fn start_on(mut self, color: Data) -> Self {
+        self.hull.draw(self.position, color);
+        self
+    }
+
+    /// Runs the brain until the robot has made one more move. Returns false, without moving, if
+    /// the brain halted first.
+    pub fn advance(&mut self) -> bool {
+        while self.pending.len() < 2 {
+            if self.halted {
+                return false;
+            }
+
+            let mut color = if self.brain.is_awaiting_input() {
+                self.color(self.position)
+            } else {
+                BLACK
+            };
+            self.halted = self.brain.step(&mut color, &mut self.pending);
+        }
+
+        let (color, turn) = (self.pending[0], self.pending[1]);
+        self.pending.clear();
+
+        self.hull.draw(self.position, color);
+        self.painted.insert(self.position);
+        self.facing = match turn {
+            0 => self.facing.left(),
+            1 => self.facing.right(),
+            _ => panic!("Unknown turn: {}", turn),
+        };
+
+        let (x, y) = self.position;
+        self.position = match self.facing {
+            Direction::Up => (x, y - 1),
+            Direction::Right => (x + 1, y),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x - 1, y),
+        };
+        self.moves += 1;
+
+        true
+    }
+
+    /// Runs the brain until it halts.
+    pub fn run(&mut self) {
+        while self.advance() {}
+    }
+
+    /// Runs the brain until it halts or the robot has made `limit` more moves, and returns
+    /// whether it halted.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of moves.
+    pub fn run_for(&mut self, limit: usize) -> bool {
+        for _ in 0..limit {
+            if !self.advance() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns whether the brain has halted.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn facing(&self) -> Direction {
+        self.facing
+    }
+
+    /// Returns the number of moves made.
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Returns the color of a panel.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The panel.
+    pub fn color(&self, point: Point) -> Data {
+        self.hull.get(point).unwrap_or(BLACK)
+    }
+
+    /// Returns the panels painted at least once.
+    pub fn painted(&self) -> &HashSet<Point> {
+        &self.painted
+    }
+
+    /// Returns the colors of the panels, as painted or as started on.
+    pub fn hull(&self) -> &Canvas {
+        &self.hull
+    }
+
+    /// Renders the hull as text, with white panels as `#`.
+    pub fn render(&self) -> String {
+        self.hull.render(|color| match color {
+            Some(WHITE) => '#',
+            _ => ' ',
+        })
+    }
+
+    /// Renders the hull as a binary PPM image.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - The width and height of a panel, in pixels.
+    pub fn ppm(&self, scale: usize) -> Vec<u8> {
+        self.hull.ppm(
+            |color| match color {
+                Some(WHITE) => [255, 255, 255],
+                _ => [0, 0, 0],
+            },
+            scale,
+        )
+    }
+
+    /// Returns the brain.
+    pub fn brain(&self) -> &Machine {
+        &self.brain
+    }
+}
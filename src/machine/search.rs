@@ -0,0 +1,250 @@
+use crate::combinatorics::{
+    k_permutations, nth_permutation, nth_product, permutation_count, product, product_count,
+};
+use crate::Data;
+use std::iter;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// The number of ranks a worker claims from the shared counter at a time.
+const CHUNK: usize = 64;
+
+/// The set of phase settings to search over.
+///
+/// Settings are ordered lexicographically by the position of each value in `values`, so every
+/// setting has a stable rank in `0..len()`.
+#[derive(Clone, Debug)]
+pub struct PhaseSpace {
+    values: Vec<Data>,
+    length: usize,
+    repetition: bool,
+}
+
+impl PhaseSpace {
+    /// Every ordering of the given values, as in the puzzle's amplifier chains.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The phase values, each of which is used exactly once.
+    pub fn permutations<T: IntoIterator<Item = Data>>(values: T) -> Self {
+        let values: Vec<Data> = values.into_iter().collect();
+        let length = values.len();
+        PhaseSpace::arrangements(values, length)
+    }
+
+    /// Every ordered selection of `length` distinct values.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The phase values to choose from.
+    /// * `length` - The number of amplifiers in the chain.
+    pub fn arrangements<T: IntoIterator<Item = Data>>(values: T, length: usize) -> Self {
+        PhaseSpace {
+            values: values.into_iter().collect(),
+            length,
+            repetition: false,
+        }
+    }
+
+    /// Every sequence of `length` values, where a value may be used more than once.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The phase values to choose from.
+    /// * `length` - The number of amplifiers in the chain.
+    pub fn with_repetition<T: IntoIterator<Item = Data>>(values: T, length: usize) -> Self {
+        PhaseSpace {
+            values: values.into_iter().collect(),
+            length,
+            repetition: true,
+        }
+    }
+
+    /// Returns the number of settings in the space.
+    pub fn len(&self) -> usize {
+        self.completions(0)
+    }
+
+    /// Returns whether the space contains no settings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over every setting in rank order.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Vec<Data>>> {
+        if self.repetition {
+            Box::new(product(vec![self.values.clone(); self.length]))
+        } else {
+            Box::new(k_permutations(self.values.clone(), self.length))
+        }
+    }
+
+    /// Returns the setting with the given rank.
+    ///
+    /// # Arguments
+    ///
+    /// * `rank` - The rank of the setting, must be less than `len()`.
+    pub fn nth(&self, rank: usize) -> Vec<Data> {
+        let setting = if self.repetition {
+            nth_product(&vec![&self.values[..]; self.length], rank)
+        } else {
+            nth_permutation(&self.values, self.length, rank)
+        };
+
+        setting.unwrap_or_else(|| panic!("Rank {} is out of range", rank))
+    }
+
+    /// Returns the number of settings which share any given prefix of length `prefix`.
+    fn completions(&self, prefix: usize) -> usize {
+        let n = self.values.len();
+        let count = if self.repetition {
+            product_count(iter::repeat_n(n, self.length - prefix))
+        } else if self.length > n {
+            Some(0)
+        } else {
+            permutation_count(n - prefix, self.length - prefix)
+        };
+
+        count.expect("Phase space is too large to enumerate")
+    }
+
+    /// Returns the range of ranks of all settings which share the prefix of the given setting.
+    fn prefix_block(&self, rank: usize, prefix: usize) -> Range<usize> {
+        let block = self.completions(prefix);
+        let start = rank / block * block;
+        start..start + block
+    }
+}
+
+/// A phase setting together with the signal it produced.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub setting: Vec<Data>,
+    pub signal: Data,
+}
+
+/// A predicate over partial phase settings.
+type Prune<'a> = Box<dyn Fn(&[Data]) -> bool + Sync + 'a>;
+
+/// Searches a phase space for the settings which produce the highest signals.
+pub struct PhaseSearch<'a> {
+    space: PhaseSpace,
+    workers: usize,
+    top: usize,
+    prune: Option<Prune<'a>>,
+}
+
+impl<'a> PhaseSearch<'a> {
+    /// Creates a search over the given space, using one worker per available core and reporting
+    /// only the best setting.
+    ///
+    /// # Arguments
+    ///
+    /// * `space` - The phase settings to search over.
+    pub fn new(space: PhaseSpace) -> Self {
+        PhaseSearch {
+            space,
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            top: 1,
+            prune: None,
+        }
+    }
+
+    /// Sets the number of worker threads.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Sets how many of the best settings are reported. With a `top` of 0, `run` evaluates
+    /// nothing and reports nothing.
+    pub fn top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// Sets a predicate which rejects partial settings. When a prefix is rejected, every setting
+    /// that starts with it is skipped without being evaluated.
+    ///
+    /// # Arguments
+    ///
+    /// * `prune` - Returns true if settings starting with the given prefix should be skipped.
+    pub fn prune<F: Fn(&[Data]) -> bool + Sync + 'a>(mut self, prune: F) -> Self {
+        self.prune = Some(Box::new(prune));
+        self
+    }
+
+    /// Evaluates every setting which is not pruned and returns the best ones, highest signal
+    /// first. Ties are broken in favour of the setting with the lower rank.
+    ///
+    /// # Arguments
+    ///
+    /// * `evaluate` - Returns the signal produced by a given setting.
+    pub fn run<F: Fn(&[Data]) -> Data + Sync>(&self, evaluate: F) -> Vec<Candidate> {
+        let total = if self.top == 0 { 0 } else { self.space.len() };
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::new());
+
+        thread::scope(|scope| {
+            for _ in 0..self.workers {
+                scope.spawn(|| {
+                    let mut best = Vec::new();
+
+                    loop {
+                        let start = next.fetch_add(CHUNK, Ordering::Relaxed);
+                        if start >= total {
+                            break;
+                        }
+
+                        let mut rank = start;
+                        let end = (start + CHUNK).min(total);
+                        while rank < end {
+                            let setting = self.space.nth(rank);
+
+                            if let Some(prefix) = self.rejected_prefix(&setting) {
+                                rank = self.space.prefix_block(rank, prefix).end;
+                                continue;
+                            }
+
+                            let signal = evaluate(&setting);
+                            self.offer(&mut best, (rank, Candidate { setting, signal }));
+                            rank += 1;
+                        }
+                    }
+
+                    results.lock().unwrap().push(best);
+                });
+            }
+        });
+
+        let mut best = Vec::new();
+        for entry in results.into_inner().unwrap().into_iter().flatten() {
+            self.offer(&mut best, entry);
+        }
+
+        best.into_iter().map(|(_, candidate)| candidate).collect()
+    }
+
+    /// Returns the length of the shortest prefix of the setting that is rejected, if any.
+    fn rejected_prefix(&self, setting: &[Data]) -> Option<usize> {
+        let prune = self.prune.as_ref()?;
+        (1..=setting.len()).find(|len| prune(&setting[..*len]))
+    }
+
+    /// Inserts a ranked candidate into a sorted list of the best results, keeping at most `top`.
+    fn offer(&self, best: &mut Vec<(usize, Candidate)>, entry: (usize, Candidate)) {
+        let position = best
+            .iter()
+            .position(|(rank, c)| {
+                entry.1.signal > c.signal || (entry.1.signal == c.signal && entry.0 < *rank)
+            })
+            .unwrap_or(best.len());
+
+        if position < self.top {
+            best.insert(position, entry);
+            best.truncate(self.top);
+        }
+    }
+}
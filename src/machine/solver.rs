@@ -0,0 +1,344 @@
+use crate::combinatorics::product_count;
+use crate::symbolic::{End, Limits, SymbolicMachine};
+use crate::{Data, Machine};
+use std::ops::RangeInclusive;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// The number of ranks a worker claims from the shared counter at a time.
+const CHUNK: usize = 64;
+
+/// The number of instructions a candidate may execute before it is given up on, by default.
+pub const BUDGET: usize = 1_000_000;
+
+/// A condition on the state of a machine after it has halted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// The memory cell at the given address holds the given value.
+    Memory(usize, Data),
+    /// The output at the given index is the given value.
+    Output(usize, Data),
+}
+
+impl Target {
+    /// Returns the value the target observes, if the run produced one.
+    fn observe(&self, machine: &Machine, output: &[Data]) -> Option<Data> {
+        match self {
+            Target::Memory(addr, _) => Some(machine.read(*addr)),
+            Target::Output(index, _) => output.get(*index).copied(),
+        }
+    }
+
+    fn value(&self) -> Data {
+        match self {
+            Target::Memory(_, value) | Target::Output(_, value) => *value,
+        }
+    }
+}
+
+/// A model of a program whose observed value is `constant + sum(coefficients[i] * inputs[i])`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Affine {
+    pub constant: Data,
+    pub coefficients: Vec<Data>,
+}
+
+impl Affine {
+    /// Evaluates the model for the given inputs, or returns `None` if the value overflows.
+    pub fn eval(&self, inputs: &[Data]) -> Option<Data> {
+        self.coefficients
+            .iter()
+            .zip(inputs)
+            .try_fold(self.constant, |acc, (a, x)| {
+                acc.checked_add(a.checked_mul(*x)?)
+            })
+    }
+}
+
+/// Finds the values of a set of memory cells which make a program reach a target.
+pub struct Solver<'a> {
+    machine: &'a Machine,
+    variables: Vec<(usize, RangeInclusive<Data>)>,
+    input: Data,
+    workers: usize,
+    budget: usize,
+    affine: bool,
+}
+
+impl<'a> Solver<'a> {
+    /// Creates a solver for the given program with no variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The machine holding the program, which is cloned for every run.
+    pub fn new(machine: &'a Machine) -> Self {
+        Solver {
+            machine,
+            variables: Vec::new(),
+            input: 0,
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            budget: BUDGET,
+            affine: false,
+        }
+    }
+
+    /// Adds a memory cell whose value is varied over the given range.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the cell.
+    /// * `range` - The values to try.
+    pub fn vary(mut self, addr: usize, range: RangeInclusive<Data>) -> Self {
+        self.variables.push((addr, range));
+        self
+    }
+
+    /// Sets the value returned whenever the program reads input.
+    pub fn input(mut self, input: Data) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Sets the number of worker threads used for brute force.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Sets the number of instructions each candidate may execute. Candidates which have not
+    /// halted by then are not solutions.
+    pub fn budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Sets whether `solve` first tries to model the program as affine in the variables.
+    pub fn affine(mut self, affine: bool) -> Self {
+        self.affine = affine;
+        self
+    }
+
+    /// Returns every assignment of the variables which reaches the target, in the order the
+    /// variables were added with the last one varying fastest.
+    ///
+    /// If affine solving is enabled and `model` proves the program affine in the variables, the
+    /// solutions are computed directly and only those are run, to confirm them. Otherwise, every
+    /// assignment is run.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The condition to reach.
+    pub fn solve(&self, target: Target) -> Vec<Vec<Data>> {
+        if self.affine && self.len() > 0 {
+            if let Some(model) = self.model(target) {
+                return self
+                    .solve_affine(&model, target.value())
+                    .into_iter()
+                    .filter(|inputs| self.reaches(inputs, target))
+                    .collect();
+            }
+        }
+
+        self.solve_with(|machine, output| target.observe(machine, output) == Some(target.value()))
+    }
+
+    /// Runs every assignment of the variables and returns those which satisfy the predicate.
+    /// Assignments which make the program fault, or run past the budget, never do.
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - Accepts the halted machine and its output.
+    pub fn solve_with<F: Fn(&Machine, &[Data]) -> bool + Sync>(
+        &self,
+        predicate: F,
+    ) -> Vec<Vec<Data>> {
+        let total = self.len();
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::new());
+
+        thread::scope(|scope| {
+            for _ in 0..self.workers {
+                scope.spawn(|| {
+                    let mut found = Vec::new();
+
+                    loop {
+                        let start = next.fetch_add(CHUNK, Ordering::Relaxed);
+                        if start >= total {
+                            break;
+                        }
+
+                        for rank in start..(start + CHUNK).min(total) {
+                            let inputs = self.nth(rank);
+                            if let Some((machine, output)) = self.run(&inputs) {
+                                if predicate(&machine, &output) {
+                                    found.push((rank, inputs));
+                                }
+                            }
+                        }
+                    }
+
+                    results.lock().unwrap().append(&mut found);
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(rank, _)| *rank);
+        results.into_iter().map(|(_, inputs)| inputs).collect()
+    }
+
+    /// Derives an affine model of the target's observed value by running the program
+    /// symbolically, with each variable as an unknown. Returns `None` unless the model holds for
+    /// every assignment: the program must take a single path to a halt whatever the variables
+    /// are, without the path constraining them, and the value must be a sum of the variables
+    /// times constants.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The value to model.
+    pub fn model(&self, target: Target) -> Option<Affine> {
+        let names: Vec<String> = (0..self.variables.len())
+            .map(|i| format!("x{}", i))
+            .collect();
+        let mut machine = SymbolicMachine::new(self.machine);
+        for ((addr, _), name) in self.variables.iter().zip(&names) {
+            machine.set_symbol(*addr, name.as_str());
+        }
+
+        let limits = Limits {
+            steps: self.budget,
+            paths: 2,
+        };
+        let path = match machine.explore(limits).as_slice() {
+            [path] if path.end == End::Halted && path.constraints.is_empty() => path.clone(),
+            _ => return None,
+        };
+
+        let value = match target {
+            Target::Memory(addr, _) => path.read(addr),
+            Target::Output(index, _) => path.outputs.get(index)?.clone(),
+        };
+
+        // Any other unknown is an input, which the model cannot account for.
+        let (mut terms, constant) = value.linear_terms()?;
+        let coefficients = names
+            .iter()
+            .map(|name| terms.remove(name.as_str()).unwrap_or(0))
+            .collect();
+        if !terms.is_empty() {
+            return None;
+        }
+
+        Some(Affine {
+            constant,
+            coefficients,
+        })
+    }
+
+    /// Returns the assignments within range for which the model produces the value.
+    fn solve_affine(&self, model: &Affine, value: Data) -> Vec<Vec<Data>> {
+        // Solve for the variable with the largest coefficient and enumerate the rest.
+        let pivot = (0..model.coefficients.len())
+            .filter(|i| model.coefficients[*i] != 0)
+            .max_by_key(|i| model.coefficients[*i].abs());
+
+        let pivot = match pivot {
+            Some(pivot) => pivot,
+            None if model.constant == value => {
+                return (0..self.len()).map(|r| self.nth(r)).collect()
+            }
+            None => return Vec::new(),
+        };
+
+        let (_, pivot_range) = &self.variables[pivot];
+        let others: Vec<usize> = (0..self.variables.len()).filter(|i| *i != pivot).collect();
+        let total = product_count(others.iter().map(|i| range_len(&self.variables[*i].1)))
+            .expect("Search space is too large to enumerate");
+
+        let mut solutions = Vec::new();
+        for rank in 0..total {
+            let mut inputs = vec![0; self.variables.len()];
+            let mut rest = rank;
+            for i in others.iter().rev() {
+                let range = &self.variables[*i].1;
+                inputs[*i] = range.start() + (rest % range_len(range)) as Data;
+                rest /= range_len(range);
+            }
+
+            // Assignments whose terms overflow are skipped.
+            let coefficient = model.coefficients[pivot];
+            let remainder = match model.eval(&inputs).and_then(|v| value.checked_sub(v)) {
+                Some(remainder) => remainder,
+                None => continue,
+            };
+            if remainder.checked_rem(coefficient) != Some(0) {
+                continue;
+            }
+            match remainder.checked_div(coefficient) {
+                Some(x) if pivot_range.contains(&x) => {
+                    inputs[pivot] = x;
+                    solutions.push(inputs);
+                }
+                _ => {}
+            }
+        }
+
+        solutions.sort();
+        solutions
+    }
+
+    /// Returns the number of assignments of the variables.
+    fn len(&self) -> usize {
+        product_count(self.variables.iter().map(|(_, r)| range_len(r)))
+            .expect("Search space is too large to enumerate")
+    }
+
+    /// Returns the assignment with the given rank.
+    fn nth(&self, mut rank: usize) -> Vec<Data> {
+        let mut inputs = vec![0; self.variables.len()];
+        for (i, (_, range)) in self.variables.iter().enumerate().rev() {
+            inputs[i] = range.start() + (rank % range_len(range)) as Data;
+            rank /= range_len(range);
+        }
+
+        inputs
+    }
+
+    /// Runs the program with the given assignment and returns the halted machine and its output,
+    /// or `None` if it faulted or ran past the budget.
+    fn run(&self, inputs: &[Data]) -> Option<(Machine, Vec<Data>)> {
+        let mut machine = self.machine.clone();
+        for ((addr, _), value) in self.variables.iter().zip(inputs) {
+            machine.set_force(*addr, *value);
+        }
+
+        let mut output = Vec::new();
+        let mut input = self.input;
+        let halted = panic::catch_unwind(AssertUnwindSafe(|| {
+            machine.execute_for(&mut input, &mut output, self.budget)
+        }))
+        .unwrap_or(false);
+
+        if halted {
+            Some((machine, output))
+        } else {
+            None
+        }
+    }
+
+    fn reaches(&self, inputs: &[Data], target: Target) -> bool {
+        self.run(inputs)
+            .and_then(|(machine, output)| target.observe(&machine, &output))
+            == Some(target.value())
+    }
+}
+
+fn range_len(range: &RangeInclusive<Data>) -> usize {
+    if range.is_empty() {
+        0
+    } else {
+        (range.end() - range.start()) as usize + 1
+    }
+}
@@ -0,0 +1,702 @@
+//! Symbolic execution of Intcode, with memory and input holding expressions over named unknowns.
+//!
+//! A conditional jump on an unknown condition forks the path, and an instruction word which is a
+//! linear function of a single unknown forks it once for each canonical instruction the unknown
+//! can produce: an opcode with a valid mode digit for each of its parameters and no other
+//! digits. `Machine` also executes words with higher digits, which it ignores, such as 199 or
+//! 100001, but as every word sharing the low digits of a canonical one behaves the same there
+//! are endlessly many of them, and they are not explored.
+
+use crate::{Data, Machine};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// A symbolic value, kept in a canonical form as a polynomial over atoms.
+///
+/// Two expressions which are equal as polynomials compare equal, so `a + b` and `b + a` are the
+/// same expression.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Expr {
+    /// Maps each monomial, a sorted product of atoms, to its non-zero coefficient. The empty
+    /// monomial holds the constant term.
+    terms: BTreeMap<Vec<Atom>, Data>,
+}
+
+/// A value which cannot be broken down further.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Atom {
+    /// A named unknown, such as a memory cell or an input.
+    Symbol(String),
+    /// 1 if the first expression is less than the second, otherwise 0.
+    Less(Expr, Expr),
+    /// 1 if the expressions are equal, otherwise 0.
+    Equal(Expr, Expr),
+    /// The value of memory at an address which is not known.
+    Load(Expr),
+}
+
+/// The error returned when a coefficient of an expression does not fit in `Data`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Arithmetic overflow")
+    }
+}
+
+impl Error for Overflow {}
+
+impl Expr {
+    /// Creates a constant expression.
+    pub fn constant(value: Data) -> Self {
+        let mut terms = BTreeMap::new();
+        if value != 0 {
+            terms.insert(Vec::new(), value);
+        }
+
+        Expr { terms }
+    }
+
+    /// Creates an expression for a named unknown.
+    pub fn symbol<S: Into<String>>(name: S) -> Self {
+        Expr::atom(Atom::Symbol(name.into()))
+    }
+
+    /// Returns the value of the expression if it does not depend on any unknowns.
+    pub fn as_constant(&self) -> Option<Data> {
+        match self.terms.len() {
+            0 => Some(0),
+            1 => self.terms.get(&Vec::new()).copied(),
+            _ => None,
+        }
+    }
+
+    /// Returns the sum of two expressions, or an error if a coefficient overflows.
+    pub fn add(&self, other: &Expr) -> Result<Expr, Overflow> {
+        let mut terms = self.terms.clone();
+        for (monomial, coefficient) in &other.terms {
+            let entry = terms.entry(monomial.clone()).or_insert(0);
+            *entry = entry.checked_add(*coefficient).ok_or(Overflow)?;
+            if *entry == 0 {
+                terms.remove(monomial);
+            }
+        }
+
+        Ok(Expr { terms })
+    }
+
+    /// Returns the product of two expressions, or an error if a coefficient overflows.
+    pub fn mul(&self, other: &Expr) -> Result<Expr, Overflow> {
+        let mut result = Expr::constant(0);
+        for (first, a) in &self.terms {
+            for (second, b) in &other.terms {
+                let mut monomial: Vec<Atom> = first.iter().chain(second).cloned().collect();
+                monomial.sort();
+
+                let mut terms = BTreeMap::new();
+                terms.insert(monomial, a.checked_mul(*b).ok_or(Overflow)?);
+                result = result.add(&Expr { terms })?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns 1 if this expression is less than the other, otherwise 0.
+    pub fn less(&self, other: &Expr) -> Expr {
+        match (self.as_constant(), other.as_constant()) {
+            (Some(a), Some(b)) => Expr::constant(if a < b { 1 } else { 0 }),
+            _ if self == other => Expr::constant(0),
+            _ => Expr::atom(Atom::Less(self.clone(), other.clone())),
+        }
+    }
+
+    /// Returns 1 if the expressions are equal, otherwise 0.
+    pub fn equal(&self, other: &Expr) -> Expr {
+        match (self.as_constant(), other.as_constant()) {
+            (Some(a), Some(b)) => Expr::constant(if a == b { 1 } else { 0 }),
+            _ if self == other => Expr::constant(1),
+            _ => Expr::atom(Atom::Equal(self.clone(), other.clone())),
+        }
+    }
+
+    /// Returns an expression with every occurrence of a named unknown replaced by a value, or an
+    /// error if a coefficient overflows.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the unknown.
+    /// * `value` - The value to replace it with.
+    pub fn substitute(&self, name: &str, value: Data) -> Result<Expr, Overflow> {
+        let mut result = Expr::constant(0);
+        for (monomial, coefficient) in &self.terms {
+            let mut term = Expr::constant(*coefficient);
+            for atom in monomial {
+                let atom = match atom {
+                    Atom::Symbol(symbol) if symbol == name => Expr::constant(value),
+                    Atom::Symbol(_) => Expr::atom(atom.clone()),
+                    Atom::Less(a, b) => {
+                        a.substitute(name, value)?.less(&b.substitute(name, value)?)
+                    }
+                    Atom::Equal(a, b) => a
+                        .substitute(name, value)?
+                        .equal(&b.substitute(name, value)?),
+                    Atom::Load(addr) => Expr::atom(Atom::Load(addr.substitute(name, value)?)),
+                };
+                term = term.mul(&atom)?;
+            }
+
+            result = result.add(&term)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the coefficient of each named unknown and the constant term, if the expression is
+    /// a sum of named unknowns times constants.
+    pub fn linear_terms(&self) -> Option<(BTreeMap<&str, Data>, Data)> {
+        let mut coefficients = BTreeMap::new();
+        for (monomial, coefficient) in &self.terms {
+            match monomial.as_slice() {
+                [] => {}
+                [Atom::Symbol(name)] => {
+                    coefficients.insert(name.as_str(), *coefficient);
+                }
+                _ => return None,
+            }
+        }
+
+        let constant = self.terms.get(&Vec::new()).copied().unwrap_or(0);
+        Some((coefficients, constant))
+    }
+
+    /// Returns `(name, a, c)` if the expression is `a * name + c` for a single unknown.
+    fn linear_symbol(&self) -> Option<(String, Data, Data)> {
+        let mut symbol = None;
+        for (monomial, coefficient) in &self.terms {
+            match monomial.as_slice() {
+                [] => {}
+                [Atom::Symbol(name)] if symbol.is_none() => {
+                    symbol = Some((name.clone(), *coefficient))
+                }
+                _ => return None,
+            }
+        }
+
+        let (name, a) = symbol?;
+        Some((name, a, self.terms.get(&Vec::new()).copied().unwrap_or(0)))
+    }
+
+    fn atom(atom: Atom) -> Self {
+        let mut terms = BTreeMap::new();
+        terms.insert(vec![atom], 1);
+        Expr { terms }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+
+        // Write the constant term last, as it is the least interesting.
+        let terms = self
+            .terms
+            .iter()
+            .filter(|(m, _)| !m.is_empty())
+            .chain(self.terms.iter().filter(|(m, _)| m.is_empty()));
+
+        for (i, (monomial, coefficient)) in terms.enumerate() {
+            let magnitude = coefficient.unsigned_abs();
+            match (i, *coefficient < 0) {
+                (0, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, true) => write!(f, " - ")?,
+                (_, false) => write!(f, " + ")?,
+            }
+
+            if monomial.is_empty() {
+                write!(f, "{}", magnitude)?;
+                continue;
+            }
+
+            if magnitude != 1 {
+                write!(f, "{}*", magnitude)?;
+            }
+
+            for (j, atom) in monomial.iter().enumerate() {
+                if j > 0 {
+                    write!(f, "*")?;
+                }
+
+                write!(f, "{}", atom)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Atom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Atom::Symbol(name) => write!(f, "{}", name),
+            Atom::Less(a, b) => write!(f, "({} < {})", a, b),
+            Atom::Equal(a, b) => write!(f, "({} == {})", a, b),
+            Atom::Load(addr) => write!(f, "mem[{}]", addr),
+        }
+    }
+}
+
+/// A condition assumed to hold on a path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    pub condition: Expr,
+    /// Whether the condition is non-zero.
+    pub holds: bool,
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let op = if self.holds { "!=" } else { "==" };
+        match self.condition.linear_symbol() {
+            Some((name, 1, c)) => write!(f, "{} {} {}", name, op, -c),
+            _ => write!(f, "{} {} 0", self.condition, op),
+        }
+    }
+}
+
+/// The reason exploration of a path stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum End {
+    /// The program reached opcode 99.
+    Halted,
+    /// The path ran for more steps than allowed.
+    StepLimit,
+    /// The instruction at the given address is not known.
+    SymbolicOpcode(usize),
+    /// The instruction at the given address jumps to an address which is not known.
+    SymbolicJump(usize),
+    /// The instruction at the given address writes to an address which is not known.
+    SymbolicWrite(usize),
+    /// The instruction at the given address adjusts the relative base by a value which is not
+    /// known.
+    SymbolicBase(usize),
+    /// The instruction at the given address is invalid, or accesses a negative address.
+    Fault(usize),
+    /// The instruction at the given address computes a value which does not fit in `Data`.
+    Overflow(usize),
+}
+
+/// A single path through the program, from the start to the point exploration stopped.
+#[derive(Clone, Debug)]
+pub struct Path {
+    pub outputs: Vec<Expr>,
+    pub constraints: Vec<Constraint>,
+    pub end: End,
+    memory: Vec<Expr>,
+}
+
+impl Path {
+    /// Reads a value of the final memory at a given address.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to read at.
+    pub fn read(&self, addr: usize) -> Expr {
+        self.memory
+            .get(addr)
+            .cloned()
+            .unwrap_or_else(|| Expr::constant(0))
+    }
+
+    /// Returns the final memory.
+    pub fn memory(&self) -> &[Expr] {
+        &self.memory
+    }
+}
+
+/// Bounds on how much of a program is explored.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// The maximum number of instructions executed on a single path.
+    pub steps: usize,
+    /// The number of paths after which exploration stops.
+    pub paths: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            steps: 100_000,
+            paths: 1_000,
+        }
+    }
+}
+
+/// A machine whose memory and input may hold symbolic values.
+#[derive(Clone)]
+pub struct SymbolicMachine {
+    memory: Vec<Expr>,
+    ip: usize,
+    base: Data,
+    inputs: Vec<Expr>,
+    read: usize,
+    outputs: Vec<Expr>,
+    constraints: Vec<Constraint>,
+}
+
+/// The reason a symbolic machine cannot continue alone.
+enum Stop {
+    End(End),
+    /// A conditional jump depends on the given condition.
+    Fork(Expr),
+    /// The instruction depends on the named unknown, which may take any of the given values.
+    Bind(String, Vec<Data>),
+}
+
+/// Every opcode, with the number of parameters it takes.
+const OPCODES: &[(Data, u32)] = &[
+    (1, 3),
+    (2, 3),
+    (3, 1),
+    (4, 1),
+    (5, 2),
+    (6, 2),
+    (7, 3),
+    (8, 3),
+    (9, 1),
+    (99, 0),
+];
+
+/// Returns every canonical instruction, as an opcode combined with a valid mode for each of its
+/// parameters and no higher digits.
+fn instructions() -> Vec<Data> {
+    let mut words = Vec::new();
+    for &(opcode, params) in OPCODES {
+        for modes in 0..3_i64.pow(params) {
+            let mut word = opcode;
+            let mut rest = modes;
+            for param in 0..params {
+                word += rest % 3 * 10_i64.pow(param + 2);
+                rest /= 3;
+            }
+            words.push(word);
+        }
+    }
+
+    words
+}
+
+impl SymbolicMachine {
+    /// Creates a symbolic machine from the program loaded in a machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The machine to copy memory and registers from.
+    pub fn new(machine: &Machine) -> Self {
+        SymbolicMachine {
+            memory: machine
+                .read_range(0..machine.len())
+                .into_iter()
+                .map(Expr::constant)
+                .collect(),
+            ip: machine.ip,
+            base: machine.base,
+            inputs: Vec::new(),
+            read: 0,
+            outputs: Vec::new(),
+            constraints: Vec::new(),
+        }
+    }
+
+    /// Sets a memory cell to an expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to write at.
+    /// * `value` - The value to write.
+    pub fn set(&mut self, addr: usize, value: Expr) {
+        if self.memory.len() <= addr {
+            self.memory.resize(addr + 1, Expr::constant(0));
+        }
+
+        self.memory[addr] = value;
+    }
+
+    /// Replaces a memory cell with a named unknown.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the cell.
+    /// * `name` - The name of the unknown.
+    pub fn set_symbol<S: Into<String>>(&mut self, addr: usize, name: S) {
+        self.set(addr, Expr::symbol(name));
+    }
+
+    /// Queues a value to be read by the program. Once the queue is exhausted, each input is a
+    /// fresh unknown named `input0`, `input1` and so on.
+    pub fn push_input(&mut self, value: Expr) {
+        self.inputs.push(value);
+    }
+
+    /// Explores every path through the program, forking at each conditional jump whose condition
+    /// is not known.
+    ///
+    /// If an instruction depends linearly on a single unknown, as when a program adds its input
+    /// to an opcode, the path forks once for every canonical instruction the unknown could
+    /// produce and the unknown is bound to the matching value on each. Values which give a word
+    /// with digits above the modes, which `Machine` ignores, are not tried, so such a path is
+    /// missed: an unknown `x` used as an opcode is bound to 99, but never to 199.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - The bounds on the exploration.
+    pub fn explore(&self, limits: Limits) -> Vec<Path> {
+        let mut paths = Vec::new();
+        let mut pending = vec![(self.clone(), 0)];
+
+        while let Some((mut machine, mut steps)) = pending.pop() {
+            if paths.len() >= limits.paths {
+                break;
+            }
+
+            let end = loop {
+                if steps >= limits.steps {
+                    break Some(End::StepLimit);
+                }
+
+                steps += 1;
+                match machine.step() {
+                    Ok(()) => {}
+                    Err(Stop::End(end)) => break Some(end),
+                    Err(Stop::Fork(condition)) => {
+                        for holds in [false, true] {
+                            let mut fork = machine.clone();
+                            match fork.assume(condition.clone(), holds) {
+                                Err(Stop::End(end)) => paths.push(fork.finish(end)),
+                                _ => pending.push((fork, steps)),
+                            }
+                        }
+
+                        break None;
+                    }
+                    Err(Stop::Bind(name, values)) => {
+                        for value in values.into_iter().rev() {
+                            let mut fork = machine.clone();
+                            match fork.bind(&name, value) {
+                                Ok(()) => pending.push((fork, steps)),
+                                Err(Overflow) => {
+                                    let ip = fork.ip;
+                                    paths.push(fork.finish(End::Overflow(ip)));
+                                }
+                            }
+                        }
+
+                        break None;
+                    }
+                }
+            };
+
+            if let Some(end) = end {
+                paths.push(machine.finish(end));
+            }
+        }
+
+        paths
+    }
+
+    fn finish(self, end: End) -> Path {
+        Path {
+            outputs: self.outputs,
+            constraints: self.constraints,
+            end,
+            memory: self.memory,
+        }
+    }
+
+    /// Replaces an unknown with a value everywhere, and records that it was assumed. Nothing
+    /// changes if that overflows.
+    fn bind(&mut self, name: &str, value: Data) -> Result<(), Overflow> {
+        let substitute = |values: &[Expr]| {
+            values
+                .iter()
+                .map(|e| e.substitute(name, value))
+                .collect::<Result<Vec<Expr>, Overflow>>()
+        };
+        let condition =
+            Expr::symbol(name).add(&Expr::constant(value.checked_neg().ok_or(Overflow)?))?;
+        let (memory, inputs, outputs) = (
+            substitute(&self.memory)?,
+            substitute(&self.inputs)?,
+            substitute(&self.outputs)?,
+        );
+
+        self.memory = memory;
+        self.inputs = inputs;
+        self.outputs = outputs;
+        self.constraints.push(Constraint {
+            condition,
+            holds: false,
+        });
+        Ok(())
+    }
+
+    /// Records a condition as holding or not, and continues past the jump accordingly.
+    fn assume(&mut self, condition: Expr, holds: bool) -> Result<(), Stop> {
+        self.constraints.push(Constraint { condition, holds });
+        self.jump(holds)
+    }
+
+    /// Executes a single step, or returns why execution cannot continue alone.
+    fn step(&mut self) -> Result<(), Stop> {
+        let ip = self.ip;
+        let instruction = self.read(ip);
+        if instruction.as_constant().is_none() {
+            return match instruction.linear_symbol() {
+                Some((name, a, c)) => {
+                    let values: Vec<Data> = instructions()
+                        .into_iter()
+                        .filter_map(|v| v.checked_sub(c))
+                        .filter(|v| v.checked_rem(a) == Some(0))
+                        .filter_map(|v| v.checked_div(a))
+                        .collect();
+                    Err(Stop::Bind(name, values))
+                }
+                None => Err(Stop::End(End::SymbolicOpcode(ip))),
+            };
+        }
+
+        match self.opcode() {
+            op @ (1 | 2 | 7 | 8) => {
+                let (a, b) = (self.param(1)?, self.param(2)?);
+                let value = match op {
+                    1 => a.add(&b).map_err(|_| Stop::End(End::Overflow(ip)))?,
+                    2 => a.mul(&b).map_err(|_| Stop::End(End::Overflow(ip)))?,
+                    7 => a.less(&b),
+                    _ => a.equal(&b),
+                };
+
+                self.write(3, value)?;
+                self.ip += 4;
+            }
+            3 => {
+                let value = match self.inputs.get(self.read) {
+                    Some(value) => value.clone(),
+                    None => Expr::symbol(format!("input{}", self.read)),
+                };
+
+                self.read += 1;
+                self.write(1, value)?;
+                self.ip += 2;
+            }
+            4 => {
+                let value = self.param(1)?;
+                self.outputs.push(value);
+                self.ip += 2;
+            }
+            5 | 6 => {
+                let condition = self.param(1)?;
+                let holds = match condition.as_constant() {
+                    Some(value) => value != 0,
+                    None => match self.constraints.iter().find(|c| c.condition == condition) {
+                        Some(constraint) => constraint.holds,
+                        None => return Err(Stop::Fork(condition)),
+                    },
+                };
+
+                self.jump(holds)?;
+            }
+            9 => {
+                match self.param(1)?.as_constant() {
+                    Some(value) => {
+                        self.base = self
+                            .base
+                            .checked_add(value)
+                            .ok_or(Stop::End(End::Overflow(ip)))?
+                    }
+                    None => return Err(Stop::End(End::SymbolicBase(ip))),
+                }
+
+                self.ip += 2;
+            }
+            99 => return Err(Stop::End(End::Halted)),
+            _ => return Err(Stop::End(End::Fault(ip))),
+        }
+
+        Ok(())
+    }
+
+    /// Completes a conditional jump given whether its condition is non-zero.
+    fn jump(&mut self, holds: bool) -> Result<(), Stop> {
+        let ip = self.ip;
+        let jumps = if self.opcode() == 5 { holds } else { !holds };
+        if !jumps {
+            self.ip += 3;
+            return Ok(());
+        }
+
+        match self.param(2)?.as_constant() {
+            Some(target) if target >= 0 => self.ip = target as usize,
+            Some(_) => return Err(Stop::End(End::Fault(ip))),
+            None => return Err(Stop::End(End::SymbolicJump(ip))),
+        }
+
+        Ok(())
+    }
+
+    fn read(&self, addr: usize) -> Expr {
+        self.memory
+            .get(addr)
+            .cloned()
+            .unwrap_or_else(|| Expr::constant(0))
+    }
+
+    /// Returns the current opcode, which must be known.
+    fn opcode(&self) -> Data {
+        self.read(self.ip).as_constant().unwrap_or(0) % 100
+    }
+
+    /// Returns the mode of the parameter specified at a given offset.
+    fn mode(&self, offset: usize) -> Data {
+        self.read(self.ip).as_constant().unwrap_or(0) / 10_i64.pow(offset as u32 + 1) % 10
+    }
+
+    /// Returns the address a parameter refers to as an expression.
+    fn address(&self, offset: usize) -> Result<Expr, Stop> {
+        let pointer = self.read(self.ip + offset);
+        match self.mode(offset) {
+            0 => Ok(pointer),
+            1 => Ok(Expr::constant((self.ip + offset) as Data)),
+            2 => pointer
+                .add(&Expr::constant(self.base))
+                .map_err(|_| Stop::End(End::Overflow(self.ip))),
+            _ => Err(Stop::End(End::Fault(self.ip))),
+        }
+    }
+
+    /// Gets the value of a parameter. Reads from an unknown address produce a load expression.
+    fn param(&self, offset: usize) -> Result<Expr, Stop> {
+        let address = self.address(offset)?;
+        match address.as_constant() {
+            Some(address) if address < 0 => Err(Stop::End(End::Fault(self.ip))),
+            Some(address) => Ok(self.read(address as usize)),
+            None => Ok(Expr::atom(Atom::Load(address))),
+        }
+    }
+
+    /// Writes a value using a parameter.
+    fn write(&mut self, offset: usize, value: Expr) -> Result<(), Stop> {
+        match self.address(offset)?.as_constant() {
+            Some(address) if address < 0 => Err(Stop::End(End::Fault(self.ip))),
+            Some(address) => {
+                self.set(address as usize, value);
+                Ok(())
+            }
+            None => Err(Stop::End(End::SymbolicWrite(self.ip))),
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use machine::{disassemble, loader, panic_message, Machine, Output, Status};
+use std::io::{BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+type Result<T> = std::result::Result<T, String>;
+
+const USAGE: &str = "\
+Usage: repl [program] [index]
+
+Runs Intcode programs interactively. The optional index picks a program from a file which
+holds several.";
+
+const HELP: &str = "\
+Commands:
+  load <path> [index]   Load a program, discarding the current one.
+  run                   Run until the program halts or needs input.
+  step [n]              Execute n instructions, 1 by default.
+  input <values>        Give values to the program, one per input instruction, running until it
+                        needs more. A line holding only values does the same.
+  peek <addr> [count]   Print memory cells.
+  poke <addr> <value>   Write a memory cell.
+  set ip|base <value>   Move the instruction pointer or relative base.
+  regs                  Print the instruction pointer, relative base and next instruction.
+  reset                 Restart the program from its original memory.
+  save <path>           Save the session, so that `restore` can replay it.
+  restore <path>        Restore a saved session.
+  help                  Print this message.
+  quit                  Exit.";
+
+/// The values printed by one `peek` row.
+const ROW: usize = 8;
+
+/// A loaded program, along with the commands that brought the machine to its current state.
+struct Session {
+    machine: Machine,
+    /// Commands since the program was loaded or reset, in the form they are saved in.
+    log: Vec<String>,
+}
+
+impl Session {
+    fn new(program: Vec<i64>) -> Self {
+        Session {
+            machine: Machine::from_memory(&program),
+            log: Vec::new(),
+        }
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut session = None;
+
+    if let Some(path) = args.next() {
+        if path == "-h" || path == "--help" {
+            println!("{}", USAGE);
+            return;
+        }
+
+        match load(&path, args.next().as_deref()) {
+            Ok(loaded) => session = Some(loaded),
+            Err(e) => {
+                eprintln!("repl: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("{}", prompt(session.as_ref()));
+        let _ = std::io::stdout().flush();
+
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+
+        match command(&mut session, &line) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
+
+fn prompt(session: Option<&Session>) -> &'static str {
+    match session {
+        None => "> ",
+        Some(session) if session.machine.is_awaiting_input() => "input> ",
+        Some(_) => "intcode> ",
+    }
+}
+
+/// Runs one command line. Returns false when the REPL should exit.
+fn command(session: &mut Option<Session>, line: &str) -> Result<bool> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (name, args) = match words.split_first() {
+        Some((name, args)) => (*name, args),
+        None => return Ok(true),
+    };
+
+    match name {
+        "quit" | "exit" | "q" => return Ok(false),
+        "help" | "h" | "?" => println!("{}", HELP),
+        "load" => match args {
+            [path] | [path, _] => *session = Some(load(path, args.get(1).copied())?),
+            _ => return Err(String::from("Usage: load <path> [index]")),
+        },
+        "restore" => match args {
+            [path] => *session = Some(restore(path)?),
+            _ => return Err(String::from("Usage: restore <path>")),
+        },
+        _ => {
+            let session = session
+                .as_mut()
+                .ok_or("No program loaded; use `load <path>`")?;
+            inspect(session, name, args)?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Runs a command which needs a loaded program.
+fn inspect(session: &mut Session, name: &str, args: &[&str]) -> Result<()> {
+    match (name, args) {
+        ("peek" | "p", [addr]) => peek(&session.machine, parse(addr)?, 1)?,
+        ("peek" | "p", [addr, count]) => peek(&session.machine, parse(addr)?, parse(count)?)?,
+        ("peek" | "p", _) => return Err(String::from("Usage: peek <addr> [count]")),
+        ("regs" | "r", []) => regs(&session.machine),
+        ("save", [path]) => save(session, path)?,
+        ("save", _) => return Err(String::from("Usage: save <path>")),
+        _ => {
+            let line = std::iter::once(name)
+                .chain(args.iter().copied())
+                .collect::<Vec<&str>>()
+                .join(" ");
+            change(session, &line, &mut Printer)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a command which changes the machine, recording it in the session's log.
+///
+/// # Arguments
+///
+/// * `session` - The session to change.
+/// * `line` - The command.
+/// * `output` - Where the program's output goes.
+fn change<O: Output>(session: &mut Session, line: &str, output: &mut O) -> Result<()> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let machine = &mut session.machine;
+
+    // A line of bare values is input.
+    let (name, args) = match words.split_first() {
+        Some((name, _)) if name.parse::<i64>().is_ok() => ("input", &words[..]),
+        Some((name, args)) => (*name, args),
+        None => return Ok(()),
+    };
+
+    let logged = match (name, args) {
+        ("reset", []) => {
+            machine.reset();
+            session.log.clear();
+            return Ok(());
+        }
+        ("poke", [addr, value]) => {
+            let (addr, value) = (parse(addr)?, parse(value)?);
+            machine.set_force(addr, value);
+            format!("poke {} {}", addr, value)
+        }
+        ("poke", _) => return Err(String::from("Usage: poke <addr> <value>")),
+        ("set", ["ip", value]) => {
+            machine.set_ip(parse(value)?);
+            format!("set ip {}", machine.ip())
+        }
+        ("set", ["base", value]) => {
+            machine.set_base(parse(value)?);
+            format!("set base {}", machine.base())
+        }
+        ("set", _) => return Err(String::from("Usage: set ip|base <value>")),
+        ("run", []) => {
+            guard(|| report(machine.run_until_input(output), machine))?;
+            String::from("run")
+        }
+        ("step" | "s", [] | [_]) => {
+            let count = args.first().map_or(Ok(1), |n| parse(n))?;
+            for _ in 0..count {
+                if machine.is_awaiting_input() {
+                    return Err(String::from("The program is waiting for input"));
+                }
+
+                let halted = guard(|| machine.step(&mut 0, output))?;
+                session.log.push(String::from("step 1"));
+                if halted {
+                    println!("Halted");
+                    break;
+                }
+            }
+
+            return Ok(());
+        }
+        ("step" | "s", _) => return Err(String::from("Usage: step [n]")),
+        ("input" | "i", values) if !values.is_empty() => {
+            let values = values
+                .iter()
+                .flat_map(|v| v.split(','))
+                .filter(|v| !v.is_empty())
+                .map(parse)
+                .collect::<Result<Vec<i64>>>()?;
+
+            for mut value in values {
+                guard(|| machine.run_until_input(output))?;
+                if !machine.is_awaiting_input() {
+                    return Err(String::from("The program has halted"));
+                }
+
+                guard(|| machine.step(&mut value, output))?;
+                session.log.push(format!("input {}", value));
+            }
+
+            guard(|| report(machine.run_until_input(output), machine))?;
+            return Ok(());
+        }
+        ("input" | "i", _) => return Err(String::from("Usage: input <values>")),
+        _ => return Err(format!("Unknown command {:?}; try `help`", name)),
+    };
+
+    session.log.push(logged);
+    Ok(())
+}
+
+/// Runs part of the program, turning a fault into an error. Faults are only caught here, so a
+/// bug in the REPL itself still panics as usual.
+fn guard<T, F: FnOnce() -> T>(f: F) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .map_err(|payload| format!("The program faulted: {}", panic_message(&*payload)))
+}
+
+fn report(status: Status, machine: &Machine) {
+    match status {
+        Status::Halted => println!("Halted"),
+        Status::AwaitingInput => println!("Waiting for input at {}", machine.ip()),
+        Status::Paused => println!("Paused at {}", machine.ip()),
+    }
+}
+
+fn peek(machine: &Machine, addr: usize, count: usize) -> Result<()> {
+    let end = addr
+        .checked_add(count)
+        .ok_or_else(|| format!("{} cells from {} run past the end of memory", count, addr))?;
+
+    for start in (addr..end).step_by(ROW) {
+        let values = machine
+            .read_range(start..start.saturating_add(ROW).min(end))
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>();
+        println!("{:>6}: {}", start, values.join(" "));
+    }
+
+    Ok(())
+}
+
+fn regs(machine: &Machine) {
+    let ip = machine.ip();
+    let width = disassemble::width(machine.read(ip)).unwrap_or(1);
+    let next = machine
+        .read_range(ip..ip.saturating_add(width))
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>();
+
+    println!("ip: {}  base: {}", ip, machine.base());
+    println!("next: {}", next.join(","));
+}
+
+fn parse<T: std::str::FromStr>(text: &str) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    text.parse()
+        .map_err(|e| format!("Bad value {:?}: {}", text, e))
+}
+
+fn load(path: &str, index: Option<&str>) -> Result<Session> {
+    let index = index.map_or(Ok(0), parse)?;
+    let mut programs =
+        loader::load_file(path).map_err(|e| format!("Could not load {}: {}", path, e))?;
+
+    if index >= programs.len() {
+        return Err(format!(
+            "{} holds {} programs, no program {}",
+            path,
+            programs.len(),
+            index
+        ));
+    }
+
+    Ok(Session::new(programs.swap_remove(index)))
+}
+
+/// Saves the session as the original program followed by the commands that changed it.
+fn save(session: &Session, path: &str) -> Result<()> {
+    let program = session
+        .machine
+        .program()
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+
+    let mut text = format!("# intcode session\nprogram: {}\n", program);
+    for line in &session.log {
+        text.push_str(line);
+        text.push('\n');
+    }
+
+    std::fs::write(path, text).map_err(|e| format!("Could not write {}: {}", path, e))
+}
+
+/// Restores a saved session by replaying its commands, without printing the program's output.
+fn restore(path: &str) -> Result<Session> {
+    let text =
+        std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+    let program = lines
+        .next()
+        .and_then(|line| line.strip_prefix("program:"))
+        .ok_or_else(|| format!("{} does not start with a program", path))?;
+    let program = loader::parse_program(program).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut session = Session::new(program);
+    for line in lines {
+        change(&mut session, line, &mut Vec::new())
+            .map_err(|e| format!("{}: replaying {:?}: {}", path, line, e))?;
+    }
+
+    println!("Restored {} commands", session.log.len());
+    Ok(session)
+}
+
+/// Prints each value as the program produces it.
+struct Printer;
+
+impl Output for Printer {
+    fn write(&mut self, val: i64) {
+        println!("output: {}", val);
+    }
+}
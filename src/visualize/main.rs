@@ -0,0 +1,500 @@
+use machine::{disassemble, loader, panic_message, Input, Machine};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::io::{BufRead, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "\
+Usage: visualize <program> [options]
+
+Steps through an Intcode program, drawing its memory as it runs.
+
+Options:
+  -p, --program <index>    Which program to run if the file holds several. Defaults to 0.
+  -i, --input <values>     Input values, separated by commas. May be repeated. Once they are
+                           used up, the program pauses to ask for more.
+  -s, --set <addr>=<value> Patch a memory cell before running. May be repeated.
+      --delay <ms>         The delay between steps. Defaults to 200.
+      --columns <n>        Cells per row. Defaults to 10.
+      --rows <n>           Rows of memory to show. Defaults to 16.
+  -h, --help               Print this message.
+
+Keys:
+  space  Pause or resume.     n  Execute one step while paused.
+  +  Run faster.  -  Run slower.     q  Quit.";
+
+/// The longest delay between steps.
+const MAX_DELAY: u64 = 5_000;
+
+/// The number of steps for which a written cell stays highlighted.
+const FADE: usize = 8;
+
+/// The number of output values the output pane shows.
+const OUTPUTS: usize = 12;
+
+/// The shortest time between two frames, so that fast runs are not slowed down by drawing.
+const FRAME: Duration = Duration::from_millis(30);
+
+const RESET: &str = "\x1b[0m";
+const IP: &str = "\x1b[7;1m";
+const PARAM: &str = "\x1b[36m";
+const BASE: &str = "\x1b[45m";
+const FRESH: &str = "\x1b[31;1m";
+const RECENT: &str = "\x1b[33m";
+
+struct Options {
+    program: String,
+    index: usize,
+    inputs: VecDeque<i64>,
+    patches: Vec<(usize, i64)>,
+    delay: u64,
+    columns: usize,
+    rows: usize,
+}
+
+/// The state of the visualizer around the machine.
+struct View {
+    machine: Machine,
+    inputs: VecDeque<i64>,
+    consumed: Vec<i64>,
+    outputs: Vec<i64>,
+    /// The step at which each cell was last written.
+    written: HashMap<usize, usize>,
+    steps: usize,
+    delay: u64,
+    paused: bool,
+    status: String,
+    /// The first row shown.
+    top: usize,
+    columns: usize,
+    rows: usize,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("visualize: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let options = match parse_args(std::env::args().skip(1))? {
+        Some(options) => options,
+        None => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    let programs = loader::load_file(&options.program)
+        .map_err(|e| format!("Could not load {}: {}", options.program, e))?;
+    let program = programs.get(options.index).ok_or_else(|| {
+        format!(
+            "{} holds {} programs, no program {}",
+            options.program,
+            programs.len(),
+            options.index
+        )
+    })?;
+
+    let mut machine = Machine::from_memory(program);
+    for (addr, value) in &options.patches {
+        machine.set_force(*addr, *value);
+    }
+
+    let mut view = View {
+        machine,
+        inputs: options.inputs,
+        consumed: Vec::new(),
+        outputs: Vec::new(),
+        written: HashMap::new(),
+        steps: 0,
+        delay: options.delay,
+        paused: false,
+        status: String::from("running"),
+        top: 0,
+        columns: options.columns,
+        rows: options.rows,
+    };
+
+    let terminal = Terminal::new();
+
+    // A fault's panic message is printed as it happens, so put the terminal back first and
+    // leave the last frame above it.
+    let saved = terminal.saved.clone();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore(saved.as_deref());
+        println!();
+        previous(info)
+    }));
+
+    print!("\x1b[?25l\x1b[2J");
+    let result = animate(&mut view, &terminal);
+    if !(terminal.interactive() && view.faulted()) {
+        view.draw()?;
+    }
+    drop(terminal);
+    println!();
+    result
+}
+
+/// Runs the machine, drawing it after each step, until it stops and the user quits.
+fn animate(view: &mut View, terminal: &Terminal) -> Result<()> {
+    let mut drawn: Option<Instant> = None;
+    let mut stopped = false;
+
+    loop {
+        let mut step = !view.paused && !stopped;
+        while let Some(key) = terminal.key() {
+            match key {
+                b'q' => return Ok(()),
+                b' ' => view.paused = !view.paused,
+                b'n' => step = !stopped,
+                b'+' | b'=' => view.delay /= 2,
+                b'-' => view.delay = (view.delay * 2).clamp(1, MAX_DELAY),
+                _ => {}
+            }
+        }
+
+        if step {
+            if view.machine.is_awaiting_input() && view.inputs.is_empty() {
+                view.draw()?;
+                match terminal.prompt()? {
+                    Some(values) => view.inputs.extend(values),
+                    None => return Ok(()),
+                }
+            }
+
+            stopped = view.step();
+
+            // The terminal no longer reads keys one at a time once a fault has restored it.
+            if terminal.interactive() && view.faulted() {
+                return Ok(());
+            }
+        } else if !terminal.interactive() && (view.paused || stopped) {
+            return Ok(());
+        }
+
+        let due = drawn.is_none_or(|at| at.elapsed() >= FRAME);
+        if due || view.delay >= FRAME.as_millis() as u64 || !step {
+            view.draw()?;
+            drawn = Some(Instant::now());
+        }
+
+        let delay = if step {
+            view.delay
+        } else {
+            FRAME.as_millis() as u64
+        };
+        thread::sleep(Duration::from_millis(delay));
+    }
+}
+
+impl View {
+    /// Executes one instruction and returns whether the machine stopped.
+    fn step(&mut self) -> bool {
+        let target = self.machine.write_address();
+        let mut input = Recorder {
+            inputs: &mut self.inputs,
+            consumed: &mut self.consumed,
+        };
+        let machine = &mut self.machine;
+        let outputs = &mut self.outputs;
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| machine.step(&mut input, outputs)));
+        self.steps += 1;
+
+        match result {
+            Ok(false) => {
+                if let Some(addr) = target {
+                    self.written.insert(addr, self.steps);
+                }
+
+                false
+            }
+            Ok(true) => {
+                self.status = String::from("halted");
+                true
+            }
+            Err(payload) => {
+                self.status = format!("faulted: {}", panic_message(&*payload));
+                true
+            }
+        }
+    }
+
+    /// Returns whether the machine stopped on a fault.
+    fn faulted(&self) -> bool {
+        self.status.starts_with("faulted")
+    }
+
+    /// Returns the number of cells the instruction at `ip` spans.
+    fn width(&self) -> usize {
+        disassemble::width(self.machine.read(self.machine.ip())).unwrap_or(1)
+    }
+
+    fn draw(&mut self) -> Result<()> {
+        let ip = self.machine.ip();
+        let base = self.machine.base();
+        let row = ip / self.columns;
+
+        // Scroll so that the instruction stays in view.
+        if row < self.top {
+            self.top = row;
+        } else if row + 1 >= self.top + self.rows {
+            self.top = (row + 2).saturating_sub(self.rows);
+        }
+
+        let cell_width = self
+            .machine
+            .cells()
+            .map(|(_, v)| v.to_string().len())
+            .max()
+            .unwrap_or(1)
+            .max(4);
+
+        let mut frame = String::from("\x1b[H");
+        let state = if self.status == "running" && self.paused {
+            "paused"
+        } else {
+            &self.status
+        };
+        frame.push_str(&format!(
+            "ip {:<6} base {:<6} step {:<8} delay {}ms  [{}]\x1b[K\n\x1b[K\n",
+            ip, base, self.steps, self.delay, state
+        ));
+
+        for row in self.top..self.top + self.rows {
+            frame.push_str(&format!("{:>6} |", row * self.columns));
+            for addr in row * self.columns..(row + 1) * self.columns {
+                let style = if addr == ip {
+                    IP
+                } else if (ip + 1..ip + self.width()).contains(&addr) {
+                    PARAM
+                } else if base >= 0 && addr == base as usize {
+                    BASE
+                } else {
+                    match self.written.get(&addr) {
+                        Some(&step) if step == self.steps => FRESH,
+                        Some(&step) if self.steps - step < FADE => RECENT,
+                        _ => "",
+                    }
+                };
+
+                frame.push_str(&format!(
+                    " {}{:>width$}{}",
+                    style,
+                    self.machine.read(addr),
+                    RESET,
+                    width = cell_width
+                ));
+            }
+            frame.push_str("\x1b[K\n");
+        }
+
+        let pending = join(self.inputs.iter());
+        frame.push_str(&format!(
+            "\x1b[K\ninput  {}{}\x1b[K\n",
+            join(self.consumed.iter()),
+            if pending.is_empty() {
+                String::new()
+            } else {
+                format!(" | pending {}", pending)
+            }
+        ));
+        let skip = self.outputs.len().saturating_sub(OUTPUTS);
+        frame.push_str(&format!(
+            "output {}{}\x1b[K\n\x1b[K\n",
+            if skip > 0 { "... " } else { "" },
+            join(self.outputs[skip..].iter())
+        ));
+        frame.push_str(&format!(
+            "{}ip{} {}params{} {}base{} {}written{}   space pause  n step  +/- speed  q quit\x1b[K\x1b[J",
+            IP, RESET, PARAM, RESET, BASE, RESET, FRESH, RESET
+        ));
+
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Supplies queued input, remembering what was consumed.
+struct Recorder<'a> {
+    inputs: &'a mut VecDeque<i64>,
+    consumed: &'a mut Vec<i64>,
+}
+
+impl<'a> Input for Recorder<'a> {
+    fn get(&mut self) -> i64 {
+        let value = self.inputs.pop_front().expect("Input queue is empty");
+        self.consumed.push(value);
+        value
+    }
+}
+
+/// The terminal, switched to unbuffered input for as long as this lives. When stdin is not a
+/// terminal, no keys are read and the program runs without pausing.
+struct Terminal {
+    /// The settings to restore, as printed by `stty -g`.
+    saved: Option<String>,
+}
+
+impl Terminal {
+    fn new() -> Self {
+        let saved = stty(&["-g"]).map(|settings| settings.trim().to_string());
+        let terminal = Terminal { saved };
+        terminal.raw();
+        terminal
+    }
+
+    fn interactive(&self) -> bool {
+        self.saved.is_some()
+    }
+
+    /// Stops reads from waiting for a line, or for any key at all.
+    fn raw(&self) {
+        if self.interactive() {
+            stty(&["-icanon", "-echo", "min", "0", "time", "0"]);
+        }
+    }
+
+    /// Returns the next key pressed, if there is one.
+    fn key(&self) -> Option<u8> {
+        if !self.interactive() {
+            return None;
+        }
+
+        let mut byte = [0];
+        match std::io::stdin().lock().read(&mut byte) {
+            Ok(1) => Some(byte[0]),
+            _ => None,
+        }
+    }
+
+    /// Asks for more input values. Returns `None` if there are none to be had.
+    fn prompt(&self) -> Result<Option<Vec<i64>>> {
+        if !self.interactive() {
+            return Ok(None);
+        }
+
+        if let Some(saved) = &self.saved {
+            stty(&[saved]);
+        }
+
+        print!("\n\x1b[?25hinput> ");
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        print!("\x1b[?25l\x1b[2J");
+        self.raw();
+
+        let values = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse::<i64>())
+            .collect::<std::result::Result<Vec<i64>, _>>()?;
+        Ok(if values.is_empty() {
+            None
+        } else {
+            Some(values)
+        })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        restore(self.saved.as_deref());
+    }
+}
+
+/// Puts back the terminal settings printed by `stty -g`, if there are any, and shows the cursor.
+fn restore(saved: Option<&str>) {
+    if let Some(saved) = saved {
+        stty(&[saved]);
+    }
+
+    print!("\x1b[?25h");
+    let _ = std::io::stdout().flush();
+}
+
+/// Runs `stty` on the terminal behind stdin, returning its output if it succeeded.
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        String::from_utf8(output.stdout).ok()
+    } else {
+        None
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>> {
+    let mut program = None;
+    let mut options = Options {
+        program: String::new(),
+        index: 0,
+        inputs: VecDeque::new(),
+        patches: Vec::new(),
+        delay: 200,
+        columns: 10,
+        rows: 16,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-p" | "--program" => options.index = value(&arg)?.parse()?,
+            "-i" | "--input" => {
+                for v in value(&arg)?.split(',').filter(|v| !v.trim().is_empty()) {
+                    options.inputs.push_back(v.trim().parse()?);
+                }
+            }
+            "-s" | "--set" => {
+                let patch = value(&arg)?;
+                let (addr, val) = patch
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected <addr>=<value>, found {:?}", patch))?;
+                options
+                    .patches
+                    .push((addr.trim().parse()?, val.trim().parse()?));
+            }
+            "--delay" => options.delay = value(&arg)?.parse::<u64>()?.min(MAX_DELAY),
+            "--columns" => options.columns = value(&arg)?.parse::<usize>()?.max(1),
+            "--rows" => options.rows = value(&arg)?.parse::<usize>()?.max(1),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("Unknown option {}\n\n{}", arg, USAGE).into())
+            }
+            _ if program.is_none() => program = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg).into()),
+        }
+    }
+
+    options.program = program.ok_or_else(|| format!("Missing program\n\n{}", USAGE))?;
+    Ok(Some(options))
+}
+
+fn join<'a, I: Iterator<Item = &'a i64>>(values: I) -> String {
+    values
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
@@ -0,0 +1,136 @@
+use machine::arcade::{Arcade, Controller, FollowBall, Tile, PALETTE};
+use machine::gif::Gif;
+use machine::Machine;
+
+/// A game which draws a block, a ball and a paddle at (1, 2), then moves the paddle by the
+/// joystick three times and shows 100 times its final `x` as the score.
+const GAME: &str = "104,1,104,0,104,2,104,2,104,1,104,4,4,54,104,2,104,3,3,55,4,54,104,2,104,0,\
+                    1,54,55,54,4,54,104,2,104,3,1001,56,-1,56,1005,56,18,1002,54,100,57,104,-1,\
+                    104,0,4,57,99,1,0,3,0";
+
+#[test]
+fn follows_the_ball() {
+    let mut arcade = Arcade::new(Machine::new(GAME).unwrap());
+    let mut screens = Vec::new();
+    let score = arcade.play(&mut FollowBall, |arcade| screens.push(arcade.render()));
+
+    assert_eq!(score, 200);
+    assert!(arcade.is_halted());
+    assert_eq!(arcade.frames(), 3);
+    assert_eq!(arcade.paddle(), Some((2, 2)));
+    assert_eq!(arcade.ball(), Some((2, 1)));
+    assert_eq!(arcade.tile((1, 0)), Tile::Block);
+    assert_eq!(arcade.tile((1, 2)), Tile::Empty);
+    assert_eq!(arcade.blocks(), 1);
+
+    assert_eq!(screens.len(), 4);
+    assert_eq!(screens[0], "= \n o\n_ \nScore: 0\n");
+    assert_eq!(screens[3], "= \n o\n _\nScore: 200\n");
+}
+
+#[test]
+fn takes_any_controller() {
+    let mut arcade = Arcade::new(Machine::new(GAME).unwrap());
+    let mut seen = Vec::new();
+    let score = arcade.play(
+        &mut |arcade: &Arcade| {
+            seen.push(arcade.paddle().unwrap());
+            -1
+        },
+        |_| {},
+    );
+
+    assert_eq!(score, -200);
+    assert_eq!(seen, vec![(1, 2), (0, 2), (-1, 2)]);
+}
+
+/// Decodes the pixels of each frame of a GIF written by `Gif`, which only uses literal codes.
+fn decode(gif: &[u8]) -> Vec<Vec<u8>> {
+    let bits = u32::from(gif[10] & 7) + 1;
+    let mut at = 13 + 3 * (1 << bits) + 19;
+    let mut frames = Vec::new();
+    while gif[at] == 0x21 {
+        at += 8 + 10;
+        let width = gif[at] as u32 + 1;
+        at += 1;
+
+        let mut data = Vec::new();
+        while gif[at] != 0 {
+            let length = gif[at] as usize;
+            data.extend_from_slice(&gif[at + 1..at + 1 + length]);
+            at += 1 + length;
+        }
+        at += 1;
+
+        let mut pixels = Vec::new();
+        let (mut buffer, mut filled) = (0u32, 0);
+        for byte in data {
+            buffer |= u32::from(byte) << filled;
+            filled += 8;
+            while filled >= width {
+                let code = buffer & ((1 << width) - 1);
+                buffer >>= width;
+                filled -= width;
+                match code {
+                    c if c < 1 << bits => pixels.push(c as u8),
+                    c if c == 1 << bits => {}
+                    _ => break,
+                }
+            }
+        }
+        frames.push(pixels);
+    }
+
+    assert_eq!(gif[at..], [0x3b]);
+    frames
+}
+
+#[test]
+fn records_frames() {
+    let mut arcade = Arcade::new(Machine::new(GAME).unwrap());
+    let mut gif = Gif::new(3, 3, &PALETTE, 5);
+    arcade.play(&mut FollowBall, |arcade| {
+        gif.add_frame(&arcade.pixels(3, 3))
+    });
+    assert_eq!(gif.frames(), 4);
+
+    let image = gif.finish();
+    assert_eq!(&image[..10], b"GIF89a\x03\x00\x03\x00");
+    let frames = decode(&image);
+    assert_eq!(frames[0], vec![0, 2, 0, 0, 0, 4, 0, 3, 0]);
+    assert_eq!(frames[3], vec![0, 2, 0, 0, 0, 4, 0, 0, 3]);
+
+    // Long frames need many clear codes.
+    let pixels: Vec<u8> = (0..1000).map(|n| (n % 5) as u8).collect();
+    let mut gif = Gif::new(100, 10, &PALETTE, 5);
+    gif.add_frame(&pixels);
+    assert_eq!(decode(&gif.finish()), vec![pixels]);
+}
+
+/// Tilts the joystick right, and quits after a number of frames.
+struct Quitter {
+    frames: usize,
+}
+
+impl Controller for Quitter {
+    fn joystick(&mut self, _: &Arcade) -> i64 {
+        1
+    }
+
+    fn quit(&mut self) -> bool {
+        self.frames = self.frames.saturating_sub(1);
+        self.frames == 0
+    }
+}
+
+#[test]
+fn stops_when_the_controller_quits() {
+    let mut arcade = Arcade::new(Machine::new(GAME).unwrap());
+    let mut screens = 0;
+    let score = arcade.play(&mut Quitter { frames: 2 }, |_| screens += 1);
+
+    assert_eq!(score, 0);
+    assert!(!arcade.is_halted());
+    assert_eq!((arcade.frames(), screens), (1, 2));
+    assert_eq!(arcade.paddle(), Some((2, 2)));
+}
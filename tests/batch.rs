@@ -0,0 +1,88 @@
+use machine::batch::{self, Batch};
+use machine::combinatorics::permutations;
+use machine::{loader, Machine};
+use std::collections::VecDeque;
+
+fn day(n: u32) -> Vec<i64> {
+    loader::load_file(format!("src/day{}/input.txt", n))
+        .unwrap()
+        .remove(0)
+}
+
+/// Runs each input vector on its own machine.
+fn independently(program: &[i64], inputs: &[Vec<i64>]) -> Vec<Vec<i64>> {
+    inputs
+        .iter()
+        .map(|input| {
+            let mut output = Vec::new();
+            let mut input: VecDeque<i64> = input.iter().copied().collect();
+            Machine::from_memory(program).execute(&mut input, &mut output);
+            output
+        })
+        .collect()
+}
+
+#[test]
+fn matches_independent_runs() {
+    // Day 5 rewrites its own instructions, and branches on its input.
+    let program = day(5);
+    let inputs: Vec<Vec<i64>> = (0..10).map(|n| vec![n % 2 * 4 + 1]).collect();
+    assert_eq!(
+        batch::run(&program, &inputs),
+        independently(&program, &inputs)
+    );
+
+    // Day 9 uses relative addressing and memory beyond the program.
+    let program = day(9);
+    let inputs = vec![vec![1], vec![1]];
+    assert_eq!(
+        batch::run(&program, &inputs),
+        independently(&program, &inputs)
+    );
+}
+
+#[test]
+fn runs_amplifier_stages_in_lockstep() {
+    let program = day(7);
+    let settings: Vec<Vec<i64>> = permutations((0..5).collect()).collect();
+    let mut signals = vec![0; settings.len()];
+    for stage in 0..5 {
+        let inputs: Vec<Vec<i64>> = settings
+            .iter()
+            .zip(&signals)
+            .map(|(setting, signal)| vec![setting[stage], *signal])
+            .collect();
+        signals = batch::run(&program, &inputs)
+            .into_iter()
+            .map(|output| output[0])
+            .collect();
+    }
+
+    assert_eq!(signals.into_iter().max(), Some(92663));
+}
+
+#[test]
+fn splits_and_merges_groups() {
+    // Outputs 1 for nonzero inputs and 2 for zero, then both paths meet to output 3.
+    let program = [
+        3, 20, 1006, 20, 10, 104, 1, 1105, 1, 15, 104, 2, 1105, 1, 15, 104, 3, 99,
+    ];
+    let inputs = vec![vec![1], vec![0], vec![3]];
+    let mut batch = Batch::new(&program, &inputs);
+    let mut groups = Vec::new();
+    while !batch.step() {
+        groups.push(batch.groups());
+    }
+
+    assert_eq!(groups, vec![1, 2, 2, 1, 1]);
+    assert_eq!(batch.passes(), 8);
+    assert_eq!(batch.outputs(), vec![&[1, 3][..], &[2, 3], &[1, 3]]);
+    assert!(batch::run(&program, &[]).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Unknown op code: 42")]
+fn panics_as_machines_do() {
+    // Each lane executes its input as an instruction.
+    batch::run(&[3, 2, 0], &[vec![99], vec![42]]);
+}
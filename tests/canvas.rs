@@ -0,0 +1,80 @@
+use machine::canvas::Canvas;
+use machine::frames::Frames;
+use machine::Machine;
+
+/// Returns a program which outputs the given values and halts.
+fn outputs(values: &[i64]) -> Machine {
+    let mut program: Vec<String> = values.iter().map(|v| format!("104,{}", v)).collect();
+    program.push("99".to_string());
+    Machine::new(&program.join(",")).unwrap()
+}
+
+#[test]
+fn groups_outputs_into_frames() {
+    let mut pairs = Vec::new();
+    let mut output = Frames::new(|pair: (i64, i64)| pairs.push(pair));
+    outputs(&[1, 0, 0, 1, 5]).execute(&mut 0, &mut output);
+    assert_eq!(output.pending(), &[5]);
+    drop(output);
+    assert_eq!(pairs, vec![(1, 0), (0, 1)]);
+
+    let mut frames = Vec::new();
+    let mut output = Frames::new(|frame: [i64; 4]| frames.push(frame));
+    outputs(&[1, 2, 3, 4, 5, 6, 7, 8]).execute(&mut 0, &mut output);
+    assert!(output.pending().is_empty());
+    drop(output);
+    assert_eq!(frames, vec![[1, 2, 3, 4], [5, 6, 7, 8]]);
+}
+
+#[test]
+fn draws_tiles_and_keeps_special_values_apart() {
+    let mut canvas = Canvas::new().special((-1, 0));
+    let mut output = Frames::new(|(x, y, tile)| canvas.draw((x, y), tile));
+    #[rustfmt::skip]
+    outputs(&[
+        0, 0, 1,
+        2, 1, 2,
+        -1, 0, 1234,
+        1, 0, 1,
+        0, 0, 2,
+    ])
+    .execute(&mut 0, &mut output);
+    drop(output);
+
+    assert_eq!(canvas.value((-1, 0)), Some(1234));
+    assert_eq!(canvas.len(), 3);
+    assert_eq!((canvas.count(1), canvas.count(2)), (1, 2));
+    assert_eq!(canvas.get((1, 1)), None);
+    assert_eq!(canvas.bounds(), Some((0..=2, 0..=1)));
+
+    let glyph = |tile| match tile {
+        Some(1) => '#',
+        Some(2) => 'o',
+        _ => ' ',
+    };
+    assert_eq!(canvas.render(glyph), "o# \n  o\n");
+}
+
+#[test]
+fn renders_ppm_images() {
+    let mut canvas = Canvas::new();
+    canvas.draw((-1, 5), 1);
+    canvas.draw((0, 5), 0);
+
+    let color = |tile| match tile {
+        Some(1) => [255, 255, 255],
+        _ => [0, 0, 0],
+    };
+    let image = canvas.ppm(color, 2);
+    let header = b"P6\n4 2\n255\n";
+    assert_eq!(&image[..header.len()], header);
+
+    let row = [[255, 255, 255], [255, 255, 255], [0, 0, 0], [0, 0, 0]].concat();
+    assert_eq!(
+        &image[header.len()..],
+        [row.clone(), row].concat().as_slice()
+    );
+
+    assert_eq!(Canvas::new().ppm(color, 1), b"P6\n0 0\n255\n");
+    assert_eq!(Canvas::new().render(|_| '#'), "");
+}
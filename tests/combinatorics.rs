@@ -0,0 +1,142 @@
+use machine::combinatorics::{
+    combination_count, combinations, k_permutations, nth_permutation, nth_product,
+    permutation_count, permutations, product, product_count,
+};
+use std::collections::HashSet;
+
+#[test]
+fn permutes_by_single_swaps() {
+    let all: Vec<Vec<i32>> = permutations(vec![1, 2, 3, 4]).collect();
+    assert_eq!(all.len(), 24);
+    assert_eq!(all.iter().collect::<HashSet<_>>().len(), 24);
+    assert_eq!(all[0], vec![1, 2, 3, 4]);
+    assert_eq!(all[1], vec![2, 1, 3, 4]);
+    for pair in all.windows(2) {
+        let changed = pair[0].iter().zip(&pair[1]).filter(|(a, b)| a != b);
+        assert_eq!(changed.count(), 2);
+    }
+
+    assert_eq!(
+        permutations(Vec::<i32>::new()).collect::<Vec<_>>(),
+        vec![vec![]]
+    );
+    assert_eq!(permutations(vec!['a']).collect::<Vec<_>>(), vec![vec!['a']]);
+}
+
+#[test]
+fn selects_in_lexicographic_order() {
+    assert_eq!(
+        k_permutations(vec!['a', 'b', 'c'], 2).collect::<Vec<_>>(),
+        vec![
+            vec!['a', 'b'],
+            vec!['a', 'c'],
+            vec!['b', 'a'],
+            vec!['b', 'c'],
+            vec!['c', 'a'],
+            vec!['c', 'b'],
+        ]
+    );
+
+    let all: Vec<Vec<i32>> = k_permutations(vec![0, 1, 2, 3], 4).collect();
+    assert_eq!(all.len(), 24);
+    assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert_eq!(
+        k_permutations(vec![1, 2], 0).collect::<Vec<_>>(),
+        vec![vec![]]
+    );
+    assert_eq!(k_permutations(vec![1, 2], 3).count(), 0);
+}
+
+#[test]
+fn combines() {
+    assert_eq!(
+        combinations(vec![1, 2, 3, 4], 2).collect::<Vec<_>>(),
+        vec![
+            vec![1, 2],
+            vec![1, 3],
+            vec![1, 4],
+            vec![2, 3],
+            vec![2, 4],
+            vec![3, 4],
+        ]
+    );
+
+    assert_eq!(
+        combinations(vec![1, 2, 3], 3).collect::<Vec<_>>(),
+        vec![vec![1, 2, 3]]
+    );
+    assert_eq!(
+        combinations(vec![1, 2, 3], 0).collect::<Vec<_>>(),
+        vec![vec![]]
+    );
+    assert_eq!(combinations(vec![1, 2, 3], 4).count(), 0);
+}
+
+#[test]
+fn takes_products() {
+    assert_eq!(
+        product(vec![vec![0, 1], vec![5, 6, 7]]).collect::<Vec<_>>(),
+        vec![
+            vec![0, 5],
+            vec![0, 6],
+            vec![0, 7],
+            vec![1, 5],
+            vec![1, 6],
+            vec![1, 7],
+        ]
+    );
+
+    assert_eq!(
+        product(Vec::<Vec<i32>>::new()).collect::<Vec<_>>(),
+        vec![vec![]]
+    );
+    assert_eq!(product(vec![vec![1, 2], vec![]]).count(), 0);
+}
+
+#[test]
+fn counts() {
+    assert_eq!(permutation_count(5, 5), Some(120));
+    assert_eq!(permutation_count(10, 3), Some(720));
+    assert_eq!(permutation_count(3, 0), Some(1));
+    assert_eq!(permutation_count(3, 4), Some(0));
+    assert_eq!(permutation_count(100, 100), None);
+
+    assert_eq!(combination_count(5, 2), Some(10));
+    assert_eq!(combination_count(52, 5), Some(2_598_960));
+    assert_eq!(combination_count(4, 0), Some(1));
+    assert_eq!(combination_count(4, 5), Some(0));
+
+    assert_eq!(product_count(vec![2, 3, 4]), Some(24));
+    assert_eq!(product_count(vec![2, 0, 4]), Some(0));
+    assert_eq!(product_count(Vec::new()), Some(1));
+    assert_eq!(product_count(vec![usize::MAX, 2]), None);
+
+    for (n, k) in [(4, 2), (5, 5), (6, 3)].iter().copied() {
+        let items: Vec<usize> = (0..n).collect();
+        assert_eq!(
+            k_permutations(items.clone(), k).count(),
+            permutation_count(n, k).unwrap()
+        );
+        assert_eq!(
+            combinations(items, k).count(),
+            combination_count(n, k).unwrap()
+        );
+    }
+}
+
+#[test]
+fn ranks_match_iteration_order() {
+    let items = ['a', 'b', 'c', 'd', 'e'];
+    for (rank, selection) in k_permutations(items.to_vec(), 3).enumerate() {
+        assert_eq!(nth_permutation(&items, 3, rank), Some(selection));
+    }
+    assert_eq!(nth_permutation(&items, 3, 60), None);
+    assert_eq!(nth_permutation(&items, 6, 0), None);
+
+    let pools = vec![vec![1, 2], vec![3], vec![4, 5, 6]];
+    for (rank, sequence) in product(pools.clone()).enumerate() {
+        assert_eq!(nth_product(&pools, rank), Some(sequence));
+    }
+    assert_eq!(nth_product(&pools, 6), None);
+}
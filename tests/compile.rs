@@ -0,0 +1,282 @@
+use machine::compile::{self, Trap};
+use machine::loader;
+use machine::Machine;
+use std::collections::VecDeque;
+use std::process::{Command, Stdio};
+
+const BUDGET: usize = 10_000_000;
+
+fn day(n: u32) -> Vec<i64> {
+    loader::load_file(format!("src/day{}/input.txt", n))
+        .unwrap()
+        .remove(0)
+}
+
+#[test]
+fn day2_matches_machine() {
+    let mut memory = day(2);
+    memory[1] = 12;
+    memory[2] = 2;
+    compile::verify(&memory, &[], BUDGET).unwrap();
+    assert_eq!(
+        compile::compile(&memory)
+            .run(&mut 0, &mut 0, BUDGET)
+            .unwrap()[0],
+        5482655
+    );
+}
+
+#[test]
+fn day5_is_self_modifying() {
+    // The program adds its input to the word at 6, turning it into a valid instruction.
+    let program = compile::compile(&day(5));
+    let result = program.run(&mut VecDeque::from(vec![1]), &mut Vec::new(), BUDGET);
+    assert_eq!(result, Err(Trap::Uncompiled(6)));
+}
+
+#[test]
+fn day7_matches_machine() {
+    for phase in 0..5 {
+        compile::verify(&day(7), &[phase, 17], BUDGET).unwrap();
+    }
+}
+
+#[test]
+fn day9_matches_machine() {
+    assert_eq!(compile::verify(&day(9), &[1], BUDGET), Ok(vec![3345854957]));
+    assert_eq!(compile::verify(&day(9), &[2], BUDGET), Ok(vec![68938]));
+}
+
+#[test]
+fn traps_on_bad_jumps_and_addresses() {
+    let run = |memory: &[i64]| compile::compile(memory).run(&mut 0, &mut Vec::new(), BUDGET);
+    assert_eq!(run(&[1105, 1, 7, 99]), Err(Trap::Uncompiled(7)));
+    assert_eq!(run(&[1001, 0, 1, 0, 1105, 1, 0]), Err(Trap::Modified(0)));
+    assert_eq!(run(&[4, -1, 99]), Err(Trap::BadAddress(-1)));
+    assert_eq!(run(&[109, -5, 204, 0, 99]), Err(Trap::BadAddress(-5)));
+}
+
+#[test]
+fn generates_code_for_each_instruction() {
+    let program = compile::compile(&day(9));
+    let c = program.to_c();
+    let wat = program.to_wat();
+    for instruction in &program.instructions {
+        assert!(c.contains(&format!("case {}:", instruction.addr)));
+        assert!(wat.contains(&format!("(block $i{}\n", instruction.addr)));
+    }
+}
+
+/// The host side of a compiled program, which takes its input from the command line and prints
+/// the halt code, the output and the final memory, each on a line of its own.
+const C_HOST: &str = r#"
+#include <stdio.h>
+#include <stdlib.h>
+
+static char **inputs;
+static int remaining;
+
+int64_t intcode_input(void)
+{
+    if (remaining == 0) {
+        fprintf(stderr, "out of input\n");
+        exit(2);
+    }
+    remaining--;
+    return strtoll(*inputs++, NULL, 10);
+}
+
+void intcode_output(int64_t value)
+{
+    printf("%lld ", (long long)value);
+}
+
+int main(int argc, char **argv)
+{
+    inputs = argv + 1;
+    remaining = argc - 1;
+    printf("\n");
+    int code = intcode_run();
+    printf("\n%d\n", code);
+    for (int i = 0; i < CELLS; i++)
+        printf("%lld ", (long long)mem[i]);
+    printf("\n");
+    return 0;
+}
+"#;
+
+/// The same host for a WebAssembly module, run by Node.js.
+const WASM_HOST: &str = r#"
+const fs = require("fs");
+const inputs = process.argv.slice(3).map(BigInt);
+const output = [];
+const imports = {
+    intcode: {
+        input: () => {
+            if (inputs.length == 0) throw new Error("out of input");
+            return inputs.shift();
+        },
+        output: (value) => output.push(value),
+    },
+};
+WebAssembly.instantiate(fs.readFileSync(process.argv[2]), imports).then(({ instance }) => {
+    const code = instance.exports.run();
+    const memory = new BigInt64Array(instance.exports.memory.buffer);
+    console.log("\n" + output.join(" ") + "\n" + code);
+    console.log(memory.join(" "));
+});
+"#;
+
+/// The halt code, output and final memory of a program, as reported by a host.
+type Run = (i32, Vec<i64>, Vec<i64>);
+
+/// Returns whether a tool can be run, so that tests which need it can be skipped without it.
+fn available(tool: &str, version: &str) -> bool {
+    Command::new(tool)
+        .arg(version)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+/// Runs a host program with the given inputs, and parses what it prints.
+fn host(command: &mut Command, inputs: &[i64]) -> Run {
+    let output = command
+        .args(inputs.iter().map(|i| i.to_string()))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let values = |line: &str| -> Vec<i64> {
+        line.split_whitespace()
+            .map(|value| value.parse().unwrap())
+            .collect()
+    };
+    let lines: Vec<&str> = stdout.lines().skip(1).collect();
+    (
+        lines[1].parse().unwrap(),
+        values(lines[0]),
+        values(lines[2]),
+    )
+}
+
+/// Compiles the generated C for a program with `cc` and runs it.
+fn run_c(memory: &[i64], inputs: &[i64], name: &str) -> Run {
+    let dir = std::env::temp_dir();
+    let source = dir.join(format!("compile-{}-{}.c", name, std::process::id()));
+    let binary = dir.join(format!("compile-{}-{}", name, std::process::id()));
+    let code = compile::compile(memory).to_c() + C_HOST;
+    std::fs::write(&source, code).unwrap();
+
+    let status = Command::new("cc")
+        .args(["-std=c99", "-O1", "-Wall", "-Werror", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let run = host(&mut Command::new(&binary), inputs);
+    std::fs::remove_file(source).unwrap();
+    std::fs::remove_file(binary).unwrap();
+    run
+}
+
+/// Assembles the generated WebAssembly text for a program with `wat2wasm` and runs it in Node.js.
+fn run_wasm(memory: &[i64], inputs: &[i64], name: &str) -> Run {
+    let dir = std::env::temp_dir();
+    let text = dir.join(format!("compile-{}-{}.wat", name, std::process::id()));
+    let module = dir.join(format!("compile-{}-{}.wasm", name, std::process::id()));
+    let script = dir.join(format!("compile-{}-{}.js", name, std::process::id()));
+    std::fs::write(&text, compile::compile(memory).to_wat()).unwrap();
+    std::fs::write(&script, WASM_HOST).unwrap();
+
+    let status = Command::new("wat2wasm")
+        .arg(&text)
+        .arg("-o")
+        .arg(&module)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let run = host(Command::new("node").arg(&script).arg(&module), inputs);
+    for path in [text, module, script] {
+        std::fs::remove_file(path).unwrap();
+    }
+    run
+}
+
+/// Runs a program under `Machine`, and checks that a compiled form halts with the same output
+/// and memory.
+fn check(run: Run, memory: &[i64], inputs: &[i64]) {
+    let mut machine = Machine::from_memory(memory);
+    let mut output = Vec::new();
+    assert!(machine.execute_for(
+        &mut inputs.iter().copied().collect::<VecDeque<i64>>(),
+        &mut output,
+        BUDGET
+    ));
+
+    let (code, compiled_output, compiled_memory) = run;
+    assert_eq!(code, 0);
+    assert_eq!(compiled_output, output);
+    for (addr, value) in compiled_memory.iter().enumerate() {
+        assert_eq!(machine.read(addr), *value, "memory differs at {}", addr);
+    }
+}
+
+/// The puzzle inputs the compiled forms are checked on, with the inputs they run with.
+fn cases() -> Vec<(&'static str, Vec<i64>, Vec<i64>)> {
+    let mut day2 = day(2);
+    day2[1] = 12;
+    day2[2] = 2;
+    vec![
+        ("day2", day2, vec![]),
+        ("day7", day(7), vec![3, 17]),
+        ("day9-1", day(9), vec![1]),
+        ("day9-2", day(9), vec![2]),
+    ]
+}
+
+#[test]
+fn compiled_c_matches_machine() {
+    if !available("cc", "--version") {
+        eprintln!("cc is not available, skipping");
+        return;
+    }
+
+    for (name, memory, inputs) in cases() {
+        check(run_c(&memory, &inputs, name), &memory, &inputs);
+    }
+}
+
+#[test]
+fn compiled_c_traps() {
+    if !available("cc", "--version") {
+        eprintln!("cc is not available, skipping");
+        return;
+    }
+
+    let (code, _, _) = run_c(&[1001, 0, 1, 0, 1105, 1, 0], &[], "trap");
+    assert_eq!(code, Trap::Modified(0).code());
+    let (code, _, _) = run_c(&[4, -1, 99], &[], "trap");
+    assert_eq!(code, Trap::BadAddress(-1).code());
+}
+
+#[test]
+fn compiled_wasm_matches_machine() {
+    if !available("wat2wasm", "--version") || !available("node", "--version") {
+        eprintln!("wat2wasm or node is not available, skipping");
+        return;
+    }
+
+    for (name, memory, inputs) in cases() {
+        check(run_wasm(&memory, &inputs, name), &memory, &inputs);
+    }
+}
@@ -0,0 +1,8 @@
+use machine::conformance::{self, MachineBackend};
+
+#[test]
+fn machine_conforms() {
+    let failures = conformance::run(&MachineBackend);
+    let report: Vec<String> = failures.iter().map(|f| f.to_string()).collect();
+    assert!(failures.is_empty(), "\n{}", report.join("\n"));
+}
@@ -0,0 +1,343 @@
+use machine::dap;
+use machine::json::{object, Value};
+use std::io::Cursor;
+use std::path::PathBuf;
+
+/// Writes a program to a temporary file and returns its path.
+fn program_file(name: &str, program: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("dap-{}-{}.txt", name, std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    path
+}
+
+/// Runs a session with the given requests, numbered from 1, and returns every message the
+/// adapter sent.
+fn session(requests: Vec<(&str, Value)>) -> Vec<Value> {
+    session_after(String::new(), requests)
+}
+
+/// Runs a session as `session` does, with the client first sending some raw input.
+fn session_after(mut input: String, requests: Vec<(&str, Value)>) -> Vec<Value> {
+    for (seq, (command, arguments)) in requests.into_iter().enumerate() {
+        let request = object(vec![
+            ("seq", (seq + 1).into()),
+            ("type", "request".into()),
+            ("command", command.into()),
+            ("arguments", arguments),
+        ])
+        .to_string();
+        input.push_str(&format!(
+            "Content-Length: {}\r\n\r\n{}",
+            request.len(),
+            request
+        ));
+    }
+
+    let mut output = Vec::new();
+    dap::serve(Cursor::new(input.into_bytes()), &mut output).unwrap();
+
+    let text = String::from_utf8(output).unwrap();
+    let mut messages = Vec::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find("\r\n\r\n") {
+        let length: usize = rest["Content-Length: ".len()..start].parse().unwrap();
+        let body = &rest[start + 4..start + 4 + length];
+        messages.push(Value::parse(body).unwrap());
+        rest = &rest[start + 4 + length..];
+    }
+
+    messages
+}
+
+/// Returns the body of the response to a request, checking it succeeded.
+fn response(messages: &[Value], seq: i64) -> &Value {
+    let response = messages
+        .iter()
+        .find(|m| m.get("request_seq").as_i64() == Some(seq))
+        .unwrap();
+    assert_eq!(response.get("success"), &Value::Bool(true), "{}", response);
+    response.get("body")
+}
+
+/// Returns the events, as their names and bodies, in the order they were sent.
+fn events(messages: &[Value]) -> Vec<(&str, &Value)> {
+    messages
+        .iter()
+        .filter(|m| m.get("type").as_str() == Some("event"))
+        .map(|m| (m.get("event").as_str().unwrap(), m.get("body")))
+        .collect()
+}
+
+/// Returns the reasons of the stopped events.
+fn stops(messages: &[Value]) -> Vec<&str> {
+    events(messages)
+        .into_iter()
+        .filter(|(name, _)| *name == "stopped")
+        .map(|(_, body)| body.get("reason").as_str().unwrap())
+        .collect()
+}
+
+fn args(members: Vec<(&str, Value)>) -> Value {
+    object(members)
+}
+
+#[test]
+fn debugs_with_breakpoints_and_console_input() {
+    // Outputs its input doubled until given 0.
+    let path = program_file("double", "3,20,1006,20,14,102,2,20,21,4,21,1105,1,0,99");
+    let source = || args(vec![("sourceReference", 1.into())]);
+    let line = |n: i64| args(vec![("line", n.into())]);
+
+    let messages = session(vec![
+        ("initialize", args(vec![("linesStartAt1", true.into())])),
+        (
+            "launch",
+            args(vec![
+                ("program", path.to_str().unwrap().into()),
+                ("inputs", vec![5.into()].into()),
+            ]),
+        ),
+        (
+            "setBreakpoints",
+            args(vec![
+                ("source", source()),
+                ("breakpoints", vec![line(4), line(40)].into()),
+            ]),
+        ),
+        ("configurationDone", Value::Null),
+        ("stackTrace", args(vec![("threadId", 1.into())])),
+        ("next", args(vec![("threadId", 1.into())])),
+        ("variables", args(vec![("variablesReference", 1.into())])),
+        ("continue", args(vec![("threadId", 1.into())])),
+        (
+            "evaluate",
+            args(vec![("expression", "0".into()), ("context", "repl".into())]),
+        ),
+        (
+            "setInstructionBreakpoints",
+            args(vec![(
+                "breakpoints",
+                vec![args(vec![("instructionReference", "14".into())])].into(),
+            )]),
+        ),
+        (
+            "setBreakpoints",
+            args(vec![("source", source()), ("breakpoints", vec![].into())]),
+        ),
+        ("continue", args(vec![("threadId", 1.into())])),
+        ("source", args(vec![("sourceReference", 1.into())])),
+        ("continue", args(vec![("threadId", 1.into())])),
+        ("disconnect", Value::Null),
+    ]);
+
+    let breakpoints = response(&messages, 3).get("breakpoints").as_array();
+    assert_eq!(breakpoints[0].get("verified"), &Value::Bool(true));
+    assert_eq!(
+        breakpoints[0].get("instructionReference").as_str(),
+        Some("9")
+    );
+    assert_eq!(breakpoints[1].get("verified"), &Value::Bool(false));
+
+    let frame = &response(&messages, 5).get("stackFrames").as_array()[0];
+    assert_eq!(frame.get("line").as_i64(), Some(4));
+    assert_eq!(frame.get("name").as_str(), Some("9: out [21]"));
+
+    let registers = response(&messages, 7).get("variables").as_array();
+    assert_eq!(registers[0].get("value").as_str(), Some("11"));
+    assert_eq!(registers[2].get("value").as_str(), Some("jnz #1, #0"));
+
+    let evaluated = response(&messages, 9).get("result").as_str();
+    assert_eq!(evaluated, Some("1 values queued"));
+
+    let content = response(&messages, 13).get("content").as_str().unwrap();
+    assert!(content.contains("     9  out [21]\n"), "{}", content);
+    assert!(content.ends_with("    14  hlt\n"), "{}", content);
+
+    assert_eq!(
+        stops(&messages),
+        vec!["breakpoint", "step", "pause", "breakpoint"]
+    );
+    let outputs: Vec<&str> = events(&messages)
+        .into_iter()
+        .filter(|(_, body)| body.get("category").as_str() == Some("stdout"))
+        .map(|(_, body)| body.get("output").as_str().unwrap())
+        .collect();
+    assert_eq!(outputs, vec!["10\n"]);
+
+    let names: Vec<&str> = events(&messages).into_iter().map(|(n, _)| n).collect();
+    assert_eq!(names.first(), Some(&"initialized"));
+    assert!(names.ends_with(&["exited", "terminated"]));
+}
+
+#[test]
+fn shows_memory_in_windows() {
+    let cells: Vec<String> = (0..20).map(|n| n.to_string()).collect();
+    let path = program_file("memory", &format!("99,{}", cells.join(",")));
+    let messages = session(vec![
+        ("initialize", Value::Null),
+        (
+            "launch",
+            args(vec![
+                ("program", path.to_str().unwrap().into()),
+                ("stopOnEntry", true.into()),
+            ]),
+        ),
+        ("configurationDone", Value::Null),
+        ("scopes", args(vec![("frameId", 1.into())])),
+        ("variables", args(vec![("variablesReference", 3.into())])),
+        ("variables", args(vec![("variablesReference", 1001.into())])),
+        (
+            "evaluate",
+            args(vec![
+                ("expression", "[3]".into()),
+                ("context", "hover".into()),
+            ]),
+        ),
+        (
+            "disassemble",
+            args(vec![
+                ("memoryReference", "0".into()),
+                ("instructionOffset", (-1).into()),
+                ("instructionCount", 3.into()),
+            ]),
+        ),
+        ("disconnect", Value::Null),
+    ]);
+
+    assert_eq!(stops(&messages), vec!["entry"]);
+    let scopes = response(&messages, 4).get("scopes").as_array();
+    assert_eq!(scopes.len(), 3);
+
+    let windows = response(&messages, 5).get("variables").as_array();
+    assert_eq!(windows.len(), 2);
+    assert_eq!(windows[1].get("name").as_str(), Some("16..31"));
+
+    let cells = response(&messages, 6).get("variables").as_array();
+    assert_eq!(cells[0].get("name").as_str(), Some("[16]"));
+    assert_eq!(cells[0].get("value").as_str(), Some("15"));
+    assert_eq!(cells[5].get("value").as_str(), Some("0"));
+
+    assert_eq!(response(&messages, 7).get("result").as_str(), Some("2"));
+
+    let instructions = response(&messages, 8).get("instructions").as_array();
+    assert_eq!(
+        instructions[0].get("presentationHint").as_str(),
+        Some("invalid")
+    );
+    assert_eq!(instructions[1].get("instruction").as_str(), Some("hlt"));
+    assert_eq!(instructions[2].get("instruction").as_str(), Some("data 0"));
+}
+
+#[test]
+fn pauses_and_reports_faults() {
+    let path = program_file("loop", "1105,1,0");
+    let messages = session(vec![
+        ("initialize", Value::Null),
+        (
+            "launch",
+            args(vec![("program", path.to_str().unwrap().into())]),
+        ),
+        ("configurationDone", Value::Null),
+        ("pause", args(vec![("threadId", 1.into())])),
+        ("disconnect", Value::Null),
+    ]);
+    assert_eq!(stops(&messages), vec!["pause"]);
+    response(&messages, 4);
+
+    let path = program_file("fault", "42");
+    let messages = session(vec![
+        ("initialize", Value::Null),
+        (
+            "launch",
+            args(vec![("program", path.to_str().unwrap().into())]),
+        ),
+        ("configurationDone", Value::Null),
+        ("disconnect", Value::Null),
+    ]);
+    let (_, stopped) = events(&messages)
+        .into_iter()
+        .find(|(name, _)| *name == "stopped")
+        .unwrap();
+    assert_eq!(stopped.get("reason").as_str(), Some("exception"));
+    assert_eq!(stopped.get("text").as_str(), Some("Unknown op code: 42"));
+}
+
+#[test]
+fn round_trips_json() {
+    let text = r#"{"a":[1,-2,3.5,true,null],"b":"q\"\né😀","c":{}}"#;
+    let value = Value::parse(text).unwrap();
+    assert_eq!(value.get("a").as_array()[1].as_i64(), Some(-2));
+    assert_eq!(value.get("b").as_str(), Some("q\"\né😀"));
+    assert_eq!(Value::parse(&value.to_string()).unwrap(), value);
+    assert_eq!(
+        Value::parse("9223372036854775807").unwrap(),
+        Value::Int(i64::MAX)
+    );
+    assert!(Value::parse("[1,").is_err());
+    assert!(Value::parse("{} x").is_err());
+}
+
+#[test]
+fn does_not_step_out() {
+    let path = program_file("step-out", "1101,1,1,0,99");
+    let messages = session(vec![
+        ("initialize", Value::Null),
+        (
+            "launch",
+            args(vec![
+                ("program", path.to_str().unwrap().into()),
+                ("stopOnEntry", true.into()),
+            ]),
+        ),
+        ("configurationDone", Value::Null),
+        ("stepOut", args(vec![("threadId", 1.into())])),
+        ("disconnect", Value::Null),
+    ]);
+
+    let response = messages
+        .iter()
+        .find(|m| m.get("request_seq").as_i64() == Some(4))
+        .unwrap();
+    assert_eq!(response.get("success"), &Value::Bool(false));
+    assert_eq!(
+        response.get("message").as_str(),
+        Some("Unsupported request stepOut")
+    );
+    assert_eq!(stops(&messages), vec!["entry"]);
+}
+
+#[test]
+fn rejects_oversized_messages_and_references() {
+    let path = program_file("hostile", "99");
+    let oversized = format!("Content-Length: {}\r\n\r\n{}", 1 << 21, "x".repeat(1 << 21));
+    let messages = session_after(
+        oversized,
+        vec![
+            ("initialize", Value::Null),
+            (
+                "launch",
+                args(vec![
+                    ("program", path.to_str().unwrap().into()),
+                    ("stopOnEntry", true.into()),
+                ]),
+            ),
+            ("configurationDone", Value::Null),
+            (
+                "variables",
+                args(vec![("variablesReference", i64::MAX.into())]),
+            ),
+            ("disconnect", Value::Null),
+        ],
+    );
+
+    assert_eq!(stops(&messages), vec!["entry"]);
+    let response = messages
+        .iter()
+        .find(|m| m.get("request_seq").as_i64() == Some(4))
+        .unwrap();
+    assert_eq!(response.get("success"), &Value::Bool(false));
+    assert_eq!(
+        response.get("message").as_str(),
+        Some("Unknown variables reference")
+    );
+}
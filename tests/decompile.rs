@@ -0,0 +1,108 @@
+use machine::decompile::decompile;
+use machine::loader;
+
+#[test]
+fn recovers_while_loops() {
+    // Count down from the input, printing each value.
+    let program = [
+        3, 100, 1006, 100, 14, 4, 100, 1001, 100, -1, 100, 1105, 1, 2, 99,
+    ];
+    assert_eq!(
+        decompile(&program),
+        "\
+fn main() {
+    m100 = input()
+    while m100 != 0 {
+        output(m100)
+        m100 = m100 - 1
+    }
+    halt
+}
+"
+    );
+}
+
+#[test]
+fn recovers_if_else() {
+    let program = [
+        3, 100, 1007, 100, 10, 101, 1006, 101, 14, 104, 1, 1105, 1, 16, 104, 2, 99,
+    ];
+    assert_eq!(
+        decompile(&program),
+        "\
+fn main() {
+    m100 = input()
+    m101 = m100 < 10
+    if m101 != 0 {
+        output(1)
+    } else {
+        output(2)
+    }
+    halt
+}
+"
+    );
+}
+
+#[test]
+fn recovers_do_while_loops() {
+    // Output 3, 2, 1 by testing at the end of the loop.
+    let program = [
+        1101, 3, 0, 100, 4, 100, 1001, 100, -1, 100, 1005, 100, 4, 99,
+    ];
+    assert_eq!(
+        decompile(&program),
+        "\
+fn main() {
+    m100 = 3
+    do {
+        output(m100)
+        m100 = m100 - 1
+    } while m100 != 0
+    halt
+}
+"
+    );
+}
+
+#[test]
+fn recovers_day9_recursive_function() {
+    let program = loader::load_file("src/day9/input.txt").unwrap().remove(0);
+    let pseudocode = decompile(&program);
+
+    assert!(pseudocode.contains("    call f922(frame rb[0])\n"));
+
+    // The relative base starts at 0, so cells in `main` are named by address either way.
+    assert!(pseudocode.contains("        rb += 988\n        rb += m1000\n        rb += m1000\n"));
+    assert!(!pseudocode.contains("v1000"));
+
+    // Tests whose then branch would be empty are negated instead.
+    assert!(pseudocode.contains(
+        "\
+    m63 = rb[-9] == 22
+    if m63 != 0 {
+        output(m231)
+        m64 = m64 + 1
+    }
+"
+    ));
+    assert!(!pseudocode.contains("{\n    }"));
+    assert!(pseudocode.ends_with(
+        "\
+fn f922() {
+    rb += 3
+    m63 = v1 < 3
+    if m63 == 0 {
+        v4 = v1 - 1
+        call f922(frame v3)
+        v2 = v4
+        v4 = v1 - 3
+        call f922(frame v3)
+        v1 = v4 + v2
+    }
+    rb -= 3
+    return
+}
+"
+    ));
+}
@@ -0,0 +1,72 @@
+use machine::explore::{self, Cell, Move};
+use machine::Machine;
+
+/// A droid in the maze below, which it keeps in memory after its code, starting at `D`.
+///
+/// ```text
+/// #########
+/// #...#...#
+/// #.#.#.#.#
+/// #.#...#.#
+/// #.#####.#
+/// #D..O...#
+/// #########
+/// ```
+const DROID: &str = "3,37,1001,37,29,7,1,0,34,35,1001,35,38,15,1001,0,0,36,4,36,1006,36,0,1001,35,0,34,1105,1,0,-9,9,-1,1,46,0,0,0,0,0,0,0,0,0,0,0,0,0,1,1,1,0,1,1,1,0,0,1,0,1,0,1,0,1,0,0,1,0,1,1,1,0,1,0,0,1,0,0,0,0,0,1,0,0,1,1,1,2,1,1,1,0,0,0,0,0,0,0,0,0,0";
+
+#[test]
+fn maps_the_maze() {
+    let map = explore::explore(&Machine::new(DROID).unwrap()).unwrap();
+
+    assert_eq!(map.target(), Some((3, 0)));
+    assert_eq!(
+        map.shortest_path(),
+        Some(vec![Move::East, Move::East, Move::East])
+    );
+    assert_eq!(map.open(), 24);
+    assert_eq!(map.cell((0, -1)), Some(Cell::Open));
+    assert_eq!(map.cell((-1, 0)), Some(Cell::Wall));
+    assert_eq!(map.cell((-1, -5)), None);
+    assert_eq!(
+        map.render(),
+        [
+            " ### ### ",
+            "#...#...#",
+            "#.#.#.#.#",
+            "#.#...#.#",
+            "#.#####.#",
+            "#D..O...#",
+            " ####### ",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn finds_paths_and_fill_times() {
+    let map = explore::explore(&Machine::new(DROID).unwrap()).unwrap();
+
+    // The top right corner of the maze.
+    let path = map.path_to((6, -4)).unwrap();
+    assert_eq!(path.len(), 10);
+    assert_eq!(path[..3], [Move::East, Move::East, Move::East]);
+    assert_eq!(map.path_to((0, 0)), Some(Vec::new()));
+    assert_eq!(map.path_to((-1, 0)), None);
+
+    assert_eq!(map.flood_fill(map.target().unwrap()), Some(12));
+    assert_eq!(map.fill_times((0, 0))[&(6, -4)], 10);
+    assert_eq!(map.flood_fill((-1, 0)), None);
+}
+
+#[test]
+fn reports_misbehaving_droids() {
+    let error = explore::explore(&Machine::new("99").unwrap()).unwrap_err();
+    assert_eq!(error, "Droid halted at 0");
+
+    let error = explore::explore(&Machine::new("3,100,104,5,1105,1,0").unwrap()).unwrap_err();
+    assert_eq!(error, "Unknown status: 5");
+
+    let error = explore::explore(&Machine::new("3,100,104,1,104,1,1105,1,0").unwrap());
+    assert_eq!(error.unwrap_err(), "Expected one status, got [1, 1]");
+}
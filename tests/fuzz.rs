@@ -0,0 +1,43 @@
+use machine::fuzz::{self, Case, BUDGET, FIXTURES};
+use std::fs;
+
+#[test]
+fn fixtures_match_reference() {
+    for entry in fs::read_dir(FIXTURES).unwrap() {
+        let path = entry.unwrap().path();
+        let case: Case = fs::read_to_string(&path).unwrap().parse().unwrap();
+
+        if let Some(mismatch) = fuzz::check(&case, BUDGET) {
+            panic!("{}: {}", path.display(), mismatch);
+        }
+    }
+}
+
+#[test]
+fn generated_programs_match_reference() {
+    for seed in 0..500 {
+        let case = fuzz::generate(seed);
+        if let Some(mismatch) = fuzz::check(&case, BUDGET) {
+            panic!("seed {}: {}\n{}", seed, mismatch, case.to_fixture(""));
+        }
+    }
+}
+
+#[test]
+fn fixtures_round_trip() {
+    let case = fuzz::generate(7);
+    let parsed: Case = case.to_fixture("comment").parse().unwrap();
+    assert_eq!(case, parsed);
+}
+
+#[test]
+fn saves_fixtures() {
+    let dir = std::env::temp_dir().join(format!("fuzz-fixtures-{}", std::process::id()));
+    let case = fuzz::generate(3);
+    let path = case.save(&dir, "seed-3", "comment").unwrap();
+
+    assert_eq!(path, dir.join("seed-3.txt"));
+    let saved: Case = fs::read_to_string(&path).unwrap().parse().unwrap();
+    assert_eq!(saved, case);
+    fs::remove_dir_all(dir).unwrap();
+}
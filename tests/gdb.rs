@@ -0,0 +1,193 @@
+use machine::gdb::{Stub, TARGET_XML};
+use machine::Machine;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+
+/// The debugger end of a connection.
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    /// Starts a stub for a program on its own thread and connects to it. The thread returns the
+    /// program's output once the session ends.
+    fn start(program: &str, inputs: Vec<i64>) -> (Client, JoinHandle<Vec<i64>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let machine = Machine::new(program).unwrap();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stub = Stub::new(machine, inputs);
+            stub.serve(stream).unwrap();
+            stub.outputs
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        (Client { stream }, handle)
+    }
+
+    fn byte(&mut self) -> u8 {
+        let mut byte = [0];
+        self.stream.read_exact(&mut byte).unwrap();
+        byte[0]
+    }
+
+    /// Sends a packet and returns the reply, collecting the text of any console output first.
+    fn request(&mut self, data: &str) -> (String, String) {
+        self.send(data);
+        self.receive()
+    }
+
+    fn send(&mut self, data: &str) {
+        let sum = data.bytes().fold(0u8, |s, b| s.wrapping_add(b));
+        write!(self.stream, "${}#{:02x}", data, sum).unwrap();
+        assert_eq!(self.byte(), b'+');
+    }
+
+    /// Returns the next reply, collecting the text of any console output before it.
+    fn receive(&mut self) -> (String, String) {
+        let mut console = String::new();
+        loop {
+            let reply = self.packet();
+            match reply.strip_prefix('O') {
+                Some(text) if !text.is_empty() && text != "K" => {
+                    let bytes: Vec<u8> = (0..text.len())
+                        .step_by(2)
+                        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+                        .collect();
+                    console.push_str(&String::from_utf8(bytes).unwrap());
+                }
+                _ => return (reply, console),
+            }
+        }
+    }
+
+    fn reply(&mut self, data: &str) -> String {
+        self.request(data).0
+    }
+
+    /// Reads a packet and acknowledges it.
+    fn packet(&mut self) -> String {
+        while self.byte() != b'$' {}
+        let mut data = Vec::new();
+        loop {
+            match self.byte() {
+                b'#' => break,
+                b => data.push(b),
+            }
+        }
+
+        let checksum = [self.byte(), self.byte()];
+        let expected = data.iter().fold(0u8, |s, &b| s.wrapping_add(b));
+        assert_eq!(
+            u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap(),
+            expected
+        );
+        self.stream.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+}
+
+/// Formats a value as the target's little endian bytes.
+fn word(value: u64) -> String {
+    value
+        .to_le_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[test]
+fn reports_target() {
+    let (mut client, handle) = Client::start("99", vec![]);
+    assert!(client
+        .reply("qSupported:multiprocess+")
+        .contains("qXfer:features:read+"));
+    assert_eq!(
+        client.reply("qXfer:features:read:target.xml:0,fff"),
+        format!("l{}", TARGET_XML)
+    );
+    assert_eq!(
+        client.reply("qXfer:features:read:target.xml:0,10"),
+        format!("m{}", &TARGET_XML[..16])
+    );
+    assert_eq!(client.reply("?"), "S05");
+    assert_eq!(client.reply("qUnknown"), "");
+    assert_eq!(client.reply("D"), "OK");
+    handle.join().unwrap();
+}
+
+#[test]
+fn reads_and_writes_registers_and_memory() {
+    let (mut client, handle) = Client::start("109,3,4,0,99", vec![]);
+    assert_eq!(client.reply("g"), format!("{}{}", word(0), word(0)));
+    assert_eq!(client.reply("m0,10"), format!("{}{}", word(109), word(3)));
+    assert_eq!(client.reply("s"), "S05");
+    assert_eq!(client.reply("p0"), word(16));
+    assert_eq!(client.reply("p1"), word(24));
+
+    // Make the output read cell 4, which holds 99, relative to a base of 0.
+    assert_eq!(client.reply(&format!("P1={}", word(0))), "OK");
+    assert_eq!(client.reply(&format!("M18,8:{}", word(4))), "OK");
+    assert_eq!(client.reply("m18,1"), "04");
+    assert_eq!(client.reply(&format!("P0={}", word(3))), "E01");
+
+    // A character spanning the two halves of a `G` packet is rejected, not split.
+    let split = format!("G{}\u{e9}{}", "0".repeat(15), "0".repeat(15));
+    assert_eq!(client.reply(&split), "E01");
+
+    assert_eq!(client.reply("M10,1:cc"), "OK");
+    assert_eq!(client.reply("m10,8"), word(0xcc));
+    assert_eq!(client.reply("M10,1:04"), "OK");
+
+    let (reply, console) = client.request("c");
+    assert_eq!((reply.as_str(), console.as_str()), ("W00", "99\n"));
+    assert_eq!(client.reply("D"), "OK");
+    assert_eq!(handle.join().unwrap(), vec![99]);
+}
+
+#[test]
+fn stops_at_breakpoints_and_for_input() {
+    // Outputs its input doubled until given 0.
+    let program = "3,20,1006,20,14,102,2,20,21,4,21,1105,1,0,99";
+    let (mut client, handle) = Client::start(program, vec![5]);
+    assert_eq!(client.reply(&format!("Z0,{:x},1", 9 * 8)), "OK");
+    assert_eq!(client.reply("Z0,3,1"), "E01");
+
+    let (reply, console) = client.request("c");
+    assert_eq!((reply.as_str(), console.as_str()), ("S05", ""));
+    assert_eq!(client.reply("p0"), word(9 * 8));
+
+    let (reply, console) = client.request("vCont;c");
+    assert_eq!((reply.as_str(), console.as_str()), ("S15", "10\n"));
+
+    let command: String = "input 0".bytes().map(|b| format!("{:02x}", b)).collect();
+    let (reply, console) = client.request(&format!("qRcmd,{}", command));
+    assert_eq!(
+        (reply.as_str(), console.as_str()),
+        ("OK", "1 values queued\n")
+    );
+
+    assert_eq!(client.reply(&format!("z0,{:x},1", 9 * 8)), "OK");
+    assert_eq!(client.reply("c"), "W00");
+    assert_eq!(client.reply("D"), "OK");
+    assert_eq!(handle.join().unwrap(), vec![10]);
+}
+
+#[test]
+fn reports_faults_and_interrupts() {
+    let (mut client, handle) = Client::start("1105,1,0", vec![]);
+    client.send("c");
+    client.stream.write_all(&[0x03]).unwrap();
+    assert_eq!(client.receive().0, "S02");
+
+    assert_eq!(client.reply(&format!("M0,8:{}", word(42))), "OK");
+    assert_eq!(client.reply("s"), "S04");
+    assert_eq!(client.reply("?"), "S04");
+    assert_eq!(client.reply("p0"), word(0));
+
+    client.stream.write_all(b"$k#6b").unwrap();
+    assert_eq!(client.byte(), b'+');
+    handle.join().unwrap();
+}
@@ -0,0 +1,91 @@
+use std::process::{Command, Stdio};
+
+/// Runs `intcode` on a program written to a temporary file, followed by the given arguments,
+/// and returns what it printed to stdout and stderr, along with its exit code.
+fn intcode(name: &str, program: &str, args: &[&str]) -> (String, String, Option<i32>) {
+    let path = std::env::temp_dir().join(format!("intcode-{}-{}.txt", name, std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .arg(&path)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+        output.status.code(),
+    )
+}
+
+#[test]
+fn runs_programs() {
+    let (stdout, stderr, code) = intcode("run", "3,9,1001,9,1,9,4,9,99,0", &["-i", "41"]);
+    assert_eq!(
+        (stdout.as_str(), stderr.as_str(), code),
+        ("42\n", "", Some(0))
+    );
+}
+
+#[test]
+fn reports_faults() {
+    let (stdout, stderr, code) = intcode("fault", "1,0,0,0,98", &[]);
+    assert_eq!(stdout, "");
+    assert!(stderr.ends_with("intcode: The program faulted: Unknown op code: 98\n"));
+    assert_eq!(code, Some(1));
+}
+
+#[test]
+fn dumps_cells() {
+    let (stdout, _, code) = intcode("dump", "1,0,0,0,99", &["-d", "0,2..4", "-d", "1..1"]);
+    assert_eq!(stdout, "0: 2\n2: 0\n3: 0\n");
+    assert_eq!(code, Some(0));
+
+    let (_, stderr, code) = intcode("dump", "1,0,0,0,99", &["-d", "5..2"]);
+    assert_eq!(stderr, "intcode: Reversed range 5..2\n");
+    assert_eq!(code, Some(1));
+
+    let (_, stderr, code) = intcode("dump", "1,0,0,0,99", &["-d", "x"]);
+    assert_eq!(stderr, "intcode: invalid digit found in string\n");
+    assert_eq!(code, Some(1));
+}
+
+#[test]
+fn patches_memory() {
+    let (stdout, _, code) = intcode("set", "1,0,0,0,99", &["-s", "2=3", "-d", "0"]);
+    assert_eq!(stdout, "0: 1\n");
+    assert_eq!(code, Some(0));
+
+    let (_, stderr, code) = intcode("set", "1,0,0,0,99", &["-s", "2"]);
+    assert_eq!(stderr, "intcode: Expected <addr>=<value>, found \"2\"\n");
+    assert_eq!(code, Some(1));
+
+    let (_, stderr, _) = intcode("set", "1,0,0,0,99", &["-s", "x=1"]);
+    assert_eq!(stderr, "intcode: invalid digit found in string\n");
+}
+
+#[test]
+fn rejects_bad_arguments() {
+    let (_, stderr, code) = intcode("args", "99", &["-z"]);
+    assert!(stderr.starts_with("intcode: Unknown option -z\n\nUsage: intcode"));
+    assert_eq!(code, Some(1));
+
+    let (_, stderr, _) = intcode("args", "99", &["-p"]);
+    assert_eq!(stderr, "intcode: Missing value for -p\n");
+
+    let (_, stderr, _) = intcode("args", "99", &["-e", "js"]);
+    assert_eq!(stderr, "intcode: Unknown target js, expected c or wat\n");
+
+    let (stdout, _, code) = intcode("args", "99", &["-h"]);
+    assert!(stdout.starts_with("Usage: intcode"));
+    assert_eq!(code, Some(0));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("intcode: Missing program\n"));
+    assert_eq!(output.status.code(), Some(1));
+}
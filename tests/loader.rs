@@ -0,0 +1,50 @@
+use machine::loader::{self, ParseError};
+
+#[test]
+fn tolerates_whitespace_and_comments() {
+    let programs = loader::parse("# header\n 1, 0 ,0,\n\t0,99 # halt\n\n").unwrap();
+    assert_eq!(programs, vec![vec![1, 0, 0, 0, 99]]);
+}
+
+#[test]
+fn line_breaks_without_commas_separate_programs() {
+    let programs = loader::parse("1,0,0,0,99\n104,7,99\n").unwrap();
+    assert_eq!(programs, vec![vec![1, 0, 0, 0, 99], vec![104, 7, 99]]);
+}
+
+#[test]
+fn reports_position_of_bad_token() {
+    let error = loader::parse("1,0,\n  0,x,99").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 5));
+}
+
+#[test]
+fn reader_matches_string() {
+    let source = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99\n";
+    let loaded = loader::load(source.as_bytes()).unwrap();
+    assert_eq!(loaded, loader::parse(source).unwrap());
+}
+
+#[test]
+fn rejects_missing_values() {
+    let errors: Vec<ParseError> = ["1,,2", ",1", "1,2,", "1 2"]
+        .iter()
+        .map(|source| loader::parse(source).unwrap_err())
+        .collect();
+
+    let columns: Vec<usize> = errors.iter().map(|e| e.column).collect();
+    assert_eq!(columns, vec![3, 1, 4, 3]);
+}
+
+#[test]
+fn points_at_extra_programs() {
+    let error = loader::parse_program("# first\n1,0,0,0,99\n\n  104,7,99\n").unwrap_err();
+    assert_eq!((error.line, error.column), (4, 3));
+    assert_eq!(error.message, "Expected a single program, found 2");
+
+    let error = loader::parse_program("# nothing\n").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 1));
+    assert_eq!(error.message, "Expected a program");
+
+    assert_eq!(loader::parse_program("99").unwrap(), vec![99]);
+}
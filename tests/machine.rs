@@ -0,0 +1,415 @@
+use machine::disassemble;
+use machine::{panic_message, Control, Difference, Input, Machine, Observer, PollInput, Status};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::mpsc;
+
+#[test]
+fn registers_can_be_read_and_moved() {
+    // Output the value at the relative base, then halt.
+    let mut machine = Machine::new("204,0,99,7,8").unwrap();
+    machine.set_base(4);
+    let mut output = Vec::new();
+    machine.execute(&mut 0, &mut output);
+    assert_eq!(output, vec![8]);
+    assert_eq!(machine.ip(), 2);
+
+    machine.set_ip(0);
+    machine.set_base(3);
+    machine.execute(&mut 0, &mut output);
+    assert_eq!(output, vec![8, 7]);
+    assert_eq!(machine.base(), 3);
+}
+
+#[test]
+fn memory_can_be_inspected() {
+    let mut machine = Machine::new("1,0,0,0,99").unwrap();
+    machine.set_force(8, 5);
+
+    assert_eq!(machine.len(), 9);
+    assert_eq!(machine.read_range(3..10), vec![0, 99, 0, 0, 0, 5, 0]);
+    assert_eq!(
+        machine.cells().collect::<Vec<_>>(),
+        vec![(0, 1), (1, 0), (2, 0), (3, 0), (4, 99), (8, 5)]
+    );
+}
+
+#[test]
+fn diff_reports_changed_cells() {
+    let original = Machine::new("1,0,0,0,99").unwrap();
+    let mut machine = original.clone();
+    machine.execute(&mut 0, &mut 0);
+    machine.set_force(6, 0);
+    machine.set_force(7, 3);
+
+    assert_eq!(
+        original.diff(&machine),
+        vec![
+            Difference {
+                addr: 0,
+                left: 1,
+                right: 2
+            },
+            Difference {
+                addr: 7,
+                left: 0,
+                right: 3
+            }
+        ]
+    );
+    assert!(machine.diff(&machine.clone()).is_empty());
+}
+
+#[test]
+fn reset_restores_the_program() {
+    let mut machine = Machine::new("1,0,0,0,99").unwrap();
+    machine.execute(&mut 0, &mut 0);
+    machine.set_force(20, 1);
+    machine.set_base(4);
+
+    machine.reset();
+    assert_eq!(machine.cells().collect::<Vec<_>>().len(), 5);
+    assert_eq!(machine.read(0), 1);
+    assert_eq!((machine.ip(), machine.base()), (0, 0));
+    assert_eq!(machine.program(), &[1, 0, 0, 0, 99]);
+}
+
+#[test]
+fn forks_share_memory_until_written() {
+    use machine::memory::PAGE;
+
+    // Three pages of program, the last holding a cell that is changed.
+    let mut program = vec![0; 2 * PAGE + 10];
+    program[..4].copy_from_slice(&[1101, 2, 3, 2 * PAGE as i64 + 5]);
+    program[4] = 99;
+    let original = Machine::from_memory(&program);
+
+    let mut fork = original.clone();
+    assert_eq!(fork.shared_pages(&original), 3);
+
+    fork.execute(&mut 0, &mut 0);
+    assert_eq!(fork.shared_pages(&original), 2);
+    assert_eq!(original.read(2 * PAGE + 5), 0);
+    assert_eq!(
+        original.diff(&fork),
+        vec![Difference {
+            addr: 2 * PAGE + 5,
+            left: 0,
+            right: 5
+        }]
+    );
+
+    // Writing far beyond the program adds a page of its own.
+    fork.set_force(10 * PAGE, 1);
+    assert_eq!(fork.shared_pages(&original), 2);
+    assert_eq!(fork.len(), 10 * PAGE + 1);
+
+    fork.reset();
+    assert_eq!(fork.shared_pages(&original), 3);
+    assert!(fork.diff(&original).is_empty());
+}
+
+/// Records every event as text, pausing before each read or after each output if asked to.
+#[derive(Default)]
+struct Log {
+    events: Vec<String>,
+    pause_on_request: bool,
+    pause_on_output: bool,
+}
+
+impl Observer for Log {
+    fn on_input_request(&mut self, ip: usize) -> Control {
+        self.events.push(format!("{}: read", ip));
+        if self.pause_on_request {
+            Control::Pause
+        } else {
+            Control::Continue
+        }
+    }
+
+    fn on_input(&mut self, ip: usize, value: i64) -> Control {
+        self.events.push(format!("{}: in {}", ip, value));
+        Control::Continue
+    }
+
+    fn on_output(&mut self, ip: usize, value: i64) -> Control {
+        self.events.push(format!("{}: out {}", ip, value));
+        if self.pause_on_output {
+            Control::Pause
+        } else {
+            Control::Continue
+        }
+    }
+
+    fn on_base(&mut self, ip: usize, base: i64) -> Control {
+        self.events.push(format!("{}: base {}", ip, base));
+        Control::Continue
+    }
+
+    fn on_jump(&mut self, ip: usize, target: usize) -> Control {
+        self.events.push(format!("{}: jump {}", ip, target));
+        Control::Continue
+    }
+
+    fn on_halt(&mut self, ip: usize) {
+        self.events.push(format!("{}: halt", ip));
+    }
+}
+
+#[test]
+fn observers_see_each_event() {
+    // Reads a count, then outputs and decrements it until it reaches 0, moving the base each time.
+    let program = "3,100,4,100,109,1,1001,100,-1,100,1005,100,2,99";
+    let mut machine = Machine::new(program).unwrap();
+    let mut log = Log::default();
+    let status = machine.execute_observed(&mut VecDeque::from(vec![2]), &mut Vec::new(), &mut log);
+
+    assert_eq!(status, Status::Halted);
+    assert_eq!(
+        log.events,
+        vec![
+            "0: read",
+            "0: in 2",
+            "2: out 2",
+            "4: base 1",
+            "10: jump 2",
+            "2: out 1",
+            "4: base 2",
+            "13: halt"
+        ]
+    );
+}
+
+#[test]
+fn observers_can_pause() {
+    let mut machine = Machine::new("104,1,104,2,99").unwrap();
+    let mut log = Log {
+        pause_on_output: true,
+        ..Log::default()
+    };
+    let mut output = Vec::new();
+
+    assert_eq!(
+        machine.execute_observed(&mut 0, &mut output, &mut log),
+        Status::Paused
+    );
+    assert_eq!((output.clone(), machine.ip()), (vec![1], 2));
+    assert_eq!(
+        machine.execute_observed(&mut 0, &mut output, &mut log),
+        Status::Paused
+    );
+    assert_eq!(
+        machine.execute_observed(&mut 0, &mut output, &mut log),
+        Status::Halted
+    );
+    assert_eq!(output, vec![1, 2]);
+
+    // Plain stepping observes nothing.
+    machine.reset();
+    assert!(!machine.step(&mut 0, &mut output));
+    assert_eq!(machine.step_observed(&mut 0, &mut output, &mut ()), None);
+    assert_eq!(log.events.len(), 3);
+}
+
+#[test]
+fn observers_hear_of_input_requests_before_the_read() {
+    // An input source that logs each read to the observer's log as it happens.
+    struct Source<'a>(&'a RefCell<Log>);
+    impl Input for Source<'_> {
+        fn get(&mut self) -> i64 {
+            self.0.borrow_mut().events.push(String::from("get"));
+            7
+        }
+    }
+
+    struct Shared<'a>(&'a RefCell<Log>);
+    impl Observer for Shared<'_> {
+        fn on_input_request(&mut self, ip: usize) -> Control {
+            self.0.borrow_mut().on_input_request(ip)
+        }
+
+        fn on_input(&mut self, ip: usize, value: i64) -> Control {
+            self.0.borrow_mut().on_input(ip, value)
+        }
+    }
+
+    let log = RefCell::new(Log::default());
+    let mut machine = Machine::new("3,0,99").unwrap();
+    machine.execute_observed(&mut Source(&log), &mut Vec::new(), &mut Shared(&log));
+    assert_eq!(log.into_inner().events, vec!["0: read", "get", "0: in 7"]);
+}
+
+#[test]
+fn observers_can_pause_before_reading() {
+    let mut machine = Machine::new("3,0,4,0,99").unwrap();
+    let mut log = Log {
+        pause_on_request: true,
+        ..Log::default()
+    };
+    let mut input = VecDeque::new();
+    let mut output = Vec::new();
+
+    // Passed through a reference, which forwards every hook. Reading the empty input would
+    // panic, so the machine must pause first.
+    let mut observer = &mut log;
+    assert_eq!(
+        machine.execute_observed(&mut input, &mut output, &mut observer),
+        Status::Paused
+    );
+    assert_eq!(machine.ip(), 0);
+    assert_eq!(log.events, vec!["0: read"]);
+
+    log.pause_on_request = false;
+    input.push_back(5);
+    let mut observer = &mut log;
+    assert_eq!(
+        machine.execute_observed(&mut input, &mut output, &mut observer),
+        Status::Halted
+    );
+    assert_eq!(output, vec![5]);
+    assert_eq!(
+        log.events,
+        vec!["0: read", "0: read", "0: in 5", "2: out 5", "4: halt"]
+    );
+}
+
+#[test]
+fn observers_see_every_step() {
+    /// Counts instructions, pausing after a given number.
+    struct Steps {
+        ips: Vec<usize>,
+        pause_after: usize,
+    }
+
+    impl Observer for Steps {
+        fn on_step(&mut self, ip: usize) -> Control {
+            self.ips.push(ip);
+            if self.ips.len() == self.pause_after {
+                Control::Pause
+            } else {
+                Control::Continue
+            }
+        }
+    }
+
+    let mut machine = Machine::new("1101,1,1,0,104,7,99").unwrap();
+    let mut steps = Steps {
+        ips: Vec::new(),
+        pause_after: 1,
+    };
+    let mut output = Vec::new();
+    assert_eq!(
+        machine.execute_observed(&mut 0, &mut output, &mut steps),
+        Status::Paused
+    );
+    assert_eq!(machine.ip(), 4);
+    assert_eq!(
+        machine.execute_observed(&mut 0, &mut output, &mut steps),
+        Status::Halted
+    );
+
+    // The halt is reported by on_halt alone.
+    assert_eq!(steps.ips, vec![0, 4]);
+}
+
+#[test]
+fn polled_input_suspends_until_data_arrives() {
+    // Outputs its input doubled, forever.
+    let mut machine = Machine::new("3,11,102,2,11,11,4,11,1105,1,0").unwrap();
+    let mut input: VecDeque<i64> = vec![1, 2].into();
+    let mut output = Vec::new();
+
+    let status = machine.run_polled(&mut input, &mut output);
+    assert_eq!(status, Status::AwaitingInput);
+    assert_eq!((output.clone(), machine.ip()), (vec![2, 4], 0));
+
+    let (sender, mut receiver) = mpsc::channel();
+    assert_eq!(
+        machine.run_polled(&mut receiver, &mut output),
+        Status::AwaitingInput
+    );
+    sender.send(5).unwrap();
+    machine.run_polled(&mut receiver, &mut output);
+    assert_eq!(output, vec![2, 4, 10]);
+    assert!(machine.is_awaiting_input());
+
+    let mut machine = Machine::new("4,0,99").unwrap();
+    assert_eq!(
+        machine.run_polled(&mut receiver, &mut output),
+        Status::Halted
+    );
+}
+
+#[test]
+fn polled_input_can_default() {
+    let machine = Machine::new("3,20,4,20,3,20,4,20,99").unwrap();
+
+    let mut output = Vec::new();
+    let mut input = VecDeque::from(vec![7]).with_default(-1);
+    machine.clone().execute(&mut input, &mut output);
+    assert_eq!(output, vec![7, -1]);
+
+    let (sender, receiver) = mpsc::channel();
+    drop(sender);
+    output.clear();
+    machine
+        .clone()
+        .execute(&mut receiver.with_default(-1), &mut output);
+    assert_eq!(output, vec![-1, -1]);
+}
+
+#[test]
+fn describes_faults() {
+    let fault = std::panic::catch_unwind(|| {
+        Machine::new("1,0,0,0,98").unwrap().execute(&mut 0, &mut 0);
+    })
+    .unwrap_err();
+    assert_eq!(panic_message(&*fault), "Unknown op code: 98");
+
+    let fault = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+    assert_eq!(panic_message(&*fault), "static");
+    assert_eq!(panic_message(&42), "unknown fault");
+}
+
+#[test]
+fn knows_instruction_widths() {
+    let widths: Vec<Option<usize>> = [1101, 2, 3, 204, 1005, 6, 7, 21108, 9, 99, 0, 10]
+        .iter()
+        .map(|word| disassemble::width(*word))
+        .collect();
+    assert_eq!(
+        widths,
+        vec![
+            Some(4),
+            Some(4),
+            Some(2),
+            Some(2),
+            Some(3),
+            Some(3),
+            Some(4),
+            Some(4),
+            Some(2),
+            Some(1),
+            None,
+            None
+        ]
+    );
+}
+
+#[test]
+fn knows_write_addresses() {
+    let address = |program: &str, ip: usize, base: i64| {
+        let mut machine = Machine::new(program).unwrap();
+        machine.set_ip(ip);
+        machine.set_base(base);
+        machine.write_address()
+    };
+
+    assert_eq!(address("1,0,0,7,99", 0, 0), Some(7));
+    assert_eq!(address("11101,0,0,7,99", 0, 0), Some(3));
+    assert_eq!(address("21101,0,0,7,99", 0, 5), Some(12));
+    assert_eq!(address("203,-3,99", 0, 2), None);
+    assert_eq!(address("31101,0,0,7,99", 0, 0), None);
+    assert_eq!(address("4,0,3,5,99", 2, 0), Some(5));
+    assert_eq!(address("4,0,99", 0, 0), None);
+}
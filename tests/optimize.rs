@@ -0,0 +1,109 @@
+use machine::loader;
+use machine::optimize::{self, Optimized};
+use machine::Machine;
+
+const BUDGET: usize = 10_000_000;
+
+fn day(n: u32) -> Vec<i64> {
+    loader::load_file(format!("src/day{}/input.txt", n))
+        .unwrap()
+        .remove(0)
+}
+
+#[test]
+fn folds_constants_and_removes_dead_code() {
+    // Jumps over an output, then outputs the sum of the constants at 12 and 13.
+    let program = [1105, 1, 5, 104, 666, 1, 12, 13, 14, 4, 14, 99, 3, 4, 0];
+    let optimized = optimize::optimize(&program);
+    assert_eq!(
+        optimized.program,
+        vec![1105, 1, 5, 0, 0, 1101, 7, 0, 14, 4, 14, 99]
+    );
+    optimize::check(&program, &optimized, &[vec![]], BUDGET).unwrap();
+
+    let mut machine = Machine::new(&optimized.source()).unwrap();
+    let mut output = Vec::new();
+    machine.execute(&mut 0, &mut output);
+    assert_eq!(output, vec![7]);
+}
+
+#[test]
+fn threads_jumps() {
+    let program = [1105, 1, 3, 1105, 1, 6, 104, 1, 99];
+    let optimized = optimize::optimize(&program);
+    assert_eq!(optimized.program, vec![1105, 1, 6, 0, 0, 0, 104, 1, 99]);
+    assert_eq!(optimized.changed, vec![2, 3, 4, 5].into_iter().collect());
+}
+
+#[test]
+fn settles_constant_conditions() {
+    // The condition at 9 is never written, so the jump is never taken.
+    let program = [6, 9, 10, 104, 1, 99, 104, 2, 99, 1, 6];
+    let optimized = optimize::optimize(&program);
+    assert_eq!(optimized.program, vec![1106, 1, 6, 104, 1, 99]);
+    optimize::check(&program, &optimized, &[vec![]], BUDGET).unwrap();
+}
+
+#[test]
+fn leaves_data_cells_alone() {
+    let mut program = day(2);
+    program[1] = 12;
+    program[2] = 2;
+    let optimized = optimize::optimize(&program);
+    optimize::check(&program, &optimized, &[vec![]], BUDGET).unwrap();
+
+    let mut machine = Machine::from_memory(&optimized.program);
+    machine.execute(&mut 0, &mut 0);
+    assert_eq!(machine.read(0), 5482655);
+}
+
+#[test]
+fn leaves_dynamic_programs_alone() {
+    for n in &[5, 7, 9] {
+        let program = day(*n);
+        assert_eq!(optimize::analyze(&program), None);
+        let optimized = optimize::optimize(&program);
+        assert!(optimized.changed.is_empty());
+    }
+}
+
+#[test]
+fn check_finds_differences() {
+    let program = [3, 0, 4, 0, 99];
+    let wrong = Optimized {
+        program: vec![3, 0, 104, 0, 99],
+        changed: vec![2].into_iter().collect(),
+    };
+    assert!(optimize::check(&program, &wrong, &[vec![5]], BUDGET).is_err());
+
+    let same = optimize::optimize(&program);
+    optimize::check(&program, &same, &[vec![5], vec![-1]], BUDGET).unwrap();
+}
+
+#[test]
+fn survives_truncated_programs() {
+    let truncated: &[&[i64]] = &[
+        &[1105, 1],
+        &[1105],
+        &[1106, 0, 3, 1105],
+        &[1101, 1],
+        &[1, 0],
+        &[1001, 0, 2],
+        &[104],
+    ];
+    for program in truncated {
+        let optimized = optimize::optimize(program);
+        optimize::check(program, &optimized, &[vec![]], BUDGET).unwrap();
+    }
+}
+
+#[test]
+fn check_reports_why_a_program_failed() {
+    let program = [104, 1, 99];
+    let broken = Optimized {
+        program: vec![42],
+        changed: vec![0].into_iter().collect(),
+    };
+    let error = optimize::check(&program, &broken, &[vec![]], BUDGET).unwrap_err();
+    assert!(error.ends_with("failed: Unknown op code: 42"), "{}", error);
+}
@@ -0,0 +1,177 @@
+use machine::loader;
+use machine::record::{Event, Kind, Recorder, Replay, Session};
+use machine::Machine;
+use std::collections::VecDeque;
+use std::sync::mpsc::channel;
+use std::thread;
+
+fn day7() -> Vec<i64> {
+    loader::load_file("src/day7/input.txt").unwrap().remove(0)
+}
+
+/// Runs the Day 7 feedback loop with each amplifier on its own thread, and returns the final
+/// signal and the recorded session.
+fn record_feedback(phases: &[i64]) -> (i64, Session) {
+    let program = day7();
+    let recorder = Recorder::new();
+    let (txs, rxs): (Vec<_>, Vec<_>) = phases.iter().map(|_| channel()).unzip();
+    for (tx, phase) in txs.iter().zip(phases) {
+        tx.send(*phase).unwrap();
+    }
+    txs[0].send(0).unwrap();
+
+    let handles: Vec<_> = rxs
+        .into_iter()
+        .enumerate()
+        .map(|(i, rx)| {
+            let tx = txs[(i + 1) % phases.len()].clone();
+            let (mut input, mut output, mut clock) = recorder.attach(i, rx, tx);
+            let mut machine = Machine::from_memory(&program);
+            thread::spawn(move || machine.execute_observed(&mut input, &mut output, &mut clock))
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    // The signal is the last amplifier's final output.
+    let signal = recorder
+        .session()
+        .events
+        .iter()
+        .rev()
+        .find_map(|e| match e.kind {
+            Kind::Output(value) if e.machine == phases.len() - 1 => Some(value),
+            _ => None,
+        })
+        .unwrap();
+    (signal, recorder.session())
+}
+
+/// Replays a session with each machine on its own thread, and returns why it failed, if it did.
+fn replay(session: Session, machines: usize) -> Result<(), String> {
+    let program = day7();
+    let replay = Replay::new(session);
+    let handles: Vec<_> = (0..machines)
+        .map(|i| {
+            let (mut input, mut output, mut clock) = replay.attach(i);
+            let mut machine = Machine::from_memory(&program);
+            thread::spawn(move || machine.execute_observed(&mut input, &mut output, &mut clock))
+        })
+        .collect();
+
+    let panicked = handles.into_iter().any(|h| h.join().is_err());
+    match replay.finish() {
+        Ok(()) if panicked => Err("A machine panicked".to_string()),
+        result => result,
+    }
+}
+
+#[test]
+fn records_feedback_loop() {
+    let (signal, session) = record_feedback(&[7, 8, 6, 9, 5]);
+    assert_eq!(signal, 14365052);
+
+    // Each amplifier reads its phase before anything else, and halts last.
+    for machine in 0..5 {
+        let events = session.only(machine).events;
+        assert_eq!(events[0].steps, 0);
+        assert!(matches!(events[0].kind, Kind::Input(_)));
+        assert_eq!(events.last().unwrap().kind, Kind::Halt);
+        assert!(events.windows(2).all(|pair| pair[0].steps < pair[1].steps));
+    }
+}
+
+#[test]
+fn replays_feedback_loop() {
+    let (_, session) = record_feedback(&[9, 8, 7, 6, 5]);
+    let saved: Session = session.to_string().parse().unwrap();
+    assert_eq!(saved, session);
+    replay(saved, 5).unwrap();
+}
+
+#[test]
+fn replays_one_machine_alone() {
+    let (_, session) = record_feedback(&[5, 6, 7, 8, 9]);
+    let replay = Replay::new(session.only(2));
+    let mut machine = Machine::from_memory(&day7());
+    let (mut input, mut output, mut clock) = replay.attach(2);
+    machine.execute_observed(&mut input, &mut output, &mut clock);
+    replay.finish().unwrap();
+}
+
+#[test]
+fn detects_divergence() {
+    let (_, mut session) = record_feedback(&[9, 8, 7, 6, 5]);
+    let index = session
+        .events
+        .iter()
+        .position(|e| matches!(e.kind, Kind::Output(_)))
+        .unwrap();
+    if let Kind::Output(value) = session.events[index].kind {
+        session.events[index].kind = Kind::Output(value + 1);
+    }
+
+    let error = replay(session, 5).unwrap_err();
+    assert!(error.contains("but the session has"), "{}", error);
+}
+
+#[test]
+fn reports_machines_which_stop_early() {
+    let (_, session) = record_feedback(&[9, 8, 7, 6, 5]);
+    let replay = Replay::new(session);
+
+    // Machine 0 stops before reading its first input, while machine 1 waits for it.
+    let waiting = {
+        let (mut input, mut output, mut clock) = replay.attach(1);
+        let mut machine = Machine::from_memory(&day7());
+        thread::spawn(move || machine.execute_observed(&mut input, &mut output, &mut clock))
+    };
+    drop(replay.attach(0));
+
+    assert!(waiting.join().is_err());
+    let error = replay.finish().unwrap_err();
+    assert!(error.contains("but machine 0 stopped"), "{}", error);
+}
+
+#[test]
+fn detects_values_exchanged_at_other_steps() {
+    let (_, mut session) = record_feedback(&[9, 8, 7, 6, 5]);
+    let index = session
+        .events
+        .iter()
+        .position(|e| matches!(e.kind, Kind::Output(_)))
+        .unwrap();
+    session.events[index].steps += 1;
+
+    let error = replay(session, 5).unwrap_err();
+    assert!(error.contains("but the session has"), "{}", error);
+}
+
+#[test]
+fn records_step_counts() {
+    let recorder = Recorder::new();
+    let (mut input, mut output, mut clock) =
+        recorder.attach(3, VecDeque::from(vec![5]), Vec::new());
+    // Reads, adds, outputs and halts.
+    let mut machine = Machine::new("3,0,1101,0,0,1,4,0,99").unwrap();
+    machine.execute_observed(&mut input, &mut output, &mut clock);
+
+    assert_eq!(output.into_inner(), vec![5]);
+    assert_eq!(
+        recorder.session().to_string(),
+        "# intcode io session\n3 0 in 5\n3 2 out 5\n3 3 halt\n"
+    );
+    assert_eq!(
+        "3 2 out 5".parse::<Event>(),
+        Ok(Event {
+            machine: 3,
+            steps: 2,
+            kind: Kind::Output(5)
+        })
+    );
+    assert_eq!("3 3 halt".parse::<Event>().unwrap().kind, Kind::Halt);
+    assert!("3 3 halt 1".parse::<Event>().is_err());
+    assert!("3 1 jump 5".parse::<Event>().is_err());
+    assert!("intcode".parse::<Session>().is_err());
+}
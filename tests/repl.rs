@@ -0,0 +1,109 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Reads a value, outputs it plus one, and halts.
+const INCREMENT: &str = "3,9,1001,9,1,9,4,9,99,0";
+
+/// Writes a program to a temporary file and returns its path.
+fn program_file(name: &str, program: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("repl-{}-{}.txt", name, std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    path
+}
+
+/// Runs the REPL on a program with the given command lines, and returns what it printed to
+/// stdout and whether it exited successfully.
+fn session(name: &str, program: &str, lines: &[&str]) -> (String, bool) {
+    let path = program_file(name, program);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_repl"))
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    for line in lines {
+        writeln!(stdin, "{}", line).unwrap();
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(path).unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.success(),
+    )
+}
+
+#[test]
+fn runs_commands() {
+    let (output, success) = session(
+        "commands",
+        INCREMENT,
+        &[
+            "run", "input 41", "peek 9", "reset", "poke 9 7", "p 8 2", "regs", "quit", "run",
+        ],
+    );
+
+    assert!(success);
+    assert!(output.contains("Waiting for input at 0"));
+    assert!(output.contains("output: 42\nHalted"));
+    assert!(output.contains("     9: 42\n"));
+    assert!(output.contains("     8: 99 7\n"));
+    assert!(output.contains("ip: 0  base: 0\nnext: 3,9\n"));
+    assert_eq!(output.matches("Halted").count(), 1);
+}
+
+#[test]
+fn takes_bare_values_as_input() {
+    let (output, _) = session("values", INCREMENT, &["1", "regs"]);
+    assert!(output.contains("output: 2\nHalted"));
+    assert!(output.contains("ip: 8  base: 0"));
+}
+
+#[test]
+fn steps() {
+    let (output, _) = session("steps", INCREMENT, &["step", "step 2", "input 5", "step 5"]);
+    assert!(output.contains("error: The program is waiting for input"));
+    assert!(output.contains("output: 6\nHalted"));
+    assert!(output.contains("Halted\nintcode> Halted"));
+}
+
+#[test]
+fn reports_bad_commands() {
+    let (output, success) = session(
+        "errors",
+        INCREMENT,
+        &[
+            "jump 3",
+            "peek",
+            "peek x",
+            "poke 1",
+            "set sp 1",
+            "step 1 2",
+            "load",
+            "peek 18446744073709551615 2",
+        ],
+    );
+
+    assert!(success);
+    assert!(output.contains("error: Unknown command \"jump\"; try `help`"));
+    assert!(output.contains("error: Usage: peek <addr> [count]"));
+    assert!(output.contains("error: Bad value \"x\": invalid digit found in string"));
+    assert!(output.contains("error: Usage: poke <addr> <value>"));
+    assert!(output.contains("error: Usage: set ip|base <value>"));
+    assert!(output.contains("error: Usage: step [n]"));
+    assert!(output.contains("error: Usage: load <path> [index]"));
+    assert!(output.contains("error: 2 cells from 18446744073709551615 run past the end of memory"));
+}
+
+#[test]
+fn survives_faults() {
+    let (output, success) = session("fault", "1,0,0,0,98", &["run", "peek 0 5", "quit"]);
+    assert!(success);
+    assert!(output.contains("error: The program faulted: Unknown op code: 98"));
+    assert!(output.contains("     0: 2 0 0 0 98\n"));
+}
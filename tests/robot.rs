@@ -0,0 +1,68 @@
+use machine::robot::{Direction, Robot, BLACK, WHITE};
+use machine::Machine;
+
+/// Returns a brain which reads a panel's color before each of the given moves.
+fn scripted(moves: &[(i64, i64)]) -> Machine {
+    let mut program: Vec<String> = moves
+        .iter()
+        .map(|(color, turn)| format!("3,100,104,{},104,{}", color, turn))
+        .collect();
+    program.push("99".to_string());
+    Machine::new(&program.join(",")).unwrap()
+}
+
+#[test]
+fn paints_the_example_hull() {
+    let mut robot = Robot::new(scripted(&[
+        (1, 0),
+        (0, 0),
+        (1, 0),
+        (1, 0),
+        (0, 1),
+        (1, 0),
+        (1, 0),
+    ]));
+    robot.run();
+
+    assert!(robot.is_halted());
+    assert_eq!(robot.moves(), 7);
+    assert_eq!(robot.painted().len(), 6);
+    assert_eq!(
+        (robot.position(), robot.facing()),
+        ((0, -1), Direction::Left)
+    );
+    assert_eq!(robot.color((0, 0)), BLACK);
+    assert_eq!(robot.color((1, -1)), WHITE);
+    assert_eq!(robot.render(), "  #\n  #\n## \n");
+}
+
+#[test]
+fn brain_reads_the_panel_underneath() {
+    // Paints each panel the opposite of its color and turns right, forever.
+    let brain = Machine::new("3,100,1002,100,-1,100,101,1,100,100,4,100,104,1,1105,1,0").unwrap();
+    let mut robot = Robot::new(brain).start_on(WHITE);
+    assert!(robot.painted().is_empty());
+
+    assert!(!robot.run_for(4));
+    assert_eq!((robot.position(), robot.facing()), ((0, 0), Direction::Up));
+    assert_eq!(robot.color((0, 0)), BLACK);
+    assert_eq!(robot.hull().count(WHITE), 3);
+
+    assert!(robot.advance());
+    assert_eq!(robot.color((0, 0)), WHITE);
+    assert_eq!((robot.painted().len(), robot.moves()), (4, 5));
+
+    let image = robot.ppm(3);
+    let header = b"P6\n6 6\n255\n";
+    assert_eq!(&image[..header.len()], header);
+    assert_eq!(image.len(), header.len() + 6 * 6 * 3);
+}
+
+#[test]
+fn stops_when_the_brain_halts() {
+    let mut robot = Robot::new(Machine::new("104,1,99").unwrap());
+    assert!(robot.run_for(10));
+    assert!(!robot.advance());
+    assert_eq!(robot.moves(), 0);
+    assert!(robot.painted().is_empty());
+}
@@ -0,0 +1,128 @@
+use machine::search::{Candidate, PhaseSearch, PhaseSpace};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Reads the setting as the digits of a number, so that later values weigh less.
+fn digits(setting: &[i64]) -> i64 {
+    setting.iter().fold(0, |n, d| n * 10 + d)
+}
+
+#[test]
+fn spaces_rank_settings() {
+    let space = PhaseSpace::permutations(0..4);
+    assert_eq!(space.len(), 24);
+    assert_eq!(space.nth(0), vec![0, 1, 2, 3]);
+    assert_eq!(space.nth(23), vec![3, 2, 1, 0]);
+    for (rank, setting) in space.iter().enumerate() {
+        assert_eq!(space.nth(rank), setting);
+    }
+
+    let space = PhaseSpace::arrangements(vec![5, 7, 9], 2);
+    assert_eq!(space.len(), 6);
+    assert_eq!(
+        space.iter().collect::<Vec<_>>(),
+        vec![
+            vec![5, 7],
+            vec![5, 9],
+            vec![7, 5],
+            vec![7, 9],
+            vec![9, 5],
+            vec![9, 7]
+        ]
+    );
+
+    let space = PhaseSpace::with_repetition(0..3, 3);
+    assert_eq!(space.len(), 27);
+    assert_eq!(space.nth(5), vec![0, 1, 2]);
+    assert_eq!(space.iter().count(), 27);
+
+    let space = PhaseSpace::arrangements(0..2, 3);
+    assert!(space.is_empty());
+    assert_eq!(space.iter().count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Rank 6 is out of range")]
+fn rejects_ranks_out_of_range() {
+    PhaseSpace::permutations(0..3).nth(6);
+}
+
+#[test]
+fn finds_the_best_setting() {
+    let results = PhaseSearch::new(PhaseSpace::permutations(0..5)).run(digits);
+    assert_eq!(
+        results,
+        vec![Candidate {
+            setting: vec![4, 3, 2, 1, 0],
+            signal: 43210,
+        }]
+    );
+}
+
+#[test]
+fn reports_the_top_settings() {
+    let results = PhaseSearch::new(PhaseSpace::permutations(0..3))
+        .top(4)
+        .run(digits);
+    let settings: Vec<Vec<i64>> = results.into_iter().map(|c| c.setting).collect();
+    assert_eq!(
+        settings,
+        vec![vec![2, 1, 0], vec![2, 0, 1], vec![1, 2, 0], vec![1, 0, 2]]
+    );
+
+    // Ties go to the lower rank, however the work is split.
+    for workers in 1..5 {
+        let results = PhaseSearch::new(PhaseSpace::with_repetition(0..4, 4))
+            .workers(workers)
+            .top(3)
+            .run(|setting| setting.iter().sum());
+        let settings: Vec<Vec<i64>> = results.iter().map(|c| c.setting.clone()).collect();
+        assert_eq!(
+            settings,
+            vec![vec![3, 3, 3, 3], vec![2, 3, 3, 3], vec![3, 2, 3, 3]]
+        );
+    }
+
+    let results = PhaseSearch::new(PhaseSpace::permutations(0..3))
+        .top(10)
+        .run(digits);
+    assert_eq!(results.len(), 6);
+}
+
+#[test]
+fn evaluates_nothing_for_no_results() {
+    let evaluated = AtomicUsize::new(0);
+    let results = PhaseSearch::new(PhaseSpace::permutations(0..5))
+        .top(0)
+        .run(|setting| {
+            evaluated.fetch_add(1, Ordering::Relaxed);
+            digits(setting)
+        });
+
+    assert!(results.is_empty());
+    assert_eq!(evaluated.into_inner(), 0);
+}
+
+#[test]
+fn skips_pruned_prefixes() {
+    let evaluated = AtomicUsize::new(0);
+    let results = PhaseSearch::new(PhaseSpace::permutations(0..5))
+        .prune(|prefix| prefix[0] == 4 || prefix.ends_with(&[3, 2]))
+        .top(200)
+        .run(|setting| {
+            evaluated.fetch_add(1, Ordering::Relaxed);
+            digits(setting)
+        });
+
+    // 24 settings start with 4, and 18 more hold 3 then 2.
+    assert_eq!(evaluated.into_inner(), 120 - 24 - 18);
+    assert_eq!(results.len(), 78);
+    assert_eq!(results[0].setting, vec![3, 4, 2, 1, 0]);
+    assert!(results
+        .iter()
+        .all(|c| c.setting[0] != 4 && !c.setting.windows(2).any(|w| w == [3, 2])));
+
+    let results = PhaseSearch::new(PhaseSpace::permutations(0..3))
+        .prune(|_| true)
+        .run(digits);
+    assert!(results.is_empty());
+}
@@ -0,0 +1,114 @@
+use machine::solver::{Affine, Solver, Target};
+use machine::Machine;
+
+/// Outputs `3 * x + 2 * y + 1`, with `x` at 20 and `y` at 21.
+const LINEAR: &str = "1002,20,3,22,1002,21,2,23,1,22,23,22,1001,22,1,22,4,22,99,0,0,0,0,0";
+
+/// Outputs `x + 10` if `x`, at 20, is 2, and `x` otherwise. Over `0..=3` this matches `x` at
+/// either end and one past the start, so it cannot be told from affine by sampling those.
+const BUMP: &str = "1008,20,2,21,1002,21,10,21,1,20,21,21,4,21,99,0,0,0,0,0,0,0";
+
+fn day2() -> Machine {
+    Machine::new(&std::fs::read_to_string("src/day2/input.txt").unwrap()).unwrap()
+}
+
+#[test]
+fn models_day2() {
+    let machine = day2();
+    let solver = Solver::new(&machine).vary(1, 0..=99).vary(2, 0..=99);
+
+    assert_eq!(
+        solver.model(Target::Memory(0, 0)),
+        Some(Affine {
+            constant: 874653,
+            coefficients: vec![384000, 1],
+        })
+    );
+
+    let target = Target::Memory(0, 19690720);
+    assert_eq!(solver.solve(target), vec![vec![49, 67]]);
+    assert_eq!(solver.affine(true).solve(target), vec![vec![49, 67]]);
+}
+
+#[test]
+fn solves_affine_programs_directly() {
+    let machine = Machine::new(LINEAR).unwrap();
+    let solver = Solver::new(&machine).vary(20, 0..=5).vary(21, 0..=5);
+
+    assert_eq!(
+        solver.model(Target::Output(0, 0)),
+        Some(Affine {
+            constant: 1,
+            coefficients: vec![3, 2],
+        })
+    );
+
+    let target = Target::Output(0, 13);
+    let expected = vec![vec![2, 3], vec![4, 0]];
+    assert_eq!(solver.solve(target), expected);
+    assert_eq!(solver.workers(1).affine(true).solve(target), expected);
+}
+
+#[test]
+fn reports_every_solution_of_other_programs() {
+    let machine = Machine::new(BUMP).unwrap();
+    let solver = Solver::new(&machine).vary(20, 0..=3).affine(true);
+
+    assert_eq!(solver.model(Target::Output(0, 0)), None);
+    assert_eq!(solver.solve(Target::Output(0, 12)), vec![vec![2]]);
+    assert_eq!(solver.solve(Target::Output(0, 3)), vec![vec![3]]);
+
+    // Inputs are not part of the model.
+    let machine = Machine::new("3,7,1,7,8,9,99,0,0,0").unwrap();
+    let solver = Solver::new(&machine).vary(8, 0..=9).input(4).affine(true);
+    assert_eq!(solver.model(Target::Memory(9, 0)), None);
+    assert_eq!(solver.solve(Target::Memory(9, 10)), vec![vec![6]]);
+}
+
+#[test]
+fn gives_up_on_candidates_which_do_not_halt() {
+    // Jumps back to the start forever unless the variable is 0.
+    let machine = Machine::new("1105,0,0,99").unwrap();
+    let solutions = Solver::new(&machine)
+        .vary(1, 0..=3)
+        .budget(100)
+        .solve_with(|_, _| true);
+
+    assert_eq!(solutions, vec![vec![0]]);
+}
+
+#[test]
+fn skips_candidates_which_fault() {
+    let machine = Machine::new("0,0,0,0,99").unwrap();
+    let solutions = Solver::new(&machine).vary(0, 0..=2).solve_with(|_, _| true);
+
+    assert_eq!(solutions, vec![vec![1], vec![2]]);
+}
+
+#[test]
+fn does_not_model_constrained_paths() {
+    // Executes `1000000 * x + 99`, with `x` at 9, which halts whatever `x` is. Exploring binds
+    // `x` to 0, the only value giving a canonical instruction.
+    let machine = Machine::new("1002,9,1000000,8,1001,8,99,8,0,0").unwrap();
+    let solver = Solver::new(&machine).vary(9, 0..=2).affine(true);
+
+    assert_eq!(solver.model(Target::Memory(9, 0)), None);
+    assert_eq!(solver.solve(Target::Memory(9, 1)), vec![vec![1]]);
+}
+
+#[test]
+fn skips_assignments_which_overflow() {
+    let model = Affine {
+        constant: 1,
+        coefficients: vec![3, 2],
+    };
+    assert_eq!(model.eval(&[4, 0]), Some(13));
+    assert_eq!(model.eval(&[0, i64::MAX]), None);
+
+    let machine = Machine::new(LINEAR).unwrap();
+    let solver = Solver::new(&machine)
+        .vary(20, 0..=5)
+        .vary(21, i64::MAX - 1..=i64::MAX)
+        .affine(true);
+    assert_eq!(solver.solve(Target::Output(0, 13)), Vec::<Vec<i64>>::new());
+}
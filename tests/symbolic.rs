@@ -0,0 +1,92 @@
+use machine::symbolic::{End, Expr, Limits, Overflow, SymbolicMachine};
+use machine::Machine;
+
+fn load(day: u32) -> Machine {
+    let path = format!("src/day{}/input.txt", day);
+    Machine::new(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn keeps_expressions_canonical() {
+    let (a, b) = (Expr::symbol("a"), Expr::symbol("b"));
+    assert_eq!(a.add(&b), b.add(&a));
+    assert_eq!(a.mul(&b).unwrap().to_string(), "a*b");
+
+    let doubled = a.add(&a).unwrap().add(&Expr::constant(-3)).unwrap();
+    assert_eq!(doubled.to_string(), "2*a - 3");
+    assert_eq!(doubled.substitute("a", 5).unwrap(), Expr::constant(7));
+    assert_eq!(
+        a.add(&a.mul(&Expr::constant(-1)).unwrap()),
+        Ok(Expr::constant(0))
+    );
+}
+
+#[test]
+fn reports_overflow() {
+    let max = Expr::constant(i64::MAX);
+    assert_eq!(max.add(&Expr::constant(1)), Err(Overflow));
+    assert_eq!(max.mul(&Expr::constant(2)), Err(Overflow));
+    assert_eq!(
+        Expr::symbol("a").mul(&max).unwrap().substitute("a", 2),
+        Err(Overflow)
+    );
+
+    let program = format!("1101,{},1,0,4,0,99", i64::MAX);
+    let paths = SymbolicMachine::new(&Machine::new(&program).unwrap()).explore(Limits::default());
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].end, End::Overflow(0));
+}
+
+#[test]
+fn shows_day2_as_a_formula() {
+    let mut machine = SymbolicMachine::new(&load(2));
+    machine.set_symbol(1, "noun");
+    machine.set_symbol(2, "verb");
+
+    let paths = machine.explore(Limits::default());
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].end, End::Halted);
+    assert!(paths[0].constraints.is_empty());
+    assert_eq!(paths[0].read(0).to_string(), "384000*noun + verb + 874653");
+}
+
+#[test]
+fn enumerates_day5_paths() {
+    let paths = SymbolicMachine::new(&load(5)).explore(Limits::default());
+
+    // The system ID is added to an opcode, so each path binds it to one value.
+    let code = |id: i64| {
+        let path = paths
+            .iter()
+            .find(|path| path.constraints[0].to_string() == format!("input0 == {}", id))
+            .unwrap();
+        (path.end, path.outputs.last().map(|c| c.to_string()))
+    };
+
+    assert_eq!(code(1), (End::Halted, Some(String::from("3122865"))));
+    assert_eq!(code(5), (End::Halted, Some(String::from("773660"))));
+    assert_eq!(code(6), (End::Fault(9), None));
+
+    assert!(paths.len() > 100);
+    assert!(paths.iter().all(|path| path.constraints.len() == 1));
+    assert!(paths
+        .iter()
+        .all(|path| matches!(path.end, End::Halted | End::Fault(_))));
+}
+
+#[test]
+fn binds_opcodes_to_canonical_instructions() {
+    let mut machine = SymbolicMachine::new(&Machine::new("0,0,0,0").unwrap());
+    machine.set_symbol(0, "x");
+    let bound: Vec<String> = machine
+        .explore(Limits::default())
+        .iter()
+        .map(|path| path.constraints[0].to_string())
+        .collect();
+
+    // Every opcode with each valid mode for each of its parameters, but no higher digits.
+    assert_eq!(bound.len(), 4 * 27 + 2 * 9 + 3 * 3 + 1);
+    assert!(bound.contains(&String::from("x == 22208")));
+    assert!(bound.contains(&String::from("x == 99")));
+    assert!(!bound.contains(&String::from("x == 199")));
+}
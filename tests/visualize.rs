@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Writes a program to a temporary file and returns its path.
+fn program_file(name: &str, program: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("visualize-{}-{}.txt", name, std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    path
+}
+
+/// Runs the visualizer without a terminal, so that it runs until the program stops, and returns
+/// its last frame with colors removed, along with what it printed to stderr and whether it
+/// succeeded.
+fn visualize(name: &str, program: &str, args: &[&str]) -> (String, String, bool) {
+    let path = program_file(name, program);
+    let output = Command::new(env!("CARGO_BIN_EXE_visualize"))
+        .arg(&path)
+        .args(["--delay", "0"])
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let last = stdout.rsplit("\x1b[H").next().unwrap();
+    let mut frame = String::new();
+    let mut escape = false;
+    for c in last.chars() {
+        match c {
+            '\x1b' => escape = true,
+            c if escape => escape = !c.is_ascii_alphabetic(),
+            c => frame.push(c),
+        }
+    }
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    (frame, stderr, output.status.success())
+}
+
+#[test]
+fn runs_until_halted() {
+    let (frame, _, success) = visualize(
+        "halt",
+        "3,9,1001,9,1,9,4,9,99,0",
+        &["-i", "41", "--columns", "5", "--rows", "2"],
+    );
+
+    assert!(success);
+    assert!(frame.starts_with("ip 8      base 0      step 4        delay 0ms  [halted]"));
+    assert!(frame.contains("    10 |    0    0    0    0    0"));
+    assert!(frame.contains("     5 |    9    4    9   99   42"));
+    assert!(frame.contains("input  41"));
+    assert!(frame.contains("output 42"));
+}
+
+#[test]
+fn shows_faults() {
+    let (frame, _, success) = visualize("fault", "1,0,0,0,98", &[]);
+    assert!(success);
+    assert!(frame.contains("[faulted: Unknown op code: 98]"));
+}
+
+#[test]
+fn stops_when_input_runs_out() {
+    let (frame, _, success) = visualize("input", "3,0,3,0,99", &["-i", "7"]);
+    assert!(success);
+    assert!(frame.starts_with("ip 2 "));
+    assert!(frame.contains("input  7"));
+}
+
+#[test]
+fn rejects_bad_arguments() {
+    let (_, stderr, success) = visualize("args", "99", &["--rows"]);
+    assert!(!success);
+    assert_eq!(stderr, "visualize: Missing value for --rows\n");
+
+    let (_, stderr, success) = visualize("args", "99", &["--speed", "2"]);
+    assert!(!success);
+    assert!(stderr.starts_with("visualize: Unknown option --speed\n\nUsage: visualize"));
+
+    let (_, stderr, success) = visualize("args", "99", &["-i", "1,x"]);
+    assert!(!success);
+    assert_eq!(stderr, "visualize: invalid digit found in string\n");
+}